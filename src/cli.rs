@@ -0,0 +1,239 @@
+use std::{ops::ControlFlow, path::PathBuf};
+
+use chrono::Local;
+use gtk::{
+    gio::{self, prelude::*},
+    glib,
+};
+use libadwaita as adw;
+
+use crate::service::{
+    cliboard_history::{ClipboardHistory, IClipboardHistory},
+    cliboard_provider::{self, BoxedClipboardProvider, IClipboardProvider},
+    config_service::ConfigService,
+    history_transfer::{self, ExportFilter, ExportFormat, ExportOptions, ImageMode},
+    recording_state::RecordingState,
+};
+
+/// What the window should load on top of the regular history.
+#[derive(Default)]
+pub struct StartupOptions {
+    pub import: Option<PathBuf>,
+    pub import_from: Option<BoxedClipboardProvider>,
+    pub recording: RecordingState,
+    /// Open as a popup regardless of the configuration.
+    pub popup: bool,
+}
+
+pub fn register_options(app: &adw::Application) {
+    app.add_main_option(
+        "export",
+        glib::Char::from(b'e'),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::Filename,
+        "Export the clipboard history to FILE and exit",
+        Some("FILE"),
+    );
+    app.add_main_option(
+        "import",
+        glib::Char::from(b'i'),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::Filename,
+        "Merge the history stored in FILE on startup",
+        Some("FILE"),
+    );
+    app.add_main_option(
+        "import-from",
+        glib::Char::from(0),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::String,
+        "Load the history of another clipboard manager (clipman, copyq, gpaste or greenclip), optionally from PATH",
+        Some("NAME[=PATH]"),
+    );
+    app.add_main_option(
+        "format",
+        glib::Char::from(0),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::String,
+        "Export format: json, text or csv (default: from the file extension)",
+        Some("FORMAT"),
+    );
+    app.add_main_option(
+        "filter",
+        glib::Char::from(0),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::String,
+        "Only export entries containing TEXT",
+        Some("TEXT"),
+    );
+    app.add_main_option(
+        "pinned-only",
+        glib::Char::from(0),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        "Only export pinned entries",
+        None,
+    );
+    app.add_main_option(
+        "images-dir",
+        glib::Char::from(0),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::Filename,
+        "Write exported images into DIR instead of embedding them",
+        Some("DIR"),
+    );
+    app.add_main_option(
+        "pause",
+        glib::Char::from(b'p'),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::Int,
+        "Pause recording for MINUTES, 0 until resumed",
+        Some("MINUTES"),
+    );
+    app.add_main_option(
+        "popup",
+        glib::Char::from(0),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        "Open as a small popup near the pointer",
+        None,
+    );
+    app.add_main_option(
+        "resume",
+        glib::Char::from(0),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        "Resume recording",
+        None,
+    );
+}
+
+/// Handles the options that do not need a window. Returns what the window
+/// should load when the application keeps starting up.
+pub fn handle_options(
+    app: &adw::Application,
+    options: &glib::VariantDict,
+) -> ControlFlow<glib::ExitCode, StartupOptions> {
+    if let Some(path) = lookup_path(options, "export") {
+        return ControlFlow::Break(match export(options, path) {
+            Ok(()) => glib::ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("Export failed: {err}");
+                glib::ExitCode::FAILURE
+            }
+        });
+    }
+
+    let pause = match options.lookup::<i32>("pause").ok().flatten() {
+        Some(minutes) if minutes < 0 => {
+            eprintln!("--pause expects a number of minutes");
+            return ControlFlow::Break(glib::ExitCode::FAILURE);
+        }
+        minutes => minutes.map(|minutes| minutes as u32),
+    };
+    let resume = options.contains("resume");
+
+    if (pause.is_some() || resume) && forward_recording_command(app, pause)? {
+        return ControlFlow::Break(glib::ExitCode::SUCCESS);
+    }
+
+    if resume && pause.is_none() {
+        eprintln!("Clipboard Manager is not running; nothing to resume");
+        return ControlFlow::Break(glib::ExitCode::SUCCESS);
+    }
+
+    let import_from =
+        match lookup_string(options, "import-from").map(|value| parse_importer(&value)) {
+            Some(Ok(provider)) => Some(provider),
+            Some(Err(err)) => {
+                eprintln!("{err}");
+                return ControlFlow::Break(glib::ExitCode::FAILURE);
+            }
+            None => None,
+        };
+
+    ControlFlow::Continue(StartupOptions {
+        import: lookup_path(options, "import"),
+        import_from,
+        recording: pause
+            .map(|minutes| RecordingState::paused_for(minutes, Local::now()))
+            .unwrap_or_default(),
+        popup: options.contains("popup"),
+    })
+}
+
+/// Sends a pause or resume to the running instance over D-Bus. Returns
+/// false when this process is the first instance and should start up.
+fn forward_recording_command(
+    app: &adw::Application,
+    pause: Option<u32>,
+) -> ControlFlow<glib::ExitCode, bool> {
+    if let Err(err) = app.register(None::<&gio::Cancellable>) {
+        eprintln!("Could not register the application: {err}");
+        return ControlFlow::Break(glib::ExitCode::FAILURE);
+    }
+
+    if !app.is_remote() {
+        return ControlFlow::Continue(false);
+    }
+
+    match pause {
+        Some(minutes) => app.activate_action("pause-recording", Some(&minutes.to_variant())),
+        None => app.activate_action("resume-recording", None),
+    }
+
+    ControlFlow::Continue(true)
+}
+
+fn parse_importer(value: &str) -> Result<BoxedClipboardProvider, String> {
+    match value.split_once('=') {
+        Some((name, path)) => cliboard_provider::provider_for(name, Some(PathBuf::from(path))),
+        None => cliboard_provider::provider_for(value, None),
+    }
+}
+
+fn export(options: &glib::VariantDict, path: PathBuf) -> Result<(), String> {
+    let format = match lookup_string(options, "format") {
+        Some(name) => {
+            ExportFormat::from_name(&name).ok_or_else(|| format!("Unknown format: {name}"))?
+        }
+        None => ExportFormat::from_path(&path),
+    };
+
+    let images = match lookup_path(options, "images-dir") {
+        Some(dir) => ImageMode::Sidecar(dir),
+        None => ImageMode::Embedded,
+    };
+
+    let export_options = ExportOptions {
+        format,
+        filter: ExportFilter {
+            query: lookup_string(options, "filter"),
+            pinned_only: options.contains("pinned-only"),
+        },
+        images,
+    };
+
+    // The same sources as the window; pinned entries live in the native
+    // store, not in cliphist.
+    let mut history = ClipboardHistory::new();
+    for provider in ConfigService::new().load().enabled_providers() {
+        match provider.list_entries() {
+            Ok(entries) => history.add_provider_entries(provider.name(), entries),
+            Err(err) => eprintln!("Skipping {}: {err}", provider.name()),
+        }
+    }
+
+    let count = history_transfer::export_to_file(history.entries(), &path, &export_options)?;
+    println!("Exported {count} entries to {}", path.display());
+
+    Ok(())
+}
+
+fn lookup_path(options: &glib::VariantDict, key: &str) -> Option<PathBuf> {
+    options.lookup::<PathBuf>(key).ok().flatten()
+}
+
+fn lookup_string(options: &glib::VariantDict, key: &str) -> Option<String> {
+    options.lookup::<String>(key).ok().flatten()
+}
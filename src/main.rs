@@ -1,18 +1,42 @@
+mod cli;
 mod service;
 pub mod ui;
 
-use gtk::gio::prelude::{ApplicationExt, ApplicationExtManual};
+use std::{cell::RefCell, rc::Rc};
+
+use gtk::{
+    gio::prelude::{ApplicationExt, ApplicationExtManual},
+    prelude::{GtkApplicationExt, GtkWindowExt},
+};
 use libadwaita as adw;
 
 const APP_ID: &str = "org.example.clipmanager";
 
 fn main() {
     let app = adw::Application::builder().application_id(APP_ID).build();
+    let startup_options = Rc::new(RefCell::new(cli::StartupOptions::default()));
+
+    cli::register_options(&app);
+
+    app.connect_handle_local_options({
+        let startup_options = startup_options.clone();
+        move |app, options| {
+            *startup_options.borrow_mut() = cli::handle_options(app, options)?;
+            std::ops::ControlFlow::Continue(())
+        }
+    });
 
     app.connect_activate(move |app| {
+        // A window hidden after copying is shown again instead of building
+        // a second one.
+        if let Some(window) = app.windows().first() {
+            window.present();
+            return;
+        }
+
         let display = gtk::gdk::Display::default().expect("Could not get the default display");
 
-        ui::build_ui(app, &display);
+        ui::build_ui(app, &display, startup_options.take());
     });
 
     app.run();
@@ -0,0 +1,231 @@
+use std::{env, path::Path, process::Command, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+/// The key combination that pastes in the target window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PasteKeys {
+    #[default]
+    CtrlV,
+    /// Pastes in terminals, where Ctrl+V is taken.
+    ShiftInsert,
+}
+
+impl PasteKeys {
+    pub const ALL: [Self; 2] = [Self::CtrlV, Self::ShiftInsert];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::CtrlV => "ctrl-v",
+            Self::ShiftInsert => "shift-insert",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|keys| keys.name() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::CtrlV => "Ctrl+V",
+            Self::ShiftInsert => "Shift+Insert (Terminals)",
+        }
+    }
+}
+
+/// The tool used to type the paste keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PasteBackend {
+    /// wtype on Wayland, xdotool on X11, ydotool as the last resort.
+    #[default]
+    Auto,
+    Wtype,
+    Ydotool,
+    Xdotool,
+}
+
+impl PasteBackend {
+    fn program(self) -> Option<&'static str> {
+        match self {
+            Self::Auto => None,
+            Self::Wtype => Some("wtype"),
+            Self::Ydotool => Some("ydotool"),
+            Self::Xdotool => Some("xdotool"),
+        }
+    }
+
+    /// Picks a backend for the session. `is_installed` tells whether a
+    /// program is on the `PATH`.
+    pub fn detect(wayland: bool, is_installed: impl Fn(&str) -> bool) -> Option<Self> {
+        let candidates: &[Self] = if wayland {
+            &[Self::Wtype, Self::Ydotool]
+        } else {
+            &[Self::Xdotool, Self::Ydotool]
+        };
+
+        candidates
+            .iter()
+            .copied()
+            .find(|backend| backend.program().is_some_and(&is_installed))
+    }
+
+    /// The arguments that type `keys`.
+    fn args(self, keys: PasteKeys) -> Vec<&'static str> {
+        match (self, keys) {
+            (Self::Wtype, PasteKeys::CtrlV) => vec!["-M", "ctrl", "-k", "v", "-m", "ctrl"],
+            (Self::Wtype, PasteKeys::ShiftInsert) => {
+                vec!["-M", "shift", "-k", "Insert", "-m", "shift"]
+            }
+            // Linux input event codes: 29 left Ctrl, 47 V, 42 left Shift,
+            // 110 Insert.
+            (Self::Ydotool, PasteKeys::CtrlV) => vec!["key", "29:1", "47:1", "47:0", "29:0"],
+            (Self::Ydotool, PasteKeys::ShiftInsert) => {
+                vec!["key", "42:1", "110:1", "110:0", "42:0"]
+            }
+            (Self::Xdotool, PasteKeys::CtrlV) => vec!["key", "--clearmodifiers", "ctrl+v"],
+            (Self::Xdotool, PasteKeys::ShiftInsert) => {
+                vec!["key", "--clearmodifiers", "shift+Insert"]
+            }
+            (Self::Auto, _) => Vec::new(),
+        }
+    }
+}
+
+/// Types key combinations into the focused window.
+pub trait IVirtualKeyboard: Send + Sync {
+    fn send(&self, keys: PasteKeys) -> Result<(), String>;
+}
+
+/// Runs one of the command line virtual keyboard tools.
+pub struct CommandKeyboard {
+    backend: PasteBackend,
+}
+
+impl CommandKeyboard {
+    pub fn new(backend: PasteBackend) -> Self {
+        Self { backend }
+    }
+
+    /// Resolves `Auto` to an installed backend for the current session.
+    /// Looked up on every paste, so a tool installed meanwhile is found.
+    fn resolve(&self) -> Result<PasteBackend, String> {
+        match self.backend {
+            PasteBackend::Auto => {
+                let wayland = env::var_os("WAYLAND_DISPLAY").is_some();
+                PasteBackend::detect(wayland, is_installed).ok_or_else(|| {
+                    "No virtual keyboard tool found; install wtype, ydotool or xdotool".to_string()
+                })
+            }
+            backend => Ok(backend),
+        }
+    }
+}
+
+impl IVirtualKeyboard for CommandKeyboard {
+    fn send(&self, keys: PasteKeys) -> Result<(), String> {
+        let backend = self.resolve()?;
+        let program = backend.program().unwrap_or_default();
+        let status = Command::new(program)
+            .args(backend.args(keys))
+            .status()
+            .map_err(|err| format!("Failed to run {program}: {err}"))?;
+
+        if !status.success() {
+            return Err(format!("{program} exited with {status}"));
+        }
+
+        Ok(())
+    }
+}
+
+fn is_installed(program: &str) -> bool {
+    env::var_os("PATH").is_some_and(|paths| {
+        env::split_paths(&paths).any(|dir| Path::new(&dir).join(program).is_file())
+    })
+}
+
+/// Pastes into the window that had the focus before ours was hidden.
+#[derive(Clone)]
+pub struct AutoPaster {
+    keyboard: Arc<dyn IVirtualKeyboard>,
+    keys: PasteKeys,
+}
+
+impl AutoPaster {
+    pub fn new(keyboard: Arc<dyn IVirtualKeyboard>, keys: PasteKeys) -> Self {
+        Self { keyboard, keys }
+    }
+
+    /// Sends the paste keys. Blocks until the backend has typed them.
+    pub fn paste(&self) -> Result<(), String> {
+        self.keyboard
+            .send(self.keys)
+            .map_err(|err| format!("Auto-paste failed: {err}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct FakeKeyboard {
+        sent: Mutex<Vec<PasteKeys>>,
+        fail: bool,
+    }
+
+    impl IVirtualKeyboard for FakeKeyboard {
+        fn send(&self, keys: PasteKeys) -> Result<(), String> {
+            if self.fail {
+                return Err("no seat".to_string());
+            }
+            self.sent.lock().unwrap().push(keys);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn sends_the_configured_keys() {
+        let keyboard = Arc::new(FakeKeyboard::default());
+        let paster = AutoPaster::new(keyboard.clone(), PasteKeys::ShiftInsert);
+
+        paster.paste().unwrap();
+        assert_eq!(*keyboard.sent.lock().unwrap(), [PasteKeys::ShiftInsert]);
+
+        let failing = AutoPaster::new(
+            Arc::new(FakeKeyboard {
+                fail: true,
+                ..Default::default()
+            }),
+            PasteKeys::CtrlV,
+        );
+        assert_eq!(failing.paste().unwrap_err(), "Auto-paste failed: no seat");
+    }
+
+    #[test]
+    fn detects_backend_for_the_session() {
+        let only = |installed: &'static str| move |program: &str| program == installed;
+
+        assert_eq!(
+            PasteBackend::detect(true, |_| true),
+            Some(PasteBackend::Wtype)
+        );
+        assert_eq!(
+            PasteBackend::detect(true, only("ydotool")),
+            Some(PasteBackend::Ydotool)
+        );
+        assert_eq!(
+            PasteBackend::detect(false, |_| true),
+            Some(PasteBackend::Xdotool)
+        );
+        assert_eq!(PasteBackend::detect(false, only("wtype")), None);
+        assert_eq!(
+            PasteBackend::Wtype.args(PasteKeys::CtrlV),
+            ["-M", "ctrl", "-k", "v", "-m", "ctrl"]
+        );
+    }
+}
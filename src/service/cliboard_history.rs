@@ -1,9 +1,38 @@
-use chrono::{DateTime, Local};
+use std::{
+    collections::HashSet,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use chrono::{DateTime, Duration, Local};
+
+use crate::service::{
+    cliboard_provider::ProviderEntry,
+    file_list, rich_text,
+    sensitivity::{self, SensitiveKind},
+    time_format::{IClock, Language, TimeStyle, format_timestamp},
+};
 
 #[derive(Debug, Clone)]
 pub enum ClipboardContent {
     Text(String),
-    Image(()),
+    Image(Option<Arc<[u8]>>),
+    /// Formatted text as offered by browsers and office suites, with the plain
+    /// text fallback offered next to it. RTF is kept as read, under the MIME
+    /// type it was offered as; it is not necessarily UTF-8.
+    Rich {
+        plain: String,
+        html: Option<String>,
+        rtf: Option<ClipboardFormat>,
+    },
+    /// Copied files as URIs. `operation` is the `copy` or `cut` of
+    /// `x-special/gnome-copied-files`, when that format was offered.
+    Files {
+        uris: Vec<String>,
+        operation: Option<String>,
+    },
 }
 
 impl ClipboardContent {
@@ -11,61 +40,216 @@ impl ClipboardContent {
         match self {
             Self::Text(text) => text.clone(),
             Self::Image(_) => "[Image]".to_string(),
+            Self::Rich { plain, .. } => plain.clone(),
+            Self::Files { uris, .. } => uris.join("\n"),
         }
     }
 
     pub fn is_image(&self) -> bool {
         matches!(self, Self::Image(_))
     }
+
+    /// Whether `format` can be offered again from the content alone, so
+    /// keeping it would store the same data twice.
+    pub fn covers(&self, format: &ClipboardFormat) -> bool {
+        let mime = format.mime_type.as_str();
+        let same = |text: &str| *format.data == *text.as_bytes();
+
+        match self {
+            Self::Image(_) => mime.starts_with("image/"),
+            Self::Text(text) => mime.starts_with("text/plain") && same(text),
+            Self::Rich { plain, html, rtf } => {
+                (mime.starts_with("text/plain") && same(plain))
+                    || (mime == rich_text::HTML_MIME && html.as_deref().is_some_and(same))
+                    || rtf.as_ref() == Some(format)
+            }
+            Self::Files { .. } => {
+                mime == file_list::URI_LIST_MIME || mime == file_list::GNOME_COPIED_FILES_MIME
+            }
+        }
+    }
+
+    /// Plain or formatted text, including links and code.
+    pub fn is_text(&self) -> bool {
+        matches!(self, Self::Text(_) | Self::Rich { .. })
+    }
+
+    pub fn is_files(&self) -> bool {
+        matches!(self, Self::Files { .. })
+    }
+
+    /// Text that is a single web or mail address.
+    pub fn is_link(&self) -> bool {
+        self.plain_text()
+            .is_some_and(|text| looks_like_link(text.trim()))
+    }
+
+    /// Text that reads like source code rather than prose.
+    pub fn is_code(&self) -> bool {
+        self.plain_text().is_some_and(looks_like_code)
+    }
+
+    fn plain_text(&self) -> Option<&str> {
+        match self {
+            Self::Text(text) | Self::Rich { plain: text, .. } => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn image_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Image(Some(bytes)) => Some(bytes),
+            _ => None,
+        }
+    }
+}
+
+fn looks_like_link(text: &str) -> bool {
+    const PREFIXES: [&str; 5] = ["http://", "https://", "ftp://", "mailto:", "www."];
+
+    !text.is_empty()
+        && !text.contains(char::is_whitespace)
+        && PREFIXES
+            .iter()
+            .any(|prefix| text.len() > prefix.len() && text.starts_with(prefix))
+}
+
+/// At least a third of the lines end like statements or blocks, or start
+/// with a common keyword.
+fn looks_like_code(text: &str) -> bool {
+    const KEYWORDS: [&str; 14] = [
+        "fn ",
+        "def ",
+        "function ",
+        "class ",
+        "import ",
+        "from ",
+        "#include",
+        "let ",
+        "const ",
+        "var ",
+        "pub ",
+        "return ",
+        "SELECT ",
+        "</",
+    ];
+
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let markers = lines
+        .iter()
+        .filter(|line| {
+            line.ends_with([';', '{', '}'])
+                || KEYWORDS.iter().any(|keyword| line.starts_with(keyword))
+        })
+        .count();
+
+    markers > 0 && markers * 3 >= lines.len()
+}
+
+/// One representation of a copy, as offered by the application it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardFormat {
+    pub mime_type: String,
+    pub data: Arc<[u8]>,
+}
+
+impl ClipboardFormat {
+    pub fn new(mime_type: &str, data: &[u8]) -> Self {
+        Self {
+            mime_type: mime_type.to_string(),
+            data: Arc::from(data),
+        }
+    }
 }
 
+/// Identifies an entry for the lifetime of the process. Unlike `id`, every
+/// entry has one, whichever provider it came from.
+pub type EntryKey = u64;
+
+static NEXT_KEY: AtomicU64 = AtomicU64::new(1);
+
 #[derive(Debug, Clone)]
 pub struct ClipboardEntry {
+    pub key: EntryKey,
     pub content: ClipboardContent,
     pub timestamp: DateTime<Local>,
+    /// The timestamp was made up because the provider does not record when
+    /// the entry was copied; it only keeps the entry in order.
+    pub estimated_time: bool,
     pub source: String,
     pub id: Option<String>,
+    pub pinned: bool,
+    pub tags: Vec<String>,
+    pub sensitivity: Option<SensitiveKind>,
+    /// Every format offered when the entry was copied; empty when only
+    /// `content` is known.
+    pub formats: Vec<ClipboardFormat>,
+    /// How often the entry was copied from the history.
+    pub copy_count: u32,
+    /// When the entry was last copied from the history.
+    pub last_used: Option<DateTime<Local>>,
 }
 
 pub trait IClipboardEntry {
     fn new(content: ClipboardContent, source: Option<String>, id: Option<String>) -> Self;
-    fn format_time(&self) -> String;
+    fn format_time(&self, clock: &dyn IClock, style: TimeStyle) -> String;
+    fn is_duplicate_of(&self, other: &Self) -> bool;
 }
 
 impl IClipboardEntry for ClipboardEntry {
     fn new(content: ClipboardContent, source: Option<String>, id: Option<String>) -> Self {
         Self {
+            key: NEXT_KEY.fetch_add(1, Ordering::Relaxed),
+            sensitivity: sensitivity::analyze(&content),
             content,
             timestamp: Local::now(),
+            estimated_time: false,
             source: source.unwrap_or_else(|| "Unknown".to_string()),
             id,
+            pinned: false,
+            tags: Vec::new(),
+            formats: Vec::new(),
+            copy_count: 0,
+            last_used: None,
         }
     }
 
-    fn format_time(&self) -> String {
-        let now = Local::now();
-        let duration = now.signed_duration_since(self.timestamp);
-
-        if duration.num_seconds() < 60 {
-            "Just Now".to_string()
-        } else if duration.num_minutes() < 60 {
-            format!(
-                "{} minute{} ago",
-                duration.num_minutes(),
-                if duration.num_minutes() == 1 { "" } else { "s" }
-            )
-        } else if duration.num_hours() < 24 {
-            format!(
-                "{} hour{} ago",
-                duration.num_hours(),
-                if duration.num_hours() == 1 { "" } else { "s" }
-            )
+    fn format_time(&self, clock: &dyn IClock, style: TimeStyle) -> String {
+        let time = format_timestamp(self.timestamp, clock.now(), style, Language::current());
+        if self.estimated_time {
+            format!("~{time}")
         } else {
-            format!(
-                "{} day{} ago",
-                duration.num_days(),
-                if duration.num_days() == 1 { "" } else { "s" }
-            )
+            time
+        }
+    }
+
+    fn is_duplicate_of(&self, other: &Self) -> bool {
+        match (&self.content, &other.content) {
+            (ClipboardContent::Text(a), ClipboardContent::Text(b)) => a == b,
+            (
+                ClipboardContent::Rich {
+                    plain: a,
+                    html: a_html,
+                    rtf: a_rtf,
+                },
+                ClipboardContent::Rich {
+                    plain: b,
+                    html: b_html,
+                    rtf: b_rtf,
+                },
+            ) => a == b && a_html == b_html && a_rtf == b_rtf,
+            (ClipboardContent::Files { uris: a, .. }, ClipboardContent::Files { uris: b, .. }) => {
+                a == b
+            }
+            (ClipboardContent::Image(Some(a)), ClipboardContent::Image(Some(b))) => a == b,
+            (ClipboardContent::Image(_), ClipboardContent::Image(_)) => {
+                self.id.is_some() && self.id == other.id && self.source == other.source
+            }
+            _ => false,
         }
     }
 }
@@ -73,6 +257,9 @@ impl IClipboardEntry for ClipboardEntry {
 pub struct ClipboardHistory {
     entries: Vec<ClipboardEntry>,
     max_entries: usize,
+    /// The entry last seen on the clipboard. Kept as a copy so it can be
+    /// found again after the history is reloaded with new keys.
+    current: Option<ClipboardEntry>,
 }
 
 pub trait IClipboardHistory {
@@ -82,8 +269,18 @@ pub trait IClipboardHistory {
         content: ClipboardContent,
         source: String,
         id: Option<String>,
+        formats: Vec<ClipboardFormat>,
     );
+    fn add_provider_entries(&mut self, source: &str, entries: Vec<ProviderEntry>);
+    fn merge_entries(&mut self, entries: Vec<ClipboardEntry>) -> usize;
+    fn clear(&mut self);
+    fn retain_source(&mut self, source: &str);
+    fn expire_sensitive(&mut self, now: DateTime<Local>, max_age: Duration) -> Vec<ClipboardEntry>;
     fn entries(&self) -> &[ClipboardEntry];
+    fn mark_copied(&mut self, key: EntryKey) -> bool;
+    fn remove(&mut self, key: EntryKey) -> Option<ClipboardEntry>;
+    fn toggle_pinned(&mut self, key: EntryKey) -> Option<bool>;
+    fn current_key(&self) -> Option<EntryKey>;
 }
 
 impl IClipboardHistory for ClipboardHistory {
@@ -91,6 +288,7 @@ impl IClipboardHistory for ClipboardHistory {
         Self {
             entries: Vec::new(),
             max_entries: 100,
+            current: None,
         }
     }
 
@@ -99,23 +297,376 @@ impl IClipboardHistory for ClipboardHistory {
         content: ClipboardContent,
         source: String,
         id: Option<String>,
+        formats: Vec<ClipboardFormat>,
     ) {
-        if let Some(last) = self.entries.first() {
-            match (&last.content, &content) {
-                (ClipboardContent::Text(a), ClipboardContent::Text(b)) if a == b => return,
-                _ => {}
-            }
+        let mut entry = ClipboardEntry::new(content, Some(source), id);
+        entry.formats = formats;
+
+        // The application's own copy coming back, e.g. after another
+        // clipboard manager took ownership of it.
+        if let Some(current) = &self.current
+            && current.is_duplicate_of(&entry)
+            && self.move_to_top(current.key).is_some()
+        {
+            return;
+        }
+
+        if let Some(last) = self.entries.first()
+            && !last.content.is_image()
+            && last.is_duplicate_of(&entry)
+        {
+            self.current = Some(last.clone());
+            return;
         }
 
-        let entry = ClipboardEntry::new(content, Some(source), id);
+        self.current = Some(entry.clone());
         self.entries.insert(0, entry);
+        self.evict_overflow();
+    }
+
+    /// Adds entries as returned by a provider, which lists the newest first.
+    /// Entries without a timestamp are placed just before the next newer one
+    /// and marked as estimated. Entries already known from another provider
+    /// are not duplicated.
+    fn add_provider_entries(&mut self, source: &str, entries: Vec<ProviderEntry>) {
+        let mut newer = Local::now();
+        let entries = entries
+            .into_iter()
+            .map(|provided| {
+                let estimated_time = provided.estimated_time || provided.timestamp.is_none();
+                let timestamp = provided
+                    .timestamp
+                    .unwrap_or_else(|| newer - Duration::milliseconds(1));
+                newer = timestamp;
+
+                let source = provided.source.unwrap_or_else(|| source.to_string());
+                let mut entry = ClipboardEntry::new(provided.content, Some(source), provided.id);
+                entry.timestamp = timestamp;
+                entry.estimated_time = estimated_time;
+                entry.pinned = provided.pinned;
+                entry.tags = provided.tags;
+                entry.formats = provided.formats;
+                entry.copy_count = provided.copy_count;
+                entry.last_used = provided.last_used;
+                entry
+            })
+            .collect();
+
+        self.merge_entries(entries);
+    }
+
+    /// Merges already timestamped entries (e.g. from an import) into the
+    /// history, skipping duplicates. Returns the number of entries added
+    /// that are still in the history once it is cut back to its size.
+    fn merge_entries(&mut self, mut entries: Vec<ClipboardEntry>) -> usize {
+        let mut added = HashSet::new();
+
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.timestamp));
+
+        for entry in entries {
+            if let Some(existing) = self
+                .entries
+                .iter_mut()
+                .find(|existing| existing.is_duplicate_of(&entry))
+            {
+                existing.pinned |= entry.pinned;
+                existing.copy_count = existing.copy_count.max(entry.copy_count);
+                existing.last_used = existing.last_used.max(entry.last_used);
+                if existing.formats.is_empty() {
+                    existing.formats = entry.formats;
+                }
+                for tag in entry.tags {
+                    if !existing.tags.contains(&tag) {
+                        existing.tags.push(tag);
+                    }
+                }
+                continue;
+            }
 
-        if self.entries.len() > self.max_entries {
-            self.entries.truncate(self.max_entries);
+            added.insert(entry.key);
+            self.entries.push(entry);
         }
+
+        self.resolve_current();
+
+        self.entries
+            .sort_by_key(|entry| std::cmp::Reverse(entry.timestamp));
+        self.evict_overflow();
+
+        self.entries
+            .iter()
+            .filter(|entry| added.contains(&entry.key))
+            .count()
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Keeps only the entries from `source`.
+    fn retain_source(&mut self, source: &str) {
+        self.entries.retain(|entry| entry.source == source);
+    }
+
+    /// Removes unpinned sensitive entries older than `max_age` and returns them.
+    fn expire_sensitive(&mut self, now: DateTime<Local>, max_age: Duration) -> Vec<ClipboardEntry> {
+        let (expired, kept) =
+            std::mem::take(&mut self.entries)
+                .into_iter()
+                .partition(|entry: &ClipboardEntry| {
+                    entry.sensitivity.is_some()
+                        && !entry.pinned
+                        && now.signed_duration_since(entry.timestamp) > max_age
+                });
+
+        self.entries = kept;
+        expired
     }
 
     fn entries(&self) -> &[ClipboardEntry] {
         &self.entries
     }
+
+    /// Records that the application put the entry `key` on the clipboard:
+    /// it becomes the current entry and moves to the top instead of being
+    /// added again, and counts as used. Returns false when no such entry
+    /// exists.
+    fn mark_copied(&mut self, key: EntryKey) -> bool {
+        let Some(entry) = self.move_to_top(key) else {
+            return false;
+        };
+
+        entry.copy_count += 1;
+        entry.last_used = Some(entry.timestamp);
+        self.current = Some(entry.clone());
+        true
+    }
+
+    fn remove(&mut self, key: EntryKey) -> Option<ClipboardEntry> {
+        let position = self.entries.iter().position(|entry| entry.key == key)?;
+        Some(self.entries.remove(position))
+    }
+
+    /// Pins or unpins the entry and returns whether it is now pinned.
+    fn toggle_pinned(&mut self, key: EntryKey) -> Option<bool> {
+        let entry = self.entries.iter_mut().find(|entry| entry.key == key)?;
+        entry.pinned = !entry.pinned;
+        Some(entry.pinned)
+    }
+
+    fn current_key(&self) -> Option<EntryKey> {
+        self.current.as_ref().map(|current| current.key)
+    }
+}
+
+impl ClipboardHistory {
+    /// Moves the entry `key` to the top as the current entry, with a new
+    /// timestamp.
+    fn move_to_top(&mut self, key: EntryKey) -> Option<&mut ClipboardEntry> {
+        let position = self.entries.iter().position(|entry| entry.key == key)?;
+
+        let mut entry = self.entries.remove(position);
+        entry.timestamp = Local::now();
+        entry.estimated_time = false;
+        self.current = Some(entry.clone());
+        self.entries.insert(0, entry);
+        self.entries.first_mut()
+    }
+
+    /// Points the current entry at its reloaded counterpart when the entry
+    /// itself is no longer part of the history.
+    fn resolve_current(&mut self) {
+        let Some(current) = &self.current else {
+            return;
+        };
+        if self.entries.iter().any(|entry| entry.key == current.key) {
+            return;
+        }
+
+        if let Some(reloaded) = self
+            .entries
+            .iter()
+            .find(|entry| entry.is_duplicate_of(current))
+        {
+            self.current = Some(reloaded.clone());
+        }
+    }
+
+    /// Cuts the history back to `max_entries`, dropping the oldest unpinned
+    /// entries. Pinned entries always stay.
+    fn evict_overflow(&mut self) {
+        let mut excess = self.entries.len().saturating_sub(self.max_entries);
+
+        for position in (0..self.entries.len()).rev() {
+            if excess == 0 {
+                break;
+            }
+            if !self.entries[position].pinned {
+                self.entries.remove(position);
+                excess -= 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> ClipboardContent {
+        ClipboardContent::Text(value.to_string())
+    }
+
+    fn add(history: &mut ClipboardHistory, value: &str) {
+        history.add_entry_with_source(text(value), "clipboard".to_string(), None, Vec::new());
+    }
+
+    #[test]
+    fn recopied_entry_moves_to_top_without_duplicate() {
+        let mut history = ClipboardHistory::new();
+        add(&mut history, "first");
+        add(&mut history, "second");
+        let first = history.entries()[1].key;
+
+        assert!(history.mark_copied(first));
+        assert_eq!(history.current_key(), Some(first));
+        assert_eq!(history.entries()[0].key, first);
+        assert_eq!(history.entries()[0].copy_count, 1);
+        assert!(history.entries()[0].last_used.is_some());
+
+        // The same text arriving from the clipboard is the own copy, not
+        // another use.
+        add(&mut history, "first");
+        assert_eq!(history.entries().len(), 2);
+        assert_eq!(history.current_key(), Some(first));
+        assert_eq!(history.entries()[0].copy_count, 1);
+
+        add(&mut history, "third");
+        assert_eq!(history.entries().len(), 3);
+        assert_eq!(history.current_key(), Some(history.entries()[0].key));
+    }
+
+    #[test]
+    fn removes_and_pins_by_key() {
+        let mut history = ClipboardHistory::new();
+        add(&mut history, "keep");
+        add(&mut history, "drop");
+        let [drop, keep] = [history.entries()[0].key, history.entries()[1].key];
+
+        assert_eq!(history.toggle_pinned(keep), Some(true));
+        assert_eq!(history.toggle_pinned(keep), Some(false));
+        assert!(history.remove(drop).is_some());
+        assert!(history.remove(drop).is_none());
+        assert_eq!(history.entries().len(), 1);
+        assert_eq!(history.entries()[0].key, keep);
+    }
+
+    #[test]
+    fn keeps_only_entries_from_one_source() {
+        let mut history = ClipboardHistory::new();
+        add(&mut history, "owned");
+        history.add_entry_with_source(text("kept"), "cliphist".to_string(), None, Vec::new());
+
+        history.retain_source("cliphist");
+        assert_eq!(history.entries().len(), 1);
+        assert_eq!(history.entries()[0].content.as_text(), "kept");
+    }
+
+    #[test]
+    fn formats_repeating_the_content_are_covered() {
+        let image = ClipboardContent::Image(Some(Arc::from(&b"png"[..])));
+        assert!(image.covers(&ClipboardFormat::new("image/png", b"png")));
+        assert!(image.covers(&ClipboardFormat::new("image/bmp", b"bmp")));
+
+        let text = text("hello");
+        assert!(text.covers(&ClipboardFormat::new("text/plain;charset=utf-8", b"hello")));
+        assert!(!text.covers(&ClipboardFormat::new("text/plain", b"other")));
+        assert!(!text.covers(&ClipboardFormat::new("text/x-moz-url", b"hello")));
+    }
+
+    #[test]
+    fn recognizes_links_and_code() {
+        assert!(text("https://example.com/a?b=c").is_link());
+        assert!(text("  www.example.org\n").is_link());
+        assert!(!text("see https://example.com").is_link());
+        assert!(!text("https://").is_link());
+
+        assert!(text("let x = 5;").is_code());
+        assert!(text("fn main() {\n    println!(\"hi\");\n}").is_code());
+        assert!(!text("Dear team,\nthe meeting moved to Friday.\nThanks").is_code());
+        assert!(!ClipboardContent::Image(None).is_code());
+    }
+
+    #[test]
+    fn merges_duplicates_within_the_batch() {
+        let mut history = ClipboardHistory::new();
+        add(&mut history, "existing");
+
+        let mut pinned = ClipboardEntry::new(text("twice"), None, None);
+        pinned.pinned = true;
+        let added = history.merge_entries(vec![
+            ClipboardEntry::new(text("twice"), None, None),
+            pinned,
+            ClipboardEntry::new(text("existing"), None, None),
+        ]);
+
+        assert_eq!(added, 1);
+        assert_eq!(history.entries().len(), 2);
+        assert!(history.entries().iter().any(|entry| entry.pinned));
+    }
+
+    #[test]
+    fn counts_only_merged_entries_that_fit() {
+        let mut history = ClipboardHistory::new();
+        let now = Local::now();
+        let entries = (0..150)
+            .map(|age| {
+                let mut entry = ClipboardEntry::new(text(&format!("entry {age}")), None, None);
+                entry.timestamp = now - Duration::minutes(age);
+                entry
+            })
+            .collect();
+
+        assert_eq!(history.merge_entries(entries), 100);
+        assert_eq!(history.entries().len(), 100);
+        assert_eq!(history.entries()[99].content.as_text(), "entry 99");
+    }
+
+    #[test]
+    fn keeps_pinned_entries_past_the_limit() {
+        let mut history = ClipboardHistory::new();
+        add(&mut history, "pinned");
+        let pinned = history.entries()[0].key;
+        history.toggle_pinned(pinned);
+
+        for index in 0..120 {
+            add(&mut history, &format!("entry {index}"));
+        }
+
+        let entries = history.entries();
+        assert_eq!(entries.len(), 100);
+        assert_eq!(entries[99].key, pinned);
+        assert_eq!(entries[98].content.as_text(), "entry 21");
+    }
+
+    #[test]
+    fn current_entry_survives_reload() {
+        let mut history = ClipboardHistory::new();
+        let image = ClipboardContent::Image(Some(Arc::from(&b"png"[..])));
+        history.add_entry_with_source(image.clone(), "clipboard".to_string(), None, Vec::new());
+        add(&mut history, "text");
+        let image_key = history.entries()[1].key;
+        history.mark_copied(image_key);
+
+        history.clear();
+        history.merge_entries(vec![
+            ClipboardEntry::new(text("text"), None, None),
+            ClipboardEntry::new(image, None, None),
+        ]);
+
+        let current = history.current_key().unwrap();
+        assert_ne!(current, image_key);
+        let entry = history.entries().iter().find(|entry| entry.key == current);
+        assert!(entry.is_some_and(|entry| entry.content.is_image()));
+    }
 }
@@ -1,15 +1,71 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    sync::{Arc, Mutex, PoisonError},
+    time,
+};
 
-use gtk::{gdk, gio};
+use chrono::{Duration, Local};
+use gtk::{gdk, gio, glib, prelude::*};
 
 use crate::service::{
-    cliboard_history::{ClipboardHistory, IClipboardHistory},
-    cliboard_provider::IClipboardProvider,
+    cliboard_history::{
+        ClipboardContent, ClipboardEntry, ClipboardFormat, ClipboardHistory, EntryKey,
+        IClipboardEntry, IClipboardHistory,
+    },
+    cliboard_provider::{BoxedClipboardProvider, IClipboardProvider},
+    clipboard_filter::{self, ClipboardFilter, KDE_PASSWORD_HINT},
+    cliphist_provider::CliphistProvider,
+    file_list,
+    native_store_provider::{NativeStoreProvider, StoreProtection},
+    recording_state::RecordingState,
+    rich_text,
+    selection_sync::{Selection, SelectionSync, SyncDirection},
 };
 
+const SOURCE: &str = "clipboard";
+const PRIMARY_SOURCE: &str = "primary";
+const DROP_SOURCE: &str = "drop";
+const CROP_SOURCE: &str = "crop";
+const HINT_READ_LIMIT: usize = 64;
+/// Upper bound for all formats of one copy together.
+const FORMATS_TOTAL_LIMIT: usize = 16 * 1024 * 1024;
+const FORMAT_READ_LIMIT: usize = 4 * 1024 * 1024;
+const READ_CHUNK: usize = 64 * 1024;
+/// How long changes may pile up before the store is written.
+const PERSIST_DELAY: time::Duration = time::Duration::from_millis(500);
+
+type HistoryListener = Rc<dyn Fn()>;
+
 #[derive(Clone)]
 pub struct ClipboardMonitor {
     history: Rc<RefCell<ClipboardHistory>>,
+    store: Rc<RefCell<Option<NativeStoreProvider>>>,
+    protection: Rc<RefCell<StoreProtection>>,
+    filter: Rc<RefCell<ClipboardFilter>>,
+    listeners: Rc<RefCell<Vec<HistoryListener>>>,
+    recording: Rc<Cell<RecordingState>>,
+    primary: Rc<Cell<PrimaryTracking>>,
+    /// Bumped on every PRIMARY change; a debounced capture only runs when
+    /// no newer change arrived in the meantime.
+    primary_generation: Rc<Cell<u64>>,
+    sync: Rc<RefCell<SelectionSync>>,
+    /// Bumped on every `persist`; the debounced save only runs for the
+    /// latest call.
+    persist_generation: Rc<Cell<u64>>,
+    /// Numbers the snapshots handed to saves in the order they were taken.
+    snapshot: Rc<Cell<u64>>,
+    /// Held while saving, with the number of the newest snapshot written,
+    /// so a slow save never overwrites a newer one.
+    last_saved: Arc<Mutex<u64>>,
+}
+
+/// Whether the PRIMARY selection is recorded, and how long it has to stay
+/// unchanged first. Selections change on every drag of the pointer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrimaryTracking {
+    pub enabled: bool,
+    pub debounce: time::Duration,
 }
 
 pub trait IClipboardMonitor {
@@ -25,19 +81,25 @@ impl ClipboardMonitor {
     ) -> Result<(), String> {
         let history = self.history.clone();
         let provider_name = provider.name();
-        let result = gio::spawn_blocking(move || provider.list_entries()).await;
+        // cliphist entries get back when they were first seen and how often
+        // they were used, which cliphist does not keep.
+        let store = self
+            .store()
+            .filter(|_| provider_name == CliphistProvider.name());
+        let result = gio::spawn_blocking(move || {
+            let mut entries = provider.list_entries()?;
+            if let Some(meta) = store.and_then(|store| store.cliphist_meta().ok()) {
+                meta.apply(&mut entries);
+            }
+            Ok::<_, String>(entries)
+        })
+        .await;
 
         match result {
             Ok(Ok(entries)) => {
-                eprintln!("cliphist entries loaded: {}", entries.len());
+                eprintln!("{provider_name} entries loaded: {}", entries.len());
                 let mut history_guard = history.borrow_mut();
-                for (_raw_id, content) in entries.into_iter().rev() {
-                    history_guard.add_entry_with_source(
-                        content,
-                        provider_name.to_string(),
-                        _raw_id,
-                    );
-                }
+                history_guard.add_provider_entries(provider_name, entries);
                 let total_entries = history_guard.entries().len();
                 eprintln!("history entries after import: {}", total_entries);
                 Ok(())
@@ -46,16 +108,653 @@ impl ClipboardMonitor {
             Err(err) => Err(format!("Spawn error: {:?}", err)),
         }
     }
+
+    /// Replaces the history with the merged entries of all `providers`. A
+    /// failing provider does not prevent the others from loading.
+    pub async fn load_providers(&self, providers: Vec<BoxedClipboardProvider>) -> Vec<String> {
+        self.history.borrow_mut().clear();
+
+        let mut errors = Vec::new();
+        let mut loaded = Vec::new();
+        for provider in providers {
+            let provider_name = provider.name();
+            match self.load_history(provider).await {
+                Ok(()) => loaded.push(provider_name),
+                Err(err) => errors.push(format!("{provider_name}: {err}")),
+            }
+        }
+
+        // Records when new cliphist entries were first seen. Only once the
+        // store itself loaded, so a save cannot drop what it holds.
+        let store_loaded = self
+            .store()
+            .is_some_and(|store| loaded.contains(&store.name()));
+        if store_loaded && loaded.contains(&CliphistProvider.name()) {
+            self.persist();
+        }
+
+        errors
+    }
+
+    /// Sets where entries owned by the application are persisted, keeping
+    /// the current store protection.
+    pub fn set_store(&self, store: Option<NativeStoreProvider>) {
+        let protection = self.protection.borrow().clone();
+        *self.store.borrow_mut() = store.map(|store| store.with_protection(protection));
+    }
+
+    pub fn store(&self) -> Option<NativeStoreProvider> {
+        self.store.borrow().clone()
+    }
+
+    pub fn set_store_protection(&self, protection: StoreProtection) {
+        let store = self.store.borrow_mut().take();
+        *self.protection.borrow_mut() = protection;
+        self.set_store(store);
+    }
+
+    /// Saves and locks the store. Its entries leave the history until the
+    /// next unlock; only those living in cliphist stay.
+    pub fn lock_store(&self) {
+        self.persist_now();
+        self.set_store_protection(StoreProtection::Locked);
+        self.history
+            .borrow_mut()
+            .retain_source(CliphistProvider.name());
+        self.notify_history_changed();
+    }
+
+    pub fn is_store_locked(&self) -> bool {
+        matches!(*self.protection.borrow(), StoreProtection::Locked)
+    }
+
+    pub fn is_store_encrypted(&self) -> bool {
+        !matches!(*self.protection.borrow(), StoreProtection::Plain)
+    }
+
+    /// Drops sensitive entries older than `max_age`. Their copy in cliphist
+    /// is only deleted with `from_cliphist`, in the background. Returns how
+    /// many entries were removed.
+    pub fn expire_sensitive(&self, max_age: Duration, from_cliphist: bool) -> usize {
+        let expired = self
+            .history
+            .borrow_mut()
+            .expire_sensitive(Local::now(), max_age);
+
+        let cliphist = CliphistProvider;
+        let ids: Vec<String> = expired
+            .iter()
+            .filter(|entry| from_cliphist && entry.source == cliphist.name())
+            .filter_map(|entry| entry.id.clone())
+            .collect();
+        if !ids.is_empty() {
+            gio::spawn_blocking(move || {
+                for id in ids {
+                    if let Err(err) = CliphistProvider.delete(&id) {
+                        eprintln!("Error deleting expired entry: {err}");
+                    }
+                }
+            });
+        }
+
+        if !expired.is_empty() {
+            self.persist();
+        }
+
+        expired.len()
+    }
+
+    /// Removes an entry, also from cliphist when it came from there.
+    pub fn remove_entry(&self, key: EntryKey) {
+        let Some(entry) = self.history.borrow_mut().remove(key) else {
+            return;
+        };
+
+        let cliphist = CliphistProvider;
+        if entry.source == cliphist.name()
+            && let Some(id) = &entry.id
+            && let Err(err) = cliphist.delete(id)
+        {
+            eprintln!("Error deleting entry: {err}");
+        }
+
+        self.persist();
+        self.notify_history_changed();
+    }
+
+    /// Pins or unpins an entry and returns whether it is now pinned.
+    pub fn toggle_pinned(&self, key: EntryKey) -> Option<bool> {
+        let pinned = self.history.borrow_mut().toggle_pinned(key)?;
+
+        self.persist();
+        self.notify_history_changed();
+        Some(pinned)
+    }
+
+    /// Adds content dropped onto the window. Returns false when it is
+    /// already known.
+    pub fn add_dropped(&self, content: ClipboardContent) -> bool {
+        self.add_created(content, DROP_SOURCE)
+    }
+
+    /// Adds an image cropped in the viewer. Returns false when it is already
+    /// known.
+    pub fn add_cropped(&self, content: ClipboardContent) -> bool {
+        self.add_created(content, CROP_SOURCE)
+    }
+
+    /// Unlike a copy, content made in the application does not become the
+    /// current entry.
+    fn add_created(&self, content: ClipboardContent, source: &str) -> bool {
+        let entry = ClipboardEntry::new(content, Some(source.to_string()), None);
+        if self.history.borrow_mut().merge_entries(vec![entry]) == 0 {
+            return false;
+        }
+
+        self.persist();
+        self.notify_history_changed();
+        true
+    }
+
+    pub fn set_filter(&self, filter: ClipboardFilter) {
+        *self.filter.borrow_mut() = filter;
+    }
+
+    pub fn set_recording_state(&self, state: RecordingState) {
+        self.recording.set(state);
+    }
+
+    pub fn set_primary_tracking(&self, tracking: PrimaryTracking) {
+        self.primary.set(tracking);
+    }
+
+    pub fn set_selection_sync(&self, sync: SelectionSync) {
+        *self.sync.borrow_mut() = sync;
+    }
+
+    /// The current state, with an expired pause reported as recording.
+    pub fn recording_state(&self) -> RecordingState {
+        let state = self.recording.get();
+        if state.is_paused_at(Local::now()) {
+            state
+        } else {
+            RecordingState::Recording
+        }
+    }
+
+    /// Calls `callback` whenever the monitor records a new entry.
+    pub fn connect_history_changed<F: Fn() + 'static>(&self, callback: F) {
+        self.listeners.borrow_mut().push(Rc::new(callback));
+    }
+
+    fn notify_history_changed(&self) {
+        let listeners = self.listeners.borrow().clone();
+        for listener in listeners {
+            listener();
+        }
+    }
+
+    fn start_monitoring(&self, display: &gdk::Display) {
+        display.clipboard().connect_changed(glib::clone!(
+            #[strong(rename_to = monitor)]
+            self,
+            move |clipboard| {
+                // The application's own copies are mirrored but not recorded
+                // again.
+                let local = clipboard.is_local();
+
+                glib::MainContext::default().spawn_local(glib::clone!(
+                    #[strong]
+                    monitor,
+                    #[strong]
+                    clipboard,
+                    async move {
+                        if !local {
+                            monitor.capture(&clipboard, SOURCE).await;
+                        }
+                        monitor.mirror(&clipboard, Selection::Clipboard).await;
+                    }
+                ));
+            }
+        ));
+
+        display.primary_clipboard().connect_changed(glib::clone!(
+            #[strong(rename_to = monitor)]
+            self,
+            move |primary| {
+                let tracking = monitor.primary.get();
+                let records = tracking.enabled && !primary.is_local();
+                // Seen whenever syncing is on, as changes in either
+                // selection reset what was last mirrored.
+                let mirrors = monitor.sync.borrow().direction != SyncDirection::Off;
+                if !records && !mirrors {
+                    return;
+                }
+
+                let generation = monitor.primary_generation.get().wrapping_add(1);
+                monitor.primary_generation.set(generation);
+
+                glib::timeout_add_local_once(
+                    tracking.debounce,
+                    glib::clone!(
+                        #[strong]
+                        monitor,
+                        #[strong]
+                        primary,
+                        move || {
+                            if monitor.primary_generation.get() != generation {
+                                return;
+                            }
+
+                            glib::MainContext::default().spawn_local(async move {
+                                if records {
+                                    monitor.capture(&primary, PRIMARY_SOURCE).await;
+                                }
+                                monitor.mirror(&primary, Selection::Primary).await;
+                            });
+                        }
+                    ),
+                );
+            }
+        ));
+    }
+
+    /// Copies new text in `selection` to the other selection when the sync
+    /// settings ask for it. Content that would not be recorded is never
+    /// mirrored either.
+    async fn mirror(&self, clipboard: &gdk::Clipboard, selection: Selection) {
+        if self.sync.borrow().direction == SyncDirection::Off {
+            return;
+        }
+
+        let offered = offered_formats(clipboard);
+        let filter = self.filter.borrow().clone();
+        if !self.admits(clipboard, &filter, &offered).await {
+            self.sync.borrow_mut().reset();
+            return;
+        }
+
+        let text = match clipboard.read_text_future().await {
+            Ok(Some(text)) => text.to_string(),
+            _ => return,
+        };
+        if filter.excludes_text(&text) {
+            self.sync.borrow_mut().reset();
+            return;
+        }
+        if !self.sync.borrow_mut().should_mirror(selection, &text) {
+            return;
+        }
+
+        let display = clipboard.display();
+        let target = match selection {
+            Selection::Clipboard => display.primary_clipboard(),
+            Selection::Primary => display.clipboard(),
+        };
+        target.set_text(&text);
+    }
+
+    /// Whether the content now in `clipboard` may be read at all: recording
+    /// is on and neither its formats nor a password manager hint conceal it.
+    async fn admits(
+        &self,
+        clipboard: &gdk::Clipboard,
+        filter: &ClipboardFilter,
+        offered: &[String],
+    ) -> bool {
+        if !may_read(self.recording_state(), filter, offered) {
+            return false;
+        }
+
+        // The hint is only read once nothing else rules the content out.
+        !(offered.iter().any(|mime| mime == KDE_PASSWORD_HINT)
+            && read_password_hint(clipboard).await)
+    }
+
+    async fn capture(&self, clipboard: &gdk::Clipboard, source: &str) {
+        let offered = offered_formats(clipboard);
+        let filter = self.filter.borrow().clone();
+        if !self.admits(clipboard, &filter, &offered).await {
+            return;
+        }
+
+        let content = if let Some(files) = read_files(clipboard, &offered).await {
+            files
+        } else if offered.iter().any(|mime| mime.starts_with("image/")) {
+            match clipboard.read_texture_future().await {
+                Ok(Some(texture)) => {
+                    ClipboardContent::Image(Some(Arc::from(texture.save_to_png_bytes().as_ref())))
+                }
+                Ok(None) => return,
+                Err(err) => {
+                    eprintln!("Error reading clipboard image: {err}");
+                    return;
+                }
+            }
+        } else if let Some(rich) = read_rich_text(clipboard, &offered).await {
+            rich
+        } else {
+            match clipboard.read_text_future().await {
+                Ok(Some(text)) if !text.trim().is_empty() => {
+                    ClipboardContent::Text(text.to_string())
+                }
+                Ok(_) => return,
+                Err(err) => {
+                    eprintln!("Error reading clipboard text: {err}");
+                    return;
+                }
+            }
+        };
+
+        if !content.is_image() && filter.excludes_text(&content.as_text()) {
+            return;
+        }
+
+        let mut formats = read_all_formats(clipboard, &offered).await;
+        formats.retain(|format| !content.covers(format));
+
+        self.history
+            .borrow_mut()
+            .add_entry_with_source(content, source.to_string(), None, formats);
+        self.persist();
+        self.notify_history_changed();
+    }
+
+    /// Saves the owned entries in the background once changes stop for a
+    /// moment. While the store is locked they only live in memory and are
+    /// written after the next unlock.
+    pub fn persist(&self) {
+        let generation = self.persist_generation.get().wrapping_add(1);
+        self.persist_generation.set(generation);
+
+        glib::timeout_add_local_once(
+            PERSIST_DELAY,
+            glib::clone!(
+                #[strong(rename_to = monitor)]
+                self,
+                move || {
+                    if monitor.persist_generation.get() != generation {
+                        return;
+                    }
+                    let Some((store, entries, number)) = monitor.snapshot() else {
+                        return;
+                    };
+
+                    let last_saved = monitor.last_saved.clone();
+                    glib::MainContext::default().spawn_local(async move {
+                        let saved = gio::spawn_blocking(move || {
+                            save_snapshot(&store, &entries, number, &last_saved)
+                        })
+                        .await;
+                        if saved.is_err() {
+                            eprintln!("Error saving history: the save did not finish");
+                        }
+                    });
+                }
+            ),
+        );
+    }
+
+    /// Saves right away, for when the application quits or the store is
+    /// about to lock. Cancels a pending `persist`.
+    pub fn persist_now(&self) {
+        self.persist_generation
+            .set(self.persist_generation.get().wrapping_add(1));
+        if let Some((store, entries, number)) = self.snapshot() {
+            save_snapshot(&store, &entries, number, &self.last_saved);
+        }
+    }
+
+    fn snapshot(&self) -> Option<(NativeStoreProvider, Vec<ClipboardEntry>, u64)> {
+        let store = self.store.borrow().clone()?;
+        if self.is_store_locked() {
+            return None;
+        }
+
+        let number = self.snapshot.get() + 1;
+        self.snapshot.set(number);
+        Some((store, self.history.borrow().entries().to_vec(), number))
+    }
 }
 
 impl IClipboardMonitor for ClipboardMonitor {
-    fn new(_display: &gdk::Display) -> Self {
+    fn new(display: &gdk::Display) -> Self {
         let history = Rc::new(RefCell::new(ClipboardHistory::new()));
 
-        Self { history }
+        let monitor = Self {
+            history,
+            store: Rc::new(RefCell::new(None)),
+            protection: Rc::new(RefCell::new(StoreProtection::Plain)),
+            filter: Rc::new(RefCell::new(ClipboardFilter::default())),
+            listeners: Rc::new(RefCell::new(Vec::new())),
+            recording: Rc::new(Cell::new(RecordingState::Recording)),
+            primary: Rc::new(Cell::new(PrimaryTracking {
+                enabled: false,
+                debounce: time::Duration::ZERO,
+            })),
+            primary_generation: Rc::new(Cell::new(0)),
+            sync: Rc::new(RefCell::new(SelectionSync::default())),
+            persist_generation: Rc::new(Cell::new(0)),
+            snapshot: Rc::new(Cell::new(0)),
+            last_saved: Arc::new(Mutex::new(0)),
+        };
+
+        monitor.start_monitoring(display);
+        monitor
     }
 
     fn history(&self) -> Rc<RefCell<ClipboardHistory>> {
         Rc::clone(&self.history)
     }
 }
+
+/// Writes the snapshot numbered `number` unless a newer one was written.
+fn save_snapshot(
+    store: &NativeStoreProvider,
+    entries: &[ClipboardEntry],
+    number: u64,
+    last_saved: &Mutex<u64>,
+) {
+    let mut last_saved = last_saved.lock().unwrap_or_else(PoisonError::into_inner);
+    if number < *last_saved {
+        return;
+    }
+
+    if let Err(err) = store.save(entries) {
+        eprintln!("Error saving history: {err}");
+    }
+    *last_saved = number;
+}
+
+fn offered_formats(clipboard: &gdk::Clipboard) -> Vec<String> {
+    clipboard
+        .formats()
+        .mime_types()
+        .iter()
+        .map(|mime| mime.to_string())
+        .collect()
+}
+
+/// The checks both recording and mirroring go through before anything is
+/// read from the clipboard.
+fn may_read(recording: RecordingState, filter: &ClipboardFilter, offered: &[String]) -> bool {
+    recording == RecordingState::Recording && !filter.excludes_formats(offered)
+}
+
+/// Reads the KDE password manager hint. Content whose hint cannot be read is
+/// treated as secret.
+async fn read_password_hint(clipboard: &gdk::Clipboard) -> bool {
+    match read_format(clipboard, KDE_PASSWORD_HINT, HINT_READ_LIMIT).await {
+        Ok(bytes) => clipboard_filter::is_secret_hint(&bytes),
+        Err(_) => true,
+    }
+}
+
+/// Reads every offered representation so a re-copy can offer the same
+/// formats again. Only the first image type is kept: toolkits convert a
+/// single image into a dozen formats on request, some of them uncompressed.
+async fn read_all_formats(clipboard: &gdk::Clipboard, offered: &[String]) -> Vec<ClipboardFormat> {
+    let mut formats = Vec::new();
+    let mut total = 0;
+    let mut has_image = false;
+
+    for mime in offered {
+        // X11 targets such as `UTF8_STRING` and `TARGETS` are not MIME types.
+        if !mime.contains('/') || mime == KDE_PASSWORD_HINT {
+            continue;
+        }
+        if mime.starts_with("image/") {
+            if has_image {
+                continue;
+            }
+            has_image = true;
+        }
+
+        // One byte over the limit tells a truncated read from a complete one.
+        let Ok(data) = read_format(clipboard, mime, FORMAT_READ_LIMIT + 1).await else {
+            continue;
+        };
+        if data.is_empty()
+            || data.len() > FORMAT_READ_LIMIT
+            || total + data.len() > FORMATS_TOTAL_LIMIT
+        {
+            continue;
+        }
+
+        total += data.len();
+        formats.push(ClipboardFormat::new(mime, &data));
+    }
+
+    formats
+}
+
+/// Reads HTML and RTF when offered, with the offered plain text or the text
+/// of the HTML as fallback. RTF stays in the bytes and type it came as.
+async fn read_rich_text(
+    clipboard: &gdk::Clipboard,
+    offered: &[String],
+) -> Option<ClipboardContent> {
+    let html = match offered.iter().any(|mime| mime == rich_text::HTML_MIME) {
+        true => read_format(clipboard, rich_text::HTML_MIME, FORMAT_READ_LIMIT)
+            .await
+            .ok()
+            .map(|bytes| rich_text::decode_text(&bytes)),
+        false => None,
+    };
+
+    let rtf = match offered
+        .iter()
+        .find(|mime| rich_text::RTF_MIMES.contains(&mime.as_str()))
+    {
+        Some(mime) => read_format(clipboard, mime, FORMAT_READ_LIMIT)
+            .await
+            .ok()
+            .map(|bytes| ClipboardFormat::new(mime, &bytes)),
+        None => None,
+    };
+
+    if html.is_none() && rtf.is_none() {
+        return None;
+    }
+
+    let plain = match clipboard.read_text_future().await {
+        Ok(Some(text)) => text.to_string(),
+        _ => html
+            .as_deref()
+            .map(rich_text::html_to_plain)
+            .unwrap_or_default(),
+    };
+
+    if plain.trim().is_empty() {
+        return None;
+    }
+
+    Some(ClipboardContent::Rich { plain, html, rtf })
+}
+
+/// Reads copied files, preferring the GNOME format that also records whether
+/// they were cut. Lists with links or remote locations are left to be read
+/// as text.
+async fn read_files(clipboard: &gdk::Clipboard, offered: &[String]) -> Option<ClipboardContent> {
+    let offers = |format: &str| offered.iter().any(|mime| mime == format);
+
+    if offers(file_list::GNOME_COPIED_FILES_MIME)
+        && let Ok(bytes) = read_format(
+            clipboard,
+            file_list::GNOME_COPIED_FILES_MIME,
+            FORMAT_READ_LIMIT,
+        )
+        .await
+        && let Some((operation, uris)) =
+            file_list::parse_gnome_copied_files(&String::from_utf8_lossy(&bytes))
+        && uris.iter().all(|uri| file_list::is_file_uri(uri))
+    {
+        return Some(ClipboardContent::Files {
+            uris,
+            operation: Some(operation),
+        });
+    }
+
+    if !offers(file_list::URI_LIST_MIME) {
+        return None;
+    }
+
+    let bytes = read_format(clipboard, file_list::URI_LIST_MIME, FORMAT_READ_LIMIT)
+        .await
+        .ok()?;
+    let uris = file_list::parse_uri_list(&String::from_utf8_lossy(&bytes));
+
+    let local = !uris.is_empty() && uris.iter().all(|uri| file_list::is_file_uri(uri));
+
+    local.then_some(ClipboardContent::Files {
+        uris,
+        operation: None,
+    })
+}
+
+/// Reads up to `limit` bytes of the clipboard in the format `mime`.
+async fn read_format(
+    clipboard: &gdk::Clipboard,
+    mime: &str,
+    limit: usize,
+) -> Result<Vec<u8>, glib::Error> {
+    let (stream, _) = clipboard
+        .read_future(&[mime], glib::Priority::DEFAULT)
+        .await?;
+    let mut data = Vec::new();
+
+    while data.len() < limit {
+        let chunk = stream
+            .read_bytes_future(READ_CHUNK.min(limit - data.len()), glib::Priority::DEFAULT)
+            .await?;
+        if chunk.is_empty() {
+            break;
+        }
+        data.extend_from_slice(&chunk);
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_nothing_while_paused_or_concealed() {
+        let filter = ClipboardFilter::default();
+        let text = vec!["text/plain".to_string()];
+        let concealed = vec![
+            "text/plain".to_string(),
+            "application/x-nspasteboard-concealed-type".to_string(),
+        ];
+
+        assert!(may_read(RecordingState::Recording, &filter, &text));
+        assert!(!may_read(RecordingState::Recording, &filter, &concealed));
+        assert!(!may_read(
+            RecordingState::Paused { until: None },
+            &filter,
+            &text
+        ));
+    }
+}
@@ -1,6 +1,124 @@
-use crate::service::cliboard_history::ClipboardContent;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local};
+
+use crate::service::{
+    cliboard_history::{ClipboardContent, ClipboardEntry, ClipboardFormat},
+    cliphist_provider::CliphistProvider,
+    clipman_provider::ClipmanProvider,
+    copyq_provider::CopyqProvider,
+    file_provider::FileProvider,
+    gpaste_provider::GpasteProvider,
+    greenclip_provider::GreenclipProvider,
+    native_store_provider::NativeStoreProvider,
+};
+
+/// One history item as listed by a provider. Anything the provider does not
+/// know is left empty and filled in by the history.
+#[derive(Debug, Clone)]
+pub struct ProviderEntry {
+    pub id: Option<String>,
+    pub content: ClipboardContent,
+    pub timestamp: Option<DateTime<Local>>,
+    /// The timestamp is only the provider's guess, not when the entry was
+    /// actually copied.
+    pub estimated_time: bool,
+    pub source: Option<String>,
+    pub pinned: bool,
+    pub tags: Vec<String>,
+    pub formats: Vec<ClipboardFormat>,
+    pub copy_count: u32,
+    pub last_used: Option<DateTime<Local>>,
+}
+
+impl ProviderEntry {
+    pub fn new(id: Option<String>, content: ClipboardContent) -> Self {
+        Self {
+            id,
+            content,
+            timestamp: None,
+            estimated_time: false,
+            source: None,
+            pinned: false,
+            tags: Vec::new(),
+            formats: Vec::new(),
+            copy_count: 0,
+            last_used: None,
+        }
+    }
+}
+
+impl From<ClipboardEntry> for ProviderEntry {
+    fn from(entry: ClipboardEntry) -> Self {
+        Self {
+            id: entry.id,
+            content: entry.content,
+            timestamp: Some(entry.timestamp),
+            estimated_time: entry.estimated_time,
+            source: Some(entry.source),
+            pinned: entry.pinned,
+            tags: entry.tags,
+            formats: entry.formats,
+            copy_count: entry.copy_count,
+            last_used: entry.last_used,
+        }
+    }
+}
 
 pub trait IClipboardProvider {
     fn name(&self) -> &'static str;
-    fn list_entries(&self) -> Result<Vec<(Option<String>, ClipboardContent)>, String>;
+    fn list_entries(&self) -> Result<Vec<ProviderEntry>, String>;
+}
+
+impl<P: IClipboardProvider + ?Sized> IClipboardProvider for Box<P> {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn list_entries(&self) -> Result<Vec<ProviderEntry>, String> {
+        (**self).list_entries()
+    }
+}
+
+pub type BoxedClipboardProvider = Box<dyn IClipboardProvider + Send>;
+
+/// Providers reading a history kept outside the application. They list
+/// their entries again on every load, so the native store leaves them out.
+pub const EXTERNAL_PROVIDERS: [&str; 6] = [
+    "cliphist",
+    "file",
+    "clipman",
+    "copyq",
+    "gpaste",
+    "greenclip",
+];
+
+/// Whether entries from `source` belong to one of the [`EXTERNAL_PROVIDERS`].
+pub fn is_external(source: &str) -> bool {
+    EXTERNAL_PROVIDERS.contains(&source)
+}
+
+/// Builds a provider by its configured name. Without an explicit `path` the
+/// provider's default history location is used.
+pub fn provider_for(name: &str, path: Option<PathBuf>) -> Result<BoxedClipboardProvider, String> {
+    let provider: BoxedClipboardProvider = match name {
+        "cliphist" => Box::new(CliphistProvider),
+        "native" => {
+            Box::new(path.map_or_else(NativeStoreProvider::default, NativeStoreProvider::new))
+        }
+        "file" => Box::new(FileProvider::new(
+            path.ok_or("file provider needs the path of an exported history")?,
+        )),
+        "clipman" => Box::new(path.map_or_else(ClipmanProvider::default, ClipmanProvider::new)),
+        "copyq" => Box::new(CopyqProvider::new(
+            path.ok_or("copyq import needs the path of an exported .cpq file")?,
+        )),
+        "gpaste" => Box::new(path.map_or_else(GpasteProvider::default, GpasteProvider::new)),
+        "greenclip" => {
+            Box::new(path.map_or_else(GreenclipProvider::default, GreenclipProvider::new))
+        }
+        _ => return Err(format!("Unknown history provider: {name}")),
+    };
+
+    Ok(provider)
 }
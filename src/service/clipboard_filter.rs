@@ -0,0 +1,107 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Set by KeePassXC and Bitwarden with the value `secret` for content that
+/// must not be recorded.
+pub const KDE_PASSWORD_HINT: &str = "x-kde-passwordManagerHint";
+
+/// Formats whose mere presence marks the content as concealed.
+const CONCEALED_FORMATS: [&str; 2] = [
+    "application/x-nspasteboard-concealed-type",
+    "ExcludeClipboardContentFromMonitorProcessing",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExclusionRule {
+    /// A MIME type offered by the clipboard; a trailing `*` matches a prefix.
+    Mime(String),
+    /// A regular expression matched against the text content.
+    Pattern(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ClipboardFilter {
+    mime_types: Vec<String>,
+    patterns: Vec<Regex>,
+}
+
+impl ClipboardFilter {
+    pub fn new(rules: &[ExclusionRule]) -> Self {
+        let mut filter = Self::default();
+
+        for rule in rules {
+            match rule {
+                ExclusionRule::Mime(mime) => filter.mime_types.push(mime.clone()),
+                ExclusionRule::Pattern(pattern) => match Regex::new(pattern) {
+                    Ok(regex) => filter.patterns.push(regex),
+                    Err(err) => eprintln!("Ignoring invalid exclusion pattern {pattern}: {err}"),
+                },
+            }
+        }
+
+        filter
+    }
+
+    /// Whether the offered formats mark the content as a secret or match a
+    /// user exclusion. The KDE hint needs its value checked separately.
+    pub fn excludes_formats(&self, offered: &[String]) -> bool {
+        offered.iter().any(|mime| {
+            CONCEALED_FORMATS.contains(&mime.as_str())
+                || self.mime_types.iter().any(|rule| mime_matches(rule, mime))
+        })
+    }
+
+    pub fn excludes_text(&self, text: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern.is_match(text))
+    }
+}
+
+pub fn is_secret_hint(value: &[u8]) -> bool {
+    value.trim_ascii() == b"secret"
+}
+
+fn mime_matches(rule: &str, mime: &str) -> bool {
+    match rule.strip_suffix('*') {
+        Some(prefix) => mime.starts_with(prefix),
+        None => rule.eq_ignore_ascii_case(mime),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offered(mimes: &[&str]) -> Vec<String> {
+        mimes.iter().map(|mime| mime.to_string()).collect()
+    }
+
+    #[test]
+    fn excludes_concealed_and_configured_formats() {
+        let filter = ClipboardFilter::new(&[ExclusionRule::Mime("application/x-secret*".into())]);
+
+        assert!(filter.excludes_formats(&offered(&[
+            "text/plain",
+            "application/x-nspasteboard-concealed-type"
+        ])));
+        assert!(filter.excludes_formats(&offered(&["application/x-secret-note"])));
+        assert!(!filter.excludes_formats(&offered(&["text/plain", "text/html"])));
+    }
+
+    #[test]
+    fn excludes_text_matching_patterns() {
+        let filter = ClipboardFilter::new(&[
+            ExclusionRule::Pattern(r"^otpauth://".into()),
+            ExclusionRule::Pattern("(".into()),
+        ]);
+
+        assert!(filter.excludes_text("otpauth://totp/Example?secret=ABC"));
+        assert!(!filter.excludes_text("https://example.com"));
+    }
+
+    #[test]
+    fn reads_kde_hint_value() {
+        assert!(is_secret_hint(b"secret\n"));
+        assert!(!is_secret_hint(b"public"));
+    }
+}
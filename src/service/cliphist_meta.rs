@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::service::{
+    cliboard_history::ClipboardEntry,
+    cliboard_provider::{IClipboardProvider, ProviderEntry},
+    cliphist_provider::CliphistProvider,
+};
+
+/// What the application remembers about cliphist entries that cliphist does
+/// not store itself, keyed by cliphist id: when they were first seen and
+/// how often they were used.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CliphistMeta(BTreeMap<String, EntryMeta>);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntryMeta {
+    /// When the entry was first seen, or last copied again. Unknown for
+    /// entries that were listed by cliphist before the application saw them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Local>>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub copy_count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<DateTime<Local>>,
+}
+
+fn is_zero(count: &u32) -> bool {
+    *count == 0
+}
+
+impl CliphistMeta {
+    /// Remembers the cliphist entries among `entries`. Ids no longer in the
+    /// history are forgotten, and estimated times are left out so that they
+    /// are never taken for real ones.
+    pub fn from_entries(entries: &[ClipboardEntry]) -> Self {
+        let cliphist = CliphistProvider.name();
+
+        Self(
+            entries
+                .iter()
+                .filter(|entry| entry.source == cliphist)
+                .filter_map(|entry| {
+                    let id = entry.id.clone()?;
+                    let meta = EntryMeta {
+                        timestamp: (!entry.estimated_time).then_some(entry.timestamp),
+                        copy_count: entry.copy_count,
+                        last_used: entry.last_used,
+                    };
+                    let known =
+                        meta.timestamp.is_some() || meta.copy_count > 0 || meta.last_used.is_some();
+                    known.then_some((id, meta))
+                })
+                .collect(),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Fills in what is remembered about `entries` as listed by cliphist.
+    /// Entries never seen before keep the provider's guess.
+    pub fn apply(&self, entries: &mut [ProviderEntry]) {
+        for entry in entries {
+            let Some(meta) = entry.id.as_ref().and_then(|id| self.0.get(id)) else {
+                continue;
+            };
+            if let Some(timestamp) = meta.timestamp {
+                entry.timestamp = Some(timestamp);
+                entry.estimated_time = false;
+            }
+            entry.copy_count = meta.copy_count;
+            entry.last_used = meta.last_used;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::service::cliboard_history::{ClipboardContent, IClipboardEntry};
+
+    fn entry(id: &str, source: &str, days_ago: i64) -> ClipboardEntry {
+        let mut entry = ClipboardEntry::new(
+            ClipboardContent::Text(id.to_string()),
+            Some(source.to_string()),
+            Some(id.to_string()),
+        );
+        entry.timestamp = Local::now() - Duration::days(days_ago);
+        entry
+    }
+
+    #[test]
+    fn restores_when_known_entries_were_seen() {
+        let mut known = entry("7", "cliphist", 3);
+        known.copy_count = 4;
+        known.last_used = Some(Local::now());
+        let meta = CliphistMeta::from_entries(&[known.clone(), entry("8", "native", 1)]);
+
+        let mut listed = vec![
+            ProviderEntry::new(Some("9".to_string()), ClipboardContent::Text("new".into())),
+            ProviderEntry::new(Some("7".to_string()), ClipboardContent::Text("old".into())),
+            ProviderEntry::new(
+                Some("8".to_string()),
+                ClipboardContent::Text("other".into()),
+            ),
+        ];
+        meta.apply(&mut listed);
+
+        assert_eq!(listed[0].timestamp, None);
+        assert_eq!(listed[1].timestamp, Some(known.timestamp));
+        assert_eq!(listed[1].copy_count, 4);
+        assert_eq!(listed[1].last_used, known.last_used);
+        assert_eq!(listed[2].timestamp, None);
+    }
+
+    #[test]
+    fn leaves_out_estimated_times() {
+        let mut listed_only = entry("5", "cliphist", 0);
+        listed_only.estimated_time = true;
+        let mut used = entry("6", "cliphist", 0);
+        used.estimated_time = true;
+        used.copy_count = 2;
+        let meta = CliphistMeta::from_entries(&[listed_only, used]);
+
+        let mut listed = vec![
+            ProviderEntry::new(Some("5".to_string()), ClipboardContent::Text("a".into())),
+            ProviderEntry::new(Some("6".to_string()), ClipboardContent::Text("b".into())),
+        ];
+        meta.apply(&mut listed);
+
+        assert!(!meta.0.contains_key("5"));
+        assert_eq!(listed[1].timestamp, None);
+        assert_eq!(listed[1].copy_count, 2);
+    }
+}
@@ -1,19 +1,138 @@
-use std::process::Command;
+use std::{
+    ffi::OsStr,
+    fs,
+    io::Write,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use chrono::{DateTime, Local};
+use gtk::glib;
 
 use crate::service::cliboard_history::ClipboardContent;
-use crate::service::cliboard_provider::IClipboardProvider;
+use crate::service::cliboard_provider::{IClipboardProvider, ProviderEntry};
+use crate::service::config_service;
 
 const COMMAND: &str = "cliphist";
+const DB_PATH_ENV: &str = "CLIPHIST_DB_PATH";
+/// The program of the usual `wl-paste --watch cliphist store` watchers.
+const WATCHER: &str = "wl-paste";
+/// Exists while the watchers are stopped and holds the newest cliphist id
+/// from before, so a run that crashed can continue them on the next start.
+const PAUSE_MARKER: &str = "cliphist-paused";
 
 #[derive(Clone, Copy)]
 pub struct CliphistProvider;
 
+impl CliphistProvider {
+    /// Returns the raw stored payload of an entry, as listed by `cliphist list`.
+    pub fn decode(&self, id: &str) -> Option<Vec<u8>> {
+        let output = Command::new(COMMAND).arg("decode").arg(id).output().ok()?;
+
+        if output.status.success() {
+            return Some(output.stdout);
+        }
+
+        None
+    }
+
+    /// Removes an entry from the cliphist database.
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        let mut child = Command::new(COMMAND)
+            .arg("delete")
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("{COMMAND} delete failed: {err}"))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "{id}\t").map_err(|err| format!("{COMMAND} delete failed: {err}"))?;
+        }
+
+        let status = child
+            .wait()
+            .map_err(|err| format!("{COMMAND} delete failed: {err}"))?;
+
+        if !status.success() {
+            return Err(format!("{COMMAND} delete non-zero exit: {status}"));
+        }
+
+        Ok(())
+    }
+
+    /// Stops the current user's `wl-paste --watch cliphist` processes, so
+    /// nothing is stored while recording is paused.
+    pub fn pause_watchers(&self) -> Result<(), String> {
+        // Empty when the history cannot be listed: nothing is dropped then.
+        let newest = match self.list_entries() {
+            Ok(entries) => entries
+                .first()
+                .and_then(|entry| entry.id.as_deref()?.parse::<u64>().ok())
+                .unwrap_or(0)
+                .to_string(),
+            Err(_) => String::new(),
+        };
+
+        let marker = pause_marker();
+        if let Some(dir) = marker.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|err| format!("Cannot create {}: {err}", dir.display()))?;
+        }
+        fs::write(&marker, newest)
+            .map_err(|err| format!("Cannot write {}: {err}", marker.display()))?;
+
+        signal_watchers("STOP")
+    }
+
+    /// Continues the stopped watchers. wl-paste then handles the selection
+    /// changes it missed and stores what is on the clipboard, although that
+    /// was copied while paused. Returns the newest id from before the pause,
+    /// to find those entries with [`Self::ids_newer_than`].
+    pub fn resume_watchers(&self) -> Result<Option<u64>, String> {
+        signal_watchers("CONT")?;
+
+        let marker = pause_marker();
+        let newest = fs::read_to_string(&marker)
+            .ok()
+            .and_then(|id| id.trim().parse().ok());
+        let _ = fs::remove_file(marker);
+        Ok(newest)
+    }
+
+    /// Whether an earlier run stopped the watchers and never continued them.
+    pub fn watchers_left_paused(&self) -> bool {
+        pause_marker().exists()
+    }
+
+    /// The ids of the entries stored after the entry `id`.
+    pub fn ids_newer_than(&self, id: u64) -> Result<Vec<String>, String> {
+        let entries = self.list_entries()?;
+        Ok(ids_after(
+            entries.iter().filter_map(|entry| entry.id.as_deref()),
+            id,
+        ))
+    }
+
+    /// cliphist does not record when entries were stored, only their order.
+    /// The database is written on every store, so its modification time is
+    /// the time of the newest entry. Only a guess for entries the native
+    /// store has not seen yet; see [`CliphistMeta`](crate::service::cliphist_meta::CliphistMeta).
+    fn last_store_time(&self) -> Option<DateTime<Local>> {
+        let db_path = std::env::var_os(DB_PATH_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| glib::user_cache_dir().join(COMMAND).join("db"));
+
+        let modified = fs::metadata(db_path).ok()?.modified().ok()?;
+        Some(DateTime::from(modified))
+    }
+}
+
 impl IClipboardProvider for CliphistProvider {
     fn name(&self) -> &'static str {
         COMMAND
     }
 
-    fn list_entries(&self) -> Result<Vec<(Option<String>, ClipboardContent)>, String> {
+    fn list_entries(&self) -> Result<Vec<ProviderEntry>, String> {
         let output = Command::new(COMMAND)
             .arg("list")
             .output()
@@ -52,19 +171,143 @@ impl IClipboardProvider for CliphistProvider {
                 .filter(|value| !value.is_empty());
 
             let content_type = if content.starts_with("[[ binary data") {
-                ClipboardContent::Image(())
+                ClipboardContent::Image(None)
             } else {
                 ClipboardContent::Text(content)
             };
 
-            entries.push((raw_id, content_type));
+            entries.push(ProviderEntry::new(raw_id, content_type));
+        }
+
+        if let Some(newest) = entries.first_mut() {
+            newest.timestamp = self.last_store_time();
+            newest.estimated_time = true;
         }
 
         Ok(entries)
     }
 }
 
+fn pause_marker() -> PathBuf {
+    config_service::app_runtime_dir().join(PAUSE_MARKER)
+}
+
+/// Sends `signal` to the watchers of the current user. Other users'
+/// processes and unrelated ones mentioning cliphist are left alone.
+fn signal_watchers(signal: &str) -> Result<(), String> {
+    let pids = watcher_pids();
+    if pids.is_empty() {
+        return Ok(());
+    }
+
+    let status = Command::new("kill")
+        .arg(format!("-{signal}"))
+        .args(&pids)
+        .status()
+        .map_err(|err| format!("kill failed: {err}"))?;
+    if !status.success() {
+        return Err(format!("kill non-zero exit: {status}"));
+    }
+
+    Ok(())
+}
+
+fn watcher_pids() -> Vec<String> {
+    let Ok(uid) = fs::metadata("/proc/self").map(|metadata| metadata.uid()) else {
+        return Vec::new();
+    };
+    let Ok(processes) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    processes
+        .flatten()
+        .filter(|process| {
+            process
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.bytes().all(|byte| byte.is_ascii_digit()))
+        })
+        .filter(|process| {
+            process
+                .metadata()
+                .is_ok_and(|metadata| metadata.uid() == uid)
+        })
+        .filter(|process| {
+            fs::read(process.path().join("cmdline")).is_ok_and(|cmdline| {
+                let cmdline = String::from_utf8_lossy(&cmdline);
+                let args: Vec<&str> = cmdline.split('\0').filter(|arg| !arg.is_empty()).collect();
+                is_watcher(&args)
+            })
+        })
+        .filter_map(|process| process.file_name().into_string().ok())
+        .collect()
+}
+
+/// Whether `args` run cliphist from `wl-paste --watch`.
+fn is_watcher(args: &[&str]) -> bool {
+    let program_is = |arg: &str, name: &str| Path::new(arg).file_name() == Some(OsStr::new(name));
+
+    let Some((program, rest)) = args.split_first() else {
+        return false;
+    };
+    let Some(watch) = rest
+        .iter()
+        .position(|arg| *arg == "--watch" || *arg == "-w")
+    else {
+        return false;
+    };
+
+    program_is(program, WATCHER)
+        && rest
+            .get(watch + 1)
+            .is_some_and(|arg| program_is(arg, COMMAND))
+}
+
 fn clean_bytes_to_string(bytes: &[u8]) -> String {
     let filtered: Vec<u8> = bytes.iter().copied().filter(|byte| *byte != 0).collect();
     String::from_utf8_lossy(&filtered).trim().to_string()
 }
+
+/// The ids among `ids` that are numbered after `id`; cliphist numbers its
+/// entries in the order they were stored.
+fn ids_after<'a>(ids: impl Iterator<Item = &'a str>, id: u64) -> Vec<String> {
+    ids.filter(|other| other.parse::<u64>().is_ok_and(|other| other > id))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_ids_stored_after_another() {
+        let ids = ["12", "11", "x", "9", "10"];
+        assert_eq!(ids_after(ids.into_iter(), 10), ["12", "11"]);
+        assert!(ids_after(ids.into_iter(), 12).is_empty());
+    }
+
+    #[test]
+    fn recognizes_only_cliphist_watchers() {
+        assert!(is_watcher(&["wl-paste", "--watch", "cliphist", "store"]));
+        assert!(is_watcher(&[
+            "/usr/bin/wl-paste",
+            "--type",
+            "image",
+            "-w",
+            "/usr/bin/cliphist",
+            "store"
+        ]));
+
+        assert!(!is_watcher(&[
+            "wl-paste",
+            "--watch",
+            "notify-send",
+            "cliphist"
+        ]));
+        assert!(!is_watcher(&["vim", "wl-paste --watch cliphist"]));
+        assert!(!is_watcher(&["wl-paste", "cliphist"]));
+        assert!(!is_watcher(&[]));
+    }
+}
@@ -0,0 +1,70 @@
+use std::{fs, path::PathBuf};
+
+use gtk::glib;
+
+use crate::service::{
+    cliboard_history::ClipboardContent,
+    cliboard_provider::{IClipboardProvider, ProviderEntry},
+};
+
+const NAME: &str = "clipman";
+
+/// Reads clipman's history, a JSON array of strings stored oldest first.
+#[derive(Clone)]
+pub struct ClipmanProvider {
+    path: PathBuf,
+}
+
+impl ClipmanProvider {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl Default for ClipmanProvider {
+    fn default() -> Self {
+        Self::new(glib::user_data_dir().join("clipman.json"))
+    }
+}
+
+impl IClipboardProvider for ClipmanProvider {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn list_entries(&self) -> Result<Vec<ProviderEntry>, String> {
+        let data = fs::read_to_string(&self.path)
+            .map_err(|err| format!("{NAME}: cannot read {}: {err}", self.path.display()))?;
+        let items: Vec<String> =
+            serde_json::from_str(&data).map_err(|err| format!("{NAME}: invalid history: {err}"))?;
+
+        Ok(items
+            .into_iter()
+            .rev()
+            .filter(|item| !item.trim().is_empty())
+            .map(|item| ProviderEntry::new(None, ClipboardContent::Text(item)))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_newest_first() {
+        let provider = ClipmanProvider::new(PathBuf::from(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/clipman.json"
+        )));
+
+        let texts: Vec<String> = provider
+            .list_entries()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.content.as_text())
+            .collect();
+
+        assert_eq!(texts, ["third\nwith a newline", "second", "first"]);
+    }
+}
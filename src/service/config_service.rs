@@ -0,0 +1,223 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::PathBuf,
+};
+
+use gtk::glib;
+use serde::{Deserialize, Serialize};
+
+use crate::service::{
+    auto_paste::{PasteBackend, PasteKeys},
+    cliboard_provider::{self, BoxedClipboardProvider},
+    clipboard_filter::ExclusionRule,
+    content_filter::ContentFilter,
+    entry_sort::SortMode,
+    popup_placement::PopupAnchor,
+    selection_sync::SyncDirection,
+    time_format::TimeStyle,
+};
+
+const APP_DIR: &str = "clipboard_manager";
+const CONFIG_FILE: &str = "config.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+impl ProviderConfig {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            path: None,
+            enabled: true,
+        }
+    }
+
+    pub fn build(&self) -> Result<BoxedClipboardProvider, String> {
+        cliboard_provider::provider_for(&self.name, self.path.clone())
+    }
+
+    pub fn label(&self) -> String {
+        match &self.path {
+            Some(path) => format!("{} ({})", self.name, path.display()),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// How the native history store is encrypted at rest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreEncryption {
+    #[default]
+    None,
+    /// Key derived from a passphrase asked for on startup.
+    Passphrase,
+    /// Random key kept in the Secret Service keyring.
+    Keyring,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub providers: Vec<ProviderConfig>,
+    pub time_style: TimeStyle,
+    /// Minutes after which sensitive entries are removed; 0 keeps them.
+    pub sensitive_expiry_minutes: u64,
+    /// Also delete expired sensitive entries from cliphist. Otherwise they
+    /// are only dropped from the list and come back on the next load.
+    pub expire_from_cliphist: bool,
+    /// Clipboard content matching any of these rules is never recorded.
+    pub exclusions: Vec<ExclusionRule>,
+    /// Also stop the cliphist `wl-paste --watch` processes while paused.
+    pub pause_cliphist_watcher: bool,
+    pub encryption: StoreEncryption,
+    /// Minutes the window may stay inactive before an encrypted store locks;
+    /// 0 never locks.
+    pub auto_lock_minutes: u64,
+    /// Also record the PRIMARY selection (middle-click paste).
+    pub track_primary: bool,
+    /// How long the PRIMARY selection must stay unchanged to be recorded.
+    pub primary_debounce_ms: u64,
+    /// Mirrors new text between the CLIPBOARD and PRIMARY selections.
+    pub selection_sync: SyncDirection,
+    /// Minimum number of characters for text to be mirrored.
+    pub sync_min_length: usize,
+    /// Accelerators by action name, for the shortcuts changed by the user.
+    pub keybindings: BTreeMap<String, Vec<String>>,
+    /// Hide the window once an entry was copied.
+    pub close_after_copy: bool,
+    /// After hiding, paste into the window that had the focus before.
+    pub auto_paste: bool,
+    pub paste_keys: PasteKeys,
+    pub paste_backend: PasteBackend,
+    /// How long to wait after hiding before pasting, so the previous window
+    /// has the focus again.
+    pub paste_delay_ms: u64,
+    /// Open as a small popup that hides when it loses the focus.
+    pub popup_mode: bool,
+    pub popup_anchor: PopupAnchor,
+    pub popup_width: i32,
+    pub popup_height: i32,
+    /// The kinds chosen in the search bar; empty shows all.
+    pub content_filters: BTreeSet<ContentFilter>,
+    pub sort_mode: SortMode,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            providers: vec![
+                ProviderConfig::new("cliphist"),
+                ProviderConfig::new("native"),
+            ],
+            time_style: TimeStyle::default(),
+            sensitive_expiry_minutes: 0,
+            expire_from_cliphist: false,
+            exclusions: Vec::new(),
+            pause_cliphist_watcher: false,
+            encryption: StoreEncryption::None,
+            auto_lock_minutes: 10,
+            track_primary: false,
+            primary_debounce_ms: 500,
+            selection_sync: SyncDirection::Off,
+            sync_min_length: 2,
+            keybindings: BTreeMap::new(),
+            close_after_copy: false,
+            auto_paste: false,
+            paste_keys: PasteKeys::default(),
+            paste_backend: PasteBackend::default(),
+            paste_delay_ms: 200,
+            popup_mode: false,
+            popup_anchor: PopupAnchor::default(),
+            popup_width: 480,
+            popup_height: 420,
+            content_filters: BTreeSet::new(),
+            sort_mode: SortMode::default(),
+        }
+    }
+}
+
+impl AppConfig {
+    pub fn enabled_providers(&self) -> Vec<BoxedClipboardProvider> {
+        self.providers
+            .iter()
+            .filter(|provider| provider.enabled)
+            .filter_map(|provider| match provider.build() {
+                Ok(built) => Some(built),
+                Err(err) => {
+                    eprintln!("Skipping provider {}: {err}", provider.label());
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.providers
+            .iter()
+            .any(|provider| provider.enabled && provider.name == name)
+    }
+}
+
+pub struct ConfigService {
+    path: PathBuf,
+}
+
+impl ConfigService {
+    pub fn new() -> Self {
+        Self {
+            path: glib::user_config_dir().join(APP_DIR).join(CONFIG_FILE),
+        }
+    }
+
+    /// Loads the configuration, falling back to the defaults when the file is
+    /// missing or unreadable.
+    pub fn load(&self) -> AppConfig {
+        let Ok(data) = fs::read_to_string(&self.path) else {
+            return AppConfig::default();
+        };
+
+        serde_json::from_str(&data).unwrap_or_else(|err| {
+            eprintln!("Invalid config {}: {err}", self.path.display());
+            AppConfig::default()
+        })
+    }
+
+    pub fn save(&self, config: &AppConfig) -> Result<(), String> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|err| format!("Cannot create {}: {err}", dir.display()))?;
+        }
+
+        let data = serde_json::to_string_pretty(config)
+            .map_err(|err| format!("Config encode failed: {err}"))?;
+        fs::write(&self.path, data)
+            .map_err(|err| format!("Cannot write {}: {err}", self.path.display()))
+    }
+}
+
+impl Default for ConfigService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+pub fn app_data_dir() -> PathBuf {
+    glib::user_data_dir().join(APP_DIR)
+}
+
+/// Files that must not outlive the session, such as dragged images.
+pub fn app_runtime_dir() -> PathBuf {
+    glib::user_runtime_dir().join(APP_DIR)
+}
@@ -0,0 +1,105 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::service::cliboard_history::ClipboardEntry;
+
+/// A kind of entry the list can be narrowed to. Kinds overlap: a link is
+/// also text, a pinned entry also has a content kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFilter {
+    Text,
+    Images,
+    Links,
+    Files,
+    Code,
+    Pinned,
+}
+
+impl ContentFilter {
+    pub const ALL: [Self; 6] = [
+        Self::Text,
+        Self::Images,
+        Self::Links,
+        Self::Files,
+        Self::Code,
+        Self::Pinned,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Text => "Text",
+            Self::Images => "Images",
+            Self::Links => "Links",
+            Self::Files => "Files",
+            Self::Code => "Code",
+            Self::Pinned => "Pinned",
+        }
+    }
+
+    pub fn matches(self, entry: &ClipboardEntry) -> bool {
+        match self {
+            Self::Text => entry.content.is_text(),
+            Self::Images => entry.content.is_image(),
+            Self::Links => entry.content.is_link(),
+            Self::Files => entry.content.is_files(),
+            Self::Code => entry.content.is_code(),
+            Self::Pinned => entry.pinned,
+        }
+    }
+
+    /// The kinds `entry` belongs to.
+    pub fn kinds_of(entry: &ClipboardEntry) -> BTreeSet<Self> {
+        Self::ALL
+            .into_iter()
+            .filter(|filter| filter.matches(entry))
+            .collect()
+    }
+
+    /// How many of `entries` each kind matches, in the order of `ALL`.
+    pub fn counts(entries: &[ClipboardEntry]) -> [usize; 6] {
+        Self::ALL.map(|filter| entries.iter().filter(|entry| filter.matches(entry)).count())
+    }
+}
+
+/// Whether an entry of `kinds` passes the chosen filters: any of them, or
+/// everything when none is chosen.
+pub fn passes(active: &BTreeSet<ContentFilter>, kinds: &BTreeSet<ContentFilter>) -> bool {
+    active.is_empty() || !active.is_disjoint(kinds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::cliboard_history::{ClipboardContent, IClipboardEntry};
+
+    #[test]
+    fn combines_chosen_kinds() {
+        let mut link = ClipboardEntry::new(
+            ClipboardContent::Text("https://example.com".to_string()),
+            None,
+            None,
+        );
+        link.pinned = true;
+        let image = ClipboardEntry::new(ClipboardContent::Image(None), None, None);
+
+        let kinds = ContentFilter::kinds_of(&link);
+        assert_eq!(
+            kinds,
+            BTreeSet::from([
+                ContentFilter::Text,
+                ContentFilter::Links,
+                ContentFilter::Pinned
+            ])
+        );
+
+        assert!(passes(&BTreeSet::new(), &kinds));
+        assert!(passes(
+            &BTreeSet::from([ContentFilter::Images, ContentFilter::Pinned]),
+            &kinds
+        ));
+        assert!(!passes(&BTreeSet::from([ContentFilter::Code]), &kinds));
+        assert_eq!(ContentFilter::counts(&[link, image]), [1, 1, 1, 0, 0, 1]);
+    }
+}
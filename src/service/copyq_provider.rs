@@ -0,0 +1,243 @@
+use std::{fs, path::PathBuf, sync::Arc};
+
+use crate::service::{
+    cliboard_history::ClipboardContent,
+    cliboard_provider::{IClipboardProvider, ProviderEntry},
+    file_list,
+};
+
+const NAME: &str = "copyq";
+const HEADER_PREFIX: &[u8] = b"CopyQ v";
+const NULL_LENGTH: u32 = u32::MAX;
+
+const TYPE_BOOL: u32 = 1;
+const TYPE_INT: u32 = 2;
+const TYPE_UINT: u32 = 3;
+const TYPE_LONG_LONG: u32 = 4;
+const TYPE_ULONG_LONG: u32 = 5;
+const TYPE_DOUBLE: u32 = 6;
+const TYPE_CHAR: u32 = 7;
+const TYPE_MAP: u32 = 8;
+const TYPE_LIST: u32 = 9;
+const TYPE_STRING: u32 = 10;
+const TYPE_STRING_LIST: u32 = 11;
+const TYPE_BYTE_ARRAY: u32 = 12;
+
+/// Reads a CopyQ export (`.cpq`), a Qt `QDataStream` holding a version header
+/// followed by nested variant maps and lists. Every map keyed by MIME types is
+/// treated as one clipboard item; items keep the order CopyQ shows them in.
+#[derive(Clone)]
+pub struct CopyqProvider {
+    path: PathBuf,
+}
+
+impl CopyqProvider {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl IClipboardProvider for CopyqProvider {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn list_entries(&self) -> Result<Vec<ProviderEntry>, String> {
+        let data = fs::read(&self.path)
+            .map_err(|err| format!("{NAME}: cannot read {}: {err}", self.path.display()))?;
+        let mut stream = DataStream { data: &data };
+
+        let header = stream.read_byte_array()?.unwrap_or_default();
+        if !header.starts_with(HEADER_PREFIX) {
+            return Err(format!("{NAME}: not a CopyQ export"));
+        }
+
+        let mut entries = Vec::new();
+        while !stream.data.is_empty() {
+            collect_items(&stream.read_variant()?, &mut entries);
+        }
+
+        Ok(entries)
+    }
+}
+
+enum Variant {
+    Map(Vec<(String, Variant)>),
+    List(Vec<Variant>),
+    Bytes(Vec<u8>),
+    Other,
+}
+
+fn collect_items(variant: &Variant, entries: &mut Vec<ProviderEntry>) {
+    match variant {
+        Variant::Map(pairs) => {
+            if let Some(content) = item_content(pairs) {
+                entries.push(ProviderEntry::new(None, content));
+                return;
+            }
+
+            for (_, value) in pairs {
+                collect_items(value, entries);
+            }
+        }
+        Variant::List(values) => {
+            for value in values {
+                collect_items(value, entries);
+            }
+        }
+        Variant::Bytes(_) | Variant::Other => {}
+    }
+}
+
+fn item_content(pairs: &[(String, Variant)]) -> Option<ClipboardContent> {
+    let bytes_for = |predicate: &dyn Fn(&str) -> bool| {
+        pairs.iter().find_map(|(key, value)| match value {
+            Variant::Bytes(bytes) if predicate(key) && !bytes.is_empty() => Some(bytes),
+            _ => None,
+        })
+    };
+
+    if let Some(image) = bytes_for(&|mime| mime.starts_with("image/")) {
+        return Some(ClipboardContent::Image(Some(Arc::from(image.as_slice()))));
+    }
+
+    if let Some(uris) = bytes_for(&|mime| mime == file_list::URI_LIST_MIME)
+        .map(|data| file_list::parse_uri_list(&String::from_utf8_lossy(data)))
+        .filter(|uris| !uris.is_empty())
+    {
+        return Some(ClipboardContent::Files {
+            uris,
+            operation: None,
+        });
+    }
+
+    bytes_for(&|mime| mime == "text/plain" || mime.starts_with("text/plain;"))
+        .map(|text| ClipboardContent::Text(String::from_utf8_lossy(text).to_string()))
+        .filter(|content| !content.as_text().trim().is_empty())
+}
+
+struct DataStream<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DataStream<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.data.len() < len {
+            return Err(format!("{NAME}: truncated export"));
+        }
+
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        let bytes: [u8; 4] = self.take(4)?.try_into().expect("took 4 bytes");
+        Ok(u32::from_be_bytes(bytes))
+    }
+
+    fn read_byte_array(&mut self) -> Result<Option<Vec<u8>>, String> {
+        match self.read_u32()? {
+            NULL_LENGTH => Ok(None),
+            len => Ok(Some(self.take(len as usize)?.to_vec())),
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, String> {
+        let len = self.read_u32()?;
+        if len == NULL_LENGTH {
+            return Ok(String::new());
+        }
+
+        let units: Vec<u16> = self
+            .take(len as usize)?
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+
+        Ok(String::from_utf16_lossy(&units))
+    }
+
+    fn read_variant(&mut self) -> Result<Variant, String> {
+        let kind = self.read_u32()?;
+        let _is_null = self.take(1)?;
+
+        let variant = match kind {
+            TYPE_BOOL => {
+                self.take(1)?;
+                Variant::Other
+            }
+            TYPE_CHAR => {
+                self.take(2)?;
+                Variant::Other
+            }
+            TYPE_INT | TYPE_UINT => {
+                self.take(4)?;
+                Variant::Other
+            }
+            TYPE_LONG_LONG | TYPE_ULONG_LONG | TYPE_DOUBLE => {
+                self.take(8)?;
+                Variant::Other
+            }
+            TYPE_STRING => {
+                self.read_string()?;
+                Variant::Other
+            }
+            TYPE_STRING_LIST => {
+                for _ in 0..self.read_u32()? {
+                    self.read_string()?;
+                }
+                Variant::Other
+            }
+            TYPE_BYTE_ARRAY => Variant::Bytes(self.read_byte_array()?.unwrap_or_default()),
+            TYPE_LIST => {
+                let count = self.read_u32()?;
+                let mut values = Vec::new();
+                for _ in 0..count {
+                    values.push(self.read_variant()?);
+                }
+                Variant::List(values)
+            }
+            TYPE_MAP => {
+                let count = self.read_u32()?;
+                let mut pairs = Vec::new();
+                for _ in 0..count {
+                    let key = self.read_string()?;
+                    pairs.push((key, self.read_variant()?));
+                }
+                Variant::Map(pairs)
+            }
+            _ => return Err(format!("{NAME}: unsupported variant type {kind}")),
+        };
+
+        Ok(variant)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> CopyqProvider {
+        CopyqProvider::new(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures")
+                .join(name),
+        )
+    }
+
+    #[test]
+    fn reads_items_from_all_tabs() {
+        let entries = fixture("copyq_export.cpq").list_entries().unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].content.as_text(), "first item");
+        assert_eq!(entries[1].content.image_bytes(), Some(&b"\x89PNG fake"[..]));
+        assert_eq!(entries[2].content.as_text(), "from the notes tab");
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(fixture("clipman.json").list_entries().is_err());
+    }
+}
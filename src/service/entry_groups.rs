@@ -0,0 +1,138 @@
+use chrono::{DateTime, Datelike, Local};
+
+use crate::service::cliboard_history::ClipboardEntry;
+
+/// The sections the history list is split into, in display order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EntryGroup {
+    Pinned,
+    Today,
+    Yesterday,
+    ThisWeek,
+    Older,
+    /// Everything not pinned, for sort modes that do not go by age.
+    Unpinned,
+}
+
+impl EntryGroup {
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Pinned => "Pinned",
+            Self::Today => "Today",
+            Self::Yesterday => "Yesterday",
+            Self::ThisWeek => "This Week",
+            Self::Older => "Older",
+            Self::Unpinned => "History",
+        }
+    }
+
+    /// The section `entry` is listed under at `now`. Pinned entries stay
+    /// together whatever their age; the rest go by calendar day, or all in
+    /// one section unless `by_day`.
+    pub fn of(entry: &ClipboardEntry, now: DateTime<Local>, by_day: bool) -> Self {
+        if entry.pinned {
+            return Self::Pinned;
+        }
+        if !by_day {
+            return Self::Unpinned;
+        }
+
+        let days = now
+            .date_naive()
+            .signed_duration_since(entry.timestamp.date_naive())
+            .num_days();
+
+        match days {
+            ..=0 => Self::Today,
+            1 => Self::Yesterday,
+            _ if entry.timestamp.iso_week() == now.iso_week() => Self::ThisWeek,
+            _ => Self::Older,
+        }
+    }
+}
+
+/// Orders `entries` by section, keeping the history order within each one.
+/// Each entry comes with its index in `entries`.
+pub fn group_entries(
+    entries: &[ClipboardEntry],
+    now: DateTime<Local>,
+    by_day: bool,
+) -> Vec<(EntryGroup, usize, &ClipboardEntry)> {
+    let mut grouped: Vec<_> = entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| (EntryGroup::of(entry, now, by_day), index, entry))
+        .collect();
+    grouped.sort_by_key(|(group, _, _)| *group);
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+    use crate::service::cliboard_history::{ClipboardContent, IClipboardEntry};
+
+    fn entry(text: &str, timestamp: DateTime<Local>, pinned: bool) -> ClipboardEntry {
+        let mut entry = ClipboardEntry::new(ClipboardContent::Text(text.to_string()), None, None);
+        entry.timestamp = timestamp;
+        entry.pinned = pinned;
+        entry
+    }
+
+    #[test]
+    fn groups_by_calendar_day_with_pinned_first() {
+        // A Thursday afternoon.
+        let now = Local.with_ymd_and_hms(2025, 3, 20, 16, 45, 0).unwrap();
+        let entries = [
+            entry("morning", now - Duration::hours(10), false),
+            entry("old pin", now - Duration::days(90), true),
+            entry("last night", now - Duration::hours(17), false),
+            entry("monday", now - Duration::days(3), false),
+            entry("last week", now - Duration::days(5), false),
+            entry("just now", now, false),
+        ];
+
+        let grouped: Vec<_> = group_entries(&entries, now, true)
+            .into_iter()
+            .map(|(group, _, entry)| (group, entry.content.as_text()))
+            .collect();
+
+        assert_eq!(
+            grouped,
+            [
+                (EntryGroup::Pinned, "old pin".to_string()),
+                (EntryGroup::Today, "morning".to_string()),
+                (EntryGroup::Today, "just now".to_string()),
+                (EntryGroup::Yesterday, "last night".to_string()),
+                (EntryGroup::ThisWeek, "monday".to_string()),
+                (EntryGroup::Older, "last week".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_only_pinned_apart_when_not_by_day() {
+        let now = Local.with_ymd_and_hms(2025, 3, 20, 16, 45, 0).unwrap();
+        let entries = [
+            entry("today", now, false),
+            entry("pin", now - Duration::days(40), true),
+            entry("old", now - Duration::days(40), false),
+        ];
+
+        let grouped: Vec<_> = group_entries(&entries, now, false)
+            .into_iter()
+            .map(|(group, index, _)| (group, index))
+            .collect();
+
+        assert_eq!(
+            grouped,
+            [
+                (EntryGroup::Pinned, 1),
+                (EntryGroup::Unpinned, 0),
+                (EntryGroup::Unpinned, 2),
+            ]
+        );
+    }
+}
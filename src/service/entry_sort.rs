@@ -0,0 +1,164 @@
+use std::cmp::Ordering;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::service::cliboard_history::{ClipboardContent, ClipboardEntry};
+
+/// How entries are ordered within each group of the list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    /// Recently and often used entries first, like a browser's address bar.
+    #[default]
+    Frecency,
+    Recent,
+    Frequent,
+    Alphabetical,
+    Size,
+}
+
+impl SortMode {
+    pub const ALL: [Self; 5] = [
+        Self::Frecency,
+        Self::Recent,
+        Self::Frequent,
+        Self::Alphabetical,
+        Self::Size,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Frecency => "Frecency",
+            Self::Recent => "Most Recent",
+            Self::Frequent => "Most Used",
+            Self::Alphabetical => "Alphabetical",
+            Self::Size => "Largest",
+        }
+    }
+
+    /// Whether the list is split by age; other orders would scatter entries
+    /// over day sections that no longer read in order.
+    pub fn groups_by_day(self) -> bool {
+        matches!(self, Self::Frecency | Self::Recent)
+    }
+
+    /// Orders `a` before `b` when it sorts first. Equal entries keep their
+    /// history order, so `Recent` is always equal.
+    pub fn compare(self, a: &ClipboardEntry, b: &ClipboardEntry, now: DateTime<Local>) -> Ordering {
+        match self {
+            Self::Frecency => frecency(b, now).cmp(&frecency(a, now)),
+            Self::Recent => Ordering::Equal,
+            Self::Frequent => b.copy_count.cmp(&a.copy_count),
+            Self::Alphabetical => sort_text(a).cmp(&sort_text(b)),
+            Self::Size => size(b).cmp(&size(a)),
+        }
+    }
+}
+
+/// Each use counts more the more recent the entry was last seen; copying
+/// it again counts as one use more.
+fn frecency(entry: &ClipboardEntry, now: DateTime<Local>) -> u64 {
+    let seen = entry
+        .last_used
+        .max(Some(entry.timestamp))
+        .unwrap_or(entry.timestamp);
+    let weight = match now.signed_duration_since(seen).num_days() {
+        ..=4 => 100,
+        5..=14 => 70,
+        15..=31 => 50,
+        32..=90 => 30,
+        _ => 10,
+    };
+
+    (u64::from(entry.copy_count) + 1) * weight
+}
+
+fn sort_text(entry: &ClipboardEntry) -> String {
+    entry.content.as_text().trim().to_lowercase()
+}
+
+/// The stored size in bytes: every recorded format, or the content alone.
+fn size(entry: &ClipboardEntry) -> usize {
+    if !entry.formats.is_empty() {
+        return entry.formats.iter().map(|format| format.data.len()).sum();
+    }
+
+    match &entry.content {
+        ClipboardContent::Text(text) => text.len(),
+        ClipboardContent::Image(bytes) => bytes.as_ref().map_or(0, |bytes| bytes.len()),
+        ClipboardContent::Rich { plain, html, rtf } => {
+            plain.len()
+                + html.as_ref().map_or(0, String::len)
+                + rtf.as_ref().map_or(0, |rtf| rtf.data.len())
+        }
+        ClipboardContent::Files { uris, .. } => uris.iter().map(String::len).sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::service::cliboard_history::IClipboardEntry;
+
+    fn entry(text: &str, days_ago: i64, copy_count: u32, now: DateTime<Local>) -> ClipboardEntry {
+        let mut entry = ClipboardEntry::new(ClipboardContent::Text(text.to_string()), None, None);
+        entry.timestamp = now - Duration::days(days_ago);
+        entry.copy_count = copy_count;
+        entry
+    }
+
+    fn sorted(mode: SortMode, entries: &[ClipboardEntry], now: DateTime<Local>) -> Vec<String> {
+        let mut entries: Vec<_> = entries.iter().collect();
+        entries.sort_by(|a, b| mode.compare(a, b, now));
+        entries
+            .iter()
+            .map(|entry| entry.content.as_text())
+            .collect()
+    }
+
+    #[test]
+    fn orders_by_each_mode() {
+        let now = Local::now();
+        let entries = [
+            entry("new", 0, 0, now),
+            entry("Favorite", 40, 6, now),
+            entry("a much longer entry", 10, 1, now),
+        ];
+
+        assert_eq!(
+            sorted(SortMode::Recent, &entries, now),
+            ["new", "Favorite", "a much longer entry"]
+        );
+        assert_eq!(
+            sorted(SortMode::Frequent, &entries, now),
+            ["Favorite", "a much longer entry", "new"]
+        );
+        assert_eq!(
+            sorted(SortMode::Alphabetical, &entries, now),
+            ["a much longer entry", "Favorite", "new"]
+        );
+        assert_eq!(
+            sorted(SortMode::Size, &entries, now),
+            ["a much longer entry", "Favorite", "new"]
+        );
+    }
+
+    #[test]
+    fn frecency_blends_use_and_age() {
+        let now = Local::now();
+        let entries = [
+            entry("fresh", 0, 0, now),
+            entry("used last week", 9, 2, now),
+            entry("used long ago", 200, 8, now),
+        ];
+
+        // 3 × 70 beats 1 × 100, while 9 × 10 does not.
+        assert_eq!(
+            sorted(SortMode::Frecency, &entries, now),
+            ["used last week", "fresh", "used long ago"]
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::{fs, path::PathBuf};
+
+use gtk::glib;
+
+pub const URI_LIST_MIME: &str = "text/uri-list";
+/// Nautilus and other GTK file managers: the operation (`copy` or `cut`) on
+/// the first line, then one URI per line.
+pub const GNOME_COPIED_FILES_MIME: &str = "x-special/gnome-copied-files";
+
+/// A copied file as shown in the history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSummary {
+    pub name: String,
+    pub path: Option<PathBuf>,
+    pub size: Option<u64>,
+    pub exists: bool,
+    pub is_dir: bool,
+}
+
+/// Parses a `text/uri-list` payload, skipping comments and blank lines.
+pub fn parse_uri_list(data: &str) -> Vec<String> {
+    data.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parses `x-special/gnome-copied-files` into its operation and URIs.
+pub fn parse_gnome_copied_files(data: &str) -> Option<(String, Vec<String>)> {
+    let mut lines = data.lines().map(str::trim);
+    let operation = lines
+        .next()
+        .filter(|line| matches!(*line, "copy" | "cut"))?;
+    let uris = parse_uri_list(&lines.collect::<Vec<_>>().join("\n"));
+
+    (!uris.is_empty()).then(|| (operation.to_string(), uris))
+}
+
+/// Lines end with CRLF as RFC 2483 asks.
+pub fn render_uri_list(uris: &[String]) -> String {
+    uris.iter().map(|uri| format!("{uri}\r\n")).collect()
+}
+
+pub fn render_gnome_copied_files(operation: &str, uris: &[String]) -> String {
+    std::iter::once(operation.to_string())
+        .chain(uris.iter().cloned())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Local paths where the URI is a `file://` URI, the URI itself otherwise,
+/// one per line. This is what file managers offer as plain text.
+pub fn render_plain(uris: &[String]) -> String {
+    uris.iter()
+        .map(|uri| match uri_to_path(uri) {
+            Some(path) => path.to_string_lossy().to_string(),
+            None => uri.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Whether `uri` names a local file, as opposed to a link or a remote
+/// location.
+pub fn is_file_uri(uri: &str) -> bool {
+    uri.get(..7)
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("file://"))
+}
+
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    glib::filename_from_uri(uri).ok().map(|(path, _)| path)
+}
+
+/// The last part of the path, without looking at the file.
+pub fn file_name(uri: &str) -> String {
+    uri_to_path(uri)
+        .as_ref()
+        .and_then(|path| path.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| uri.rsplit('/').next().unwrap_or(uri).to_string())
+}
+
+/// Looks up the name, size and existence of a copied file. This reads the
+/// file system, so keep it off the main thread.
+pub fn summarize(uri: &str) -> FileSummary {
+    let path = uri_to_path(uri);
+    let metadata = path.as_ref().and_then(|path| fs::metadata(path).ok());
+
+    FileSummary {
+        name: file_name(uri),
+        size: metadata
+            .as_ref()
+            .filter(|metadata| metadata.is_file())
+            .map(|metadata| metadata.len()),
+        exists: metadata.is_some(),
+        is_dir: metadata.as_ref().is_some_and(|metadata| metadata.is_dir()),
+        path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_renders_uri_lists() {
+        let uris =
+            parse_uri_list("# comment\r\nfile:///tmp/a%20b.txt\r\n\r\nhttps://example.com/x\r\n");
+
+        assert_eq!(uris, ["file:///tmp/a%20b.txt", "https://example.com/x"]);
+        assert_eq!(
+            render_uri_list(&uris),
+            "file:///tmp/a%20b.txt\r\nhttps://example.com/x\r\n"
+        );
+        assert_eq!(render_plain(&uris), "/tmp/a b.txt\nhttps://example.com/x");
+    }
+
+    #[test]
+    fn tells_files_from_links() {
+        assert!(is_file_uri("file:///tmp/a%20b.txt"));
+        assert!(is_file_uri("FILE:///tmp/a"));
+        assert!(!is_file_uri("https://example.com/x"));
+        assert!(!is_file_uri("sftp://host/file"));
+        assert!(!is_file_uri("file"));
+    }
+
+    #[test]
+    fn parses_gnome_copied_files() {
+        let (operation, uris) =
+            parse_gnome_copied_files("cut\nfile:///home/me/one\nfile:///home/me/two").unwrap();
+
+        assert_eq!(operation, "cut");
+        assert_eq!(uris, ["file:///home/me/one", "file:///home/me/two"]);
+        assert_eq!(
+            render_gnome_copied_files(&operation, &uris),
+            "cut\nfile:///home/me/one\nfile:///home/me/two"
+        );
+        assert_eq!(parse_gnome_copied_files("file:///home/me/one"), None);
+    }
+
+    #[test]
+    fn summarizes_existing_and_missing_files() {
+        let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+        let uri = glib::filename_to_uri(manifest, None).unwrap();
+
+        let summary = summarize(&uri);
+        assert_eq!(summary.name, "Cargo.toml");
+        assert!(summary.exists && !summary.is_dir);
+        assert!(summary.size.is_some_and(|size| size > 0));
+
+        let missing = summarize("file:///nonexistent/clipboard_manager/gone.txt");
+        assert_eq!(missing.name, "gone.txt");
+        assert!(!missing.exists);
+        assert_eq!(missing.size, None);
+    }
+}
@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use crate::service::{
+    cliboard_provider::{IClipboardProvider, ProviderEntry},
+    history_transfer,
+};
+
+const NAME: &str = "file";
+
+/// Reads a history file written by the export (JSON, text or CSV).
+#[derive(Clone)]
+pub struct FileProvider {
+    path: PathBuf,
+}
+
+impl FileProvider {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl IClipboardProvider for FileProvider {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    /// The entries count as this provider's, whatever source they were
+    /// exported from, so turning it off hides them again.
+    fn list_entries(&self) -> Result<Vec<ProviderEntry>, String> {
+        Ok(history_transfer::import_from_file(&self.path)?
+            .into_iter()
+            .map(|entry| ProviderEntry {
+                source: None,
+                ..ProviderEntry::from(entry)
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,116 @@
+use std::{fs, path::PathBuf, sync::Arc};
+
+use gtk::glib;
+
+use crate::service::{
+    cliboard_history::ClipboardContent,
+    cliboard_provider::{IClipboardProvider, ProviderEntry},
+    file_list,
+};
+
+const NAME: &str = "gpaste";
+
+/// Reads GPaste's `history.xml`, which lists the newest item first. Image
+/// items reference a PNG file next to the history; password items are skipped.
+#[derive(Clone)]
+pub struct GpasteProvider {
+    path: PathBuf,
+}
+
+impl GpasteProvider {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl Default for GpasteProvider {
+    fn default() -> Self {
+        Self::new(glib::user_data_dir().join("gpaste").join("history.xml"))
+    }
+}
+
+impl IClipboardProvider for GpasteProvider {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn list_entries(&self) -> Result<Vec<ProviderEntry>, String> {
+        let data = fs::read_to_string(&self.path)
+            .map_err(|err| format!("{NAME}: cannot read {}: {err}", self.path.display()))?;
+        let document = roxmltree::Document::parse(&data)
+            .map_err(|err| format!("{NAME}: invalid history: {err}"))?;
+
+        let mut entries = Vec::new();
+
+        for item in document
+            .root_element()
+            .children()
+            .filter(|node| node.has_tag_name("item"))
+        {
+            let value: String = item
+                .children()
+                .filter(|node| node.has_tag_name("value"))
+                .flat_map(|node| node.children())
+                .filter_map(|node| node.text())
+                .collect();
+
+            if value.trim().is_empty() {
+                continue;
+            }
+
+            let id = item.attribute("uuid").map(str::to_string);
+
+            let content = match item.attribute("kind") {
+                Some("Text") => ClipboardContent::Text(value),
+                Some("Uris") => ClipboardContent::Files {
+                    uris: file_list::parse_uri_list(&value),
+                    operation: None,
+                },
+                Some("Image") => match fs::read(value.trim()) {
+                    Ok(bytes) => ClipboardContent::Image(Some(Arc::from(bytes))),
+                    Err(err) => {
+                        eprintln!("{NAME}: skipping image {}: {err}", value.trim());
+                        continue;
+                    }
+                },
+                _ => continue,
+            };
+
+            entries.push(ProviderEntry::new(id, content));
+        }
+
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_text_and_uri_items() {
+        let provider = GpasteProvider::new(PathBuf::from(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/gpaste_history.xml"
+        )));
+
+        let entries = provider.list_entries().unwrap();
+        let texts: Vec<String> = entries
+            .iter()
+            .map(|entry| entry.content.as_text())
+            .collect();
+
+        assert_eq!(
+            texts,
+            ["newest <b>entry</b>", "file:///tmp/report.pdf", "oldest"]
+        );
+        assert!(matches!(
+            &entries[1].content,
+            ClipboardContent::Files { uris, operation: None } if uris.len() == 1
+        ));
+        assert_eq!(
+            entries[0].id.as_deref(),
+            Some("5f0d2a4e-1d2b-4c55-9a1f-0c3f0c6c1a01")
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use std::{fs, path::PathBuf, sync::Arc};
+
+use gtk::glib;
+
+use crate::service::{
+    cliboard_history::ClipboardContent,
+    cliboard_provider::{IClipboardProvider, ProviderEntry},
+    file_list,
+};
+
+const NAME: &str = "greenclip";
+
+const TAG_TEXT: u8 = 0;
+const TAG_URI_LIST: u8 = 1;
+const TAG_PNG: u8 = 2;
+const TAG_JPEG: u8 = 3;
+const TAG_BMP: u8 = 4;
+
+/// Reads greenclip's history file, a Haskell `Data.Binary` encoded vector of
+/// clipboard contents stored newest first.
+#[derive(Clone)]
+pub struct GreenclipProvider {
+    path: PathBuf,
+}
+
+impl GreenclipProvider {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl Default for GreenclipProvider {
+    fn default() -> Self {
+        Self::new(glib::user_cache_dir().join("greenclip.history"))
+    }
+}
+
+impl IClipboardProvider for GreenclipProvider {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn list_entries(&self) -> Result<Vec<ProviderEntry>, String> {
+        let data = fs::read(&self.path)
+            .map_err(|err| format!("{NAME}: cannot read {}: {err}", self.path.display()))?;
+        let mut reader = BinaryReader { data: &data };
+
+        let count = reader.read_length()?;
+        let mut entries = Vec::new();
+
+        for _ in 0..count {
+            let tag = reader.read_u8()?;
+            let payload = reader.read_bytes()?;
+
+            let content = match tag {
+                TAG_TEXT => {
+                    let text = String::from_utf8_lossy(payload).to_string();
+                    if text.trim().is_empty() {
+                        continue;
+                    }
+                    ClipboardContent::Text(text)
+                }
+                TAG_URI_LIST => {
+                    let uris = file_list::parse_uri_list(&String::from_utf8_lossy(payload));
+                    if uris.is_empty() {
+                        continue;
+                    }
+                    ClipboardContent::Files {
+                        uris,
+                        operation: None,
+                    }
+                }
+                TAG_PNG | TAG_JPEG | TAG_BMP => ClipboardContent::Image(Some(Arc::from(payload))),
+                _ => return Err(format!("{NAME}: unknown entry tag {tag}")),
+            };
+
+            entries.push(ProviderEntry::new(None, content));
+        }
+
+        Ok(entries)
+    }
+}
+
+struct BinaryReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> BinaryReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.data.len() < len {
+            return Err(format!("{NAME}: truncated history file"));
+        }
+
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_length(&mut self) -> Result<usize, String> {
+        let bytes: [u8; 8] = self.take(8)?.try_into().expect("took 8 bytes");
+        usize::try_from(i64::from_be_bytes(bytes))
+            .map_err(|_| format!("{NAME}: invalid length in history file"))
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.read_length()?;
+        self.take(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_text_and_images() {
+        let provider = GreenclipProvider::new(PathBuf::from(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/greenclip.history"
+        )));
+
+        let entries = provider.list_entries().unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].content.as_text(), "newest");
+        assert_eq!(
+            entries[1].content.image_bytes(),
+            Some(&b"\x89PNG\r\n\x1a\nfake"[..])
+        );
+        assert_eq!(entries[2].content.as_text(), "file:///home/user/notes.txt");
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        let provider = GreenclipProvider::new(PathBuf::from(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/clipman.json"
+        )));
+
+        assert!(provider.list_entries().is_err());
+    }
+}
@@ -0,0 +1,819 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::service::{
+    cliboard_history::{ClipboardContent, ClipboardEntry, ClipboardFormat, IClipboardEntry},
+    cliboard_provider::{self, IClipboardProvider},
+    cliphist_provider::CliphistProvider,
+    file_list, rich_text,
+};
+
+const DOCUMENT_VERSION: u32 = 1;
+const IMPORT_SOURCE: &str = "import";
+const TAG_SEPARATOR: char = ';';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Text,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "text" | "txt" => Some(Self::Text),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the file extension, defaulting to JSON.
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Self::from_name)
+            .unwrap_or(Self::Json)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    pub query: Option<String>,
+    pub pinned_only: bool,
+}
+
+impl ExportFilter {
+    pub fn matches(&self, entry: &ClipboardEntry) -> bool {
+        if self.pinned_only && !entry.pinned {
+            return false;
+        }
+
+        match self.query.as_deref().filter(|query| !query.is_empty()) {
+            Some(query) => entry
+                .content
+                .as_text()
+                .to_lowercase()
+                .contains(&query.to_lowercase()),
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub enum ImageMode {
+    /// Images are base64-encoded into the document itself.
+    #[default]
+    Embedded,
+    /// Images are written as files into the given directory. The document
+    /// refers to them relative to itself, so both can be moved together.
+    Sidecar(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub filter: ExportFilter,
+    pub images: ImageMode,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportDocument {
+    version: u32,
+    exported_at: DateTime<Local>,
+    entries: Vec<ExportedEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedEntry {
+    #[serde(flatten)]
+    content: ExportedContent,
+    timestamp: DateTime<Local>,
+    source: String,
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    formats: Vec<ExportedFormat>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    copy_count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_used: Option<DateTime<Local>>,
+}
+
+fn is_zero(count: &u32) -> bool {
+    *count == 0
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedFormat {
+    mime_type: String,
+    data: String,
+}
+
+impl ExportedFormat {
+    fn new(format: &ClipboardFormat) -> Self {
+        Self {
+            mime_type: format.mime_type.clone(),
+            data: BASE64.encode(&format.data),
+        }
+    }
+
+    fn decode(self) -> Option<ClipboardFormat> {
+        let data = BASE64.decode(self.data).ok()?;
+        Some(ClipboardFormat::new(&self.mime_type, &data))
+    }
+}
+
+/// RTF as it was offered. Earlier documents stored it as text.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ExportedRtf {
+    Format(ExportedFormat),
+    Text(String),
+}
+
+impl ExportedRtf {
+    fn decode(self) -> Option<ClipboardFormat> {
+        match self {
+            Self::Format(format) => format.decode(),
+            Self::Text(text) => Some(ClipboardFormat::new(
+                rich_text::RTF_MIMES[0],
+                text.as_bytes(),
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ExportedContent {
+    Text {
+        text: String,
+    },
+    Image {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        data: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file: Option<String>,
+    },
+    Rich {
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        html: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rtf: Option<ExportedRtf>,
+    },
+    Files {
+        uris: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        operation: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct CsvRecord {
+    #[serde(rename = "type")]
+    kind: String,
+    timestamp: DateTime<Local>,
+    source: String,
+    pinned: bool,
+    tags: String,
+    content: String,
+    file: String,
+}
+
+/// Writes the entries matching `options.filter` to `path` and returns how many
+/// entries were written, leaving out the images a text export skips.
+pub fn export_to_file(
+    entries: &[ClipboardEntry],
+    path: &Path,
+    options: &ExportOptions,
+) -> Result<usize, String> {
+    let selected: Vec<&ClipboardEntry> = entries
+        .iter()
+        .filter(|entry| options.filter.matches(entry))
+        .collect();
+
+    let base_dir = match path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        Some(dir) => canonical_dir(dir)?,
+        None => canonical_dir(Path::new("."))?,
+    };
+    let images = match &options.images {
+        ImageMode::Sidecar(dir) => {
+            fs::create_dir_all(dir)
+                .map_err(|err| format!("Cannot create {}: {err}", dir.display()))?;
+            ImageMode::Sidecar(relative_path(&canonical_dir(dir)?, &base_dir))
+        }
+        ImageMode::Embedded => ImageMode::Embedded,
+    };
+
+    let exported = selected
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| export_entry(index, entry, &images, &base_dir))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (data, written) = match options.format {
+        ExportFormat::Json => (render_json(exported)?, selected.len()),
+        ExportFormat::Text => render_text(&exported),
+        ExportFormat::Csv => (render_csv(exported)?, selected.len()),
+    };
+
+    fs::write(path, data).map_err(|err| format!("Cannot write {}: {err}", path.display()))?;
+
+    Ok(written)
+}
+
+/// Reads a history file to add to the application's own history. Entries
+/// exported from another provider are marked as imported, so the native
+/// store keeps them.
+pub fn import_history(path: &Path) -> Result<Vec<ClipboardEntry>, String> {
+    let mut entries = import_from_file(path)?;
+    for entry in &mut entries {
+        if cliboard_provider::is_external(&entry.source) {
+            entry.source = IMPORT_SOURCE.to_string();
+        }
+    }
+    Ok(entries)
+}
+
+/// Reads entries previously written by [`export_to_file`]. Relative image
+/// files are resolved against the directory of `path`.
+pub fn import_from_file(path: &Path) -> Result<Vec<ClipboardEntry>, String> {
+    let data =
+        fs::read_to_string(path).map_err(|err| format!("Cannot read {}: {err}", path.display()))?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    let exported = match ExportFormat::from_path(path) {
+        ExportFormat::Json => parse_json(&data)?,
+        ExportFormat::Text => parse_text(&data),
+        ExportFormat::Csv => parse_csv(&data)?,
+    };
+
+    Ok(exported
+        .into_iter()
+        .filter_map(|entry| import_entry(entry, base_dir))
+        .collect())
+}
+
+/// Renders `entries` as a JSON document with embedded images, for stores
+/// that write the document themselves.
+pub fn to_json(entries: &[ClipboardEntry]) -> Result<String, String> {
+    let exported = entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| export_entry(index, entry, &ImageMode::Embedded, Path::new(".")))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    render_json(exported)
+}
+
+/// Parses a JSON document produced by [`to_json`].
+pub fn from_json(data: &str) -> Result<Vec<ClipboardEntry>, String> {
+    Ok(parse_json(data)?
+        .into_iter()
+        .filter_map(|entry| import_entry(entry, Path::new(".")))
+        .collect())
+}
+
+fn canonical_dir(dir: &Path) -> Result<PathBuf, String> {
+    fs::canonicalize(dir).map_err(|err| format!("Cannot resolve {}: {err}", dir.display()))
+}
+
+/// `target` as seen from `base`; both must be absolute.
+fn relative_path(target: &Path, base: &Path) -> PathBuf {
+    let common = target
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative: PathBuf = base
+        .components()
+        .skip(common)
+        .map(|_| Component::ParentDir)
+        .collect();
+    relative.extend(target.components().skip(common));
+    relative
+}
+
+/// Exports one entry. Sidecar image files are written relative to
+/// `base_dir`, the directory of the document.
+fn export_entry(
+    index: usize,
+    entry: &ClipboardEntry,
+    images: &ImageMode,
+    base_dir: &Path,
+) -> Result<ExportedEntry, String> {
+    let content = match &entry.content {
+        ClipboardContent::Text(text) => ExportedContent::Text { text: text.clone() },
+        ClipboardContent::Rich { plain, html, rtf } => ExportedContent::Rich {
+            text: plain.clone(),
+            html: html.clone(),
+            rtf: rtf
+                .as_ref()
+                .map(|rtf| ExportedRtf::Format(ExportedFormat::new(rtf))),
+        },
+        ClipboardContent::Files { uris, operation } => ExportedContent::Files {
+            uris: uris.clone(),
+            operation: operation.clone(),
+        },
+        ClipboardContent::Image(_) => {
+            let bytes = resolve_image_bytes(entry);
+
+            match (images, bytes) {
+                (ImageMode::Embedded, bytes) => ExportedContent::Image {
+                    data: bytes.map(|bytes| BASE64.encode(bytes)),
+                    file: None,
+                },
+                (ImageMode::Sidecar(dir), Some(bytes)) => {
+                    let name = format!("image-{index:04}.{}", image_extension(&bytes));
+                    let file = dir.join(&name);
+                    let file_path = base_dir.join(&file);
+                    fs::write(&file_path, &bytes)
+                        .map_err(|err| format!("Cannot write {}: {err}", file_path.display()))?;
+
+                    ExportedContent::Image {
+                        data: None,
+                        file: Some(file.to_string_lossy().to_string()),
+                    }
+                }
+                (ImageMode::Sidecar(_), None) => ExportedContent::Image {
+                    data: None,
+                    file: None,
+                },
+            }
+        }
+    };
+
+    Ok(ExportedEntry {
+        content,
+        timestamp: entry.timestamp,
+        source: entry.source.clone(),
+        pinned: entry.pinned,
+        tags: entry.tags.clone(),
+        formats: entry.formats.iter().map(ExportedFormat::new).collect(),
+        copy_count: entry.copy_count,
+        last_used: entry.last_used,
+    })
+}
+
+fn import_entry(exported: ExportedEntry, base_dir: &Path) -> Option<ClipboardEntry> {
+    let content = match exported.content {
+        ExportedContent::Text { text } => ClipboardContent::Text(text),
+        ExportedContent::Rich { text, html, rtf } => ClipboardContent::Rich {
+            plain: text,
+            html,
+            rtf: rtf.and_then(ExportedRtf::decode),
+        },
+        ExportedContent::Files { uris, operation } => ClipboardContent::Files { uris, operation },
+        ExportedContent::Image { data, file } => {
+            let bytes = match (data, file) {
+                (Some(data), _) => BASE64.decode(data).ok(),
+                (None, Some(file)) => fs::read(base_dir.join(file)).ok(),
+                (None, None) => None,
+            }?;
+
+            ClipboardContent::Image(Some(Arc::from(bytes)))
+        }
+    };
+
+    let mut entry = ClipboardEntry::new(content, Some(exported.source), None);
+    entry.timestamp = exported.timestamp;
+    entry.pinned = exported.pinned;
+    entry.tags = exported.tags;
+    entry.copy_count = exported.copy_count;
+    entry.last_used = exported.last_used;
+    entry.formats = exported
+        .formats
+        .into_iter()
+        .filter_map(ExportedFormat::decode)
+        .collect();
+
+    Some(entry)
+}
+
+fn resolve_image_bytes(entry: &ClipboardEntry) -> Option<Vec<u8>> {
+    if let Some(bytes) = entry.content.image_bytes() {
+        return Some(bytes.to_vec());
+    }
+
+    let provider = CliphistProvider;
+    match entry.id.as_deref() {
+        Some(id) if entry.source == provider.name() => provider.decode(id),
+        _ => None,
+    }
+}
+
+/// The usual file extension of encoded image `bytes`, "bin" when unknown.
+pub fn image_extension(bytes: &[u8]) -> &'static str {
+    if bytes.starts_with(b"\x89PNG") {
+        "png"
+    } else if bytes.starts_with(b"\xFF\xD8\xFF") {
+        "jpg"
+    } else if bytes.starts_with(b"GIF8") {
+        "gif"
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        "webp"
+    } else if bytes.starts_with(b"BM") {
+        "bmp"
+    } else {
+        "bin"
+    }
+}
+
+fn render_json(entries: Vec<ExportedEntry>) -> Result<String, String> {
+    let document = ExportDocument {
+        version: DOCUMENT_VERSION,
+        exported_at: Local::now(),
+        entries,
+    };
+
+    serde_json::to_string_pretty(&document).map_err(|err| format!("JSON encode failed: {err}"))
+}
+
+fn parse_json(data: &str) -> Result<Vec<ExportedEntry>, String> {
+    let document: ExportDocument =
+        serde_json::from_str(data).map_err(|err| format!("JSON decode failed: {err}"))?;
+
+    if document.version > DOCUMENT_VERSION {
+        return Err(format!(
+            "Unsupported export version {} (expected {DOCUMENT_VERSION} or lower)",
+            document.version
+        ));
+    }
+
+    Ok(document.entries)
+}
+
+/// One text entry per line; backslashes and newlines inside an entry are
+/// escaped so that multi-line entries survive a round trip. Images are skipped,
+/// formatted text keeps only its plain text and files are listed as URIs.
+/// Returns the data along with how many entries it holds.
+fn render_text(entries: &[ExportedEntry]) -> (String, usize) {
+    let mut data = String::new();
+    let mut written = 0;
+
+    for entry in entries {
+        let text = match &entry.content {
+            ExportedContent::Text { text } | ExportedContent::Rich { text, .. } => text.clone(),
+            ExportedContent::Files { uris, .. } => uris.join("\n"),
+            ExportedContent::Image { .. } => continue,
+        };
+
+        data.push_str(&escape_line(&text));
+        data.push('\n');
+        written += 1;
+    }
+
+    (data, written)
+}
+
+/// Text files carry no times, so the lines are stamped a millisecond apart,
+/// newest first, to keep the file order once the history sorts them.
+fn parse_text(data: &str) -> Vec<ExportedEntry> {
+    let now = Local::now();
+
+    data.lines()
+        .filter(|line| !line.is_empty())
+        .enumerate()
+        .map(|(index, line)| ExportedEntry {
+            content: ExportedContent::Text {
+                text: unescape_line(line),
+            },
+            timestamp: now - Duration::milliseconds(index as i64),
+            source: IMPORT_SOURCE.to_string(),
+            pinned: false,
+            tags: Vec::new(),
+            formats: Vec::new(),
+            copy_count: 0,
+            last_used: None,
+        })
+        .collect()
+}
+
+fn escape_line(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for ch in text.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(ch),
+        }
+    }
+
+    escaped
+}
+
+fn unescape_line(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            text.push(ch);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('r') => text.push('\r'),
+            Some(other) => text.push(other),
+            None => text.push('\\'),
+        }
+    }
+
+    text
+}
+
+fn render_csv(entries: Vec<ExportedEntry>) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    for entry in entries {
+        let (kind, content, file) = match entry.content {
+            ExportedContent::Text { text } | ExportedContent::Rich { text, .. } => {
+                ("text", text, String::new())
+            }
+            ExportedContent::Files { uris, .. } => ("files", uris.join("\n"), String::new()),
+            ExportedContent::Image { data, file } => {
+                ("image", data.unwrap_or_default(), file.unwrap_or_default())
+            }
+        };
+
+        writer
+            .serialize(CsvRecord {
+                kind: kind.to_string(),
+                timestamp: entry.timestamp,
+                source: entry.source,
+                pinned: entry.pinned,
+                tags: entry.tags.join(&TAG_SEPARATOR.to_string()),
+                content,
+                file,
+            })
+            .map_err(|err| format!("CSV encode failed: {err}"))?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|err| format!("CSV encode failed: {err}"))?;
+
+    String::from_utf8(bytes).map_err(|err| format!("CSV encode failed: {err}"))
+}
+
+fn parse_csv(data: &str) -> Result<Vec<ExportedEntry>, String> {
+    let mut reader = csv::Reader::from_reader(data.as_bytes());
+    let mut entries = Vec::new();
+
+    for record in reader.deserialize::<CsvRecord>() {
+        let record = record.map_err(|err| format!("CSV decode failed: {err}"))?;
+
+        let content = match record.kind.as_str() {
+            "image" => ExportedContent::Image {
+                data: Some(record.content).filter(|value| !value.is_empty()),
+                file: Some(record.file).filter(|value| !value.is_empty()),
+            },
+            "files" => ExportedContent::Files {
+                uris: file_list::parse_uri_list(&record.content),
+                operation: None,
+            },
+            _ => ExportedContent::Text {
+                text: record.content,
+            },
+        };
+
+        entries.push(ExportedEntry {
+            content,
+            timestamp: record.timestamp,
+            source: record.source,
+            pinned: record.pinned,
+            tags: record
+                .tags
+                .split(TAG_SEPARATOR)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect(),
+            formats: Vec::new(),
+            copy_count: 0,
+            last_used: None,
+        });
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "clipboard-manager-transfer-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sample_entries() -> Vec<ClipboardEntry> {
+        let mut text = ClipboardEntry::new(
+            ClipboardContent::Text("two\nlines \\ here".to_string()),
+            Some("clipboard".to_string()),
+            None,
+        );
+        text.pinned = true;
+        text.tags = vec!["work".to_string(), "todo".to_string()];
+        text.copy_count = 3;
+        text.last_used = Some(text.timestamp);
+        text.formats = vec![ClipboardFormat::new("text/x-custom", b"\x00raw")];
+
+        let rich = ClipboardEntry::new(
+            ClipboardContent::Rich {
+                plain: "bold".to_string(),
+                html: Some("<b>bold</b>".to_string()),
+                rtf: Some(ClipboardFormat::new(
+                    "application/rtf",
+                    b"{\\rtf1\\ansi caf\\'e9}",
+                )),
+            },
+            Some("clipboard".to_string()),
+            None,
+        );
+        let files = ClipboardEntry::new(
+            ClipboardContent::Files {
+                uris: vec!["file:///tmp/a".to_string(), "file:///tmp/b".to_string()],
+                operation: Some("cut".to_string()),
+            },
+            Some("clipboard".to_string()),
+            None,
+        );
+        let image = ClipboardEntry::new(
+            ClipboardContent::Image(Some(Arc::from(&b"\x89PNG fake"[..]))),
+            Some("clipboard".to_string()),
+            None,
+        );
+
+        vec![text, rich, files, image]
+    }
+
+    fn options(format: ExportFormat, images: ImageMode) -> ExportOptions {
+        ExportOptions {
+            format,
+            filter: ExportFilter::default(),
+            images,
+        }
+    }
+
+    #[test]
+    fn escapes_lines_reversibly() {
+        for text in ["plain", "a\nb\r\nc", "back\\slash\\n", "trailing\\"] {
+            let escaped = escape_line(text);
+            assert!(!escaped.contains('\n'));
+            assert_eq!(unescape_line(&escaped), text);
+        }
+    }
+
+    #[test]
+    fn round_trips_json_with_every_kind_of_content() {
+        let entries = sample_entries();
+        let restored = from_json(&to_json(&entries).unwrap()).unwrap();
+
+        assert_eq!(restored.len(), entries.len());
+        for (restored, original) in restored.iter().zip(&entries) {
+            assert!(restored.is_duplicate_of(original));
+            assert_eq!(restored.timestamp, original.timestamp);
+            assert_eq!(restored.pinned, original.pinned);
+            assert_eq!(restored.tags, original.tags);
+            assert_eq!(restored.formats, original.formats);
+            assert_eq!(restored.copy_count, original.copy_count);
+            assert_eq!(restored.last_used, original.last_used);
+        }
+        assert!(matches!(
+            &restored[2].content,
+            ClipboardContent::Files { operation: Some(operation), .. } if operation == "cut"
+        ));
+    }
+
+    #[test]
+    fn reads_rtf_stored_as_text() {
+        let document = r#"{"version": 1, "exported_at": "2025-03-20T16:45:00+01:00", "entries": [
+            {"type": "rich", "text": "bold", "rtf": "{\\rtf1 bold}",
+             "timestamp": "2025-03-20T16:45:00+01:00", "source": "clipboard"}
+        ]}"#;
+
+        let entries = from_json(document).unwrap();
+        assert!(matches!(
+            &entries[0].content,
+            ClipboardContent::Rich { rtf: Some(rtf), .. }
+                if rtf.mime_type == "text/rtf" && *rtf.data == *b"{\\rtf1 bold}"
+        ));
+    }
+
+    #[test]
+    fn round_trips_csv() {
+        let dir = temp_dir("csv");
+        let path = dir.join("history.csv");
+        let entries = sample_entries();
+
+        let count = export_to_file(
+            &entries,
+            &path,
+            &options(ExportFormat::Csv, ImageMode::Embedded),
+        )
+        .unwrap();
+        let restored = import_from_file(&path).unwrap();
+
+        assert_eq!(count, entries.len());
+        assert_eq!(restored.len(), entries.len());
+        assert_eq!(restored[0].content.as_text(), entries[0].content.as_text());
+        assert_eq!(restored[0].tags, entries[0].tags);
+        assert!(restored[0].pinned);
+        // CSV keeps only the plain text of formatted entries.
+        assert_eq!(restored[1].content.as_text(), "bold");
+        assert!(restored[2].is_duplicate_of(&entries[2]));
+        assert!(restored[3].is_duplicate_of(&entries[3]));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn text_export_counts_only_written_lines_and_keeps_their_order() {
+        let dir = temp_dir("text");
+        let path = dir.join("history.txt");
+        let entries = sample_entries();
+
+        let count = export_to_file(
+            &entries,
+            &path,
+            &options(ExportFormat::Text, ImageMode::Embedded),
+        )
+        .unwrap();
+        let restored = import_from_file(&path).unwrap();
+
+        // The image is skipped.
+        assert_eq!(count, entries.len() - 1);
+        assert_eq!(restored.len(), count);
+        assert_eq!(restored[0].content.as_text(), entries[0].content.as_text());
+        assert!(
+            restored
+                .windows(2)
+                .all(|pair| pair[0].timestamp > pair[1].timestamp)
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn sidecar_images_move_with_the_export() {
+        let dir = temp_dir("sidecar");
+        let path = dir.join("history.json");
+        let entries = sample_entries();
+
+        export_to_file(
+            &entries,
+            &path,
+            &options(ExportFormat::Json, ImageMode::Sidecar(dir.join("images"))),
+        )
+        .unwrap();
+        let data = fs::read_to_string(&path).unwrap();
+        assert!(data.contains("\"file\": \"images/image-0003.png\""));
+
+        let moved = temp_dir("sidecar-moved");
+        fs::rename(&path, moved.join("history.json")).unwrap();
+        fs::rename(dir.join("images"), moved.join("images")).unwrap();
+
+        let restored = import_from_file(&moved.join("history.json")).unwrap();
+        assert!(restored[3].is_duplicate_of(&entries[3]));
+
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_dir_all(moved).unwrap();
+    }
+
+    #[test]
+    fn relative_paths_climb_out_of_the_base() {
+        assert_eq!(
+            relative_path(Path::new("/a/b/images"), Path::new("/a/b")),
+            PathBuf::from("images")
+        );
+        assert_eq!(
+            relative_path(Path::new("/a/images"), Path::new("/a/b")),
+            PathBuf::from("../images")
+        );
+        assert_eq!(
+            relative_path(Path::new("/a"), Path::new("/a")),
+            PathBuf::new()
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use std::path::Path;
+
+pub const MIN_ZOOM: f64 = 0.1;
+pub const MAX_ZOOM: f64 = 8.0;
+const ZOOM_STEP: f64 = 1.25;
+
+/// The formats an image can be saved in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    WebP,
+}
+
+impl ImageFormat {
+    pub const ALL: [Self; 3] = [Self::Png, Self::Jpeg, Self::WebP];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Png => "PNG",
+            Self::Jpeg => "JPEG",
+            Self::WebP => "WebP",
+        }
+    }
+
+    /// File extensions, the preferred one first.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::Png => &["png"],
+            Self::Jpeg => &["jpg", "jpeg"],
+            Self::WebP => &["webp"],
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::WebP => "image/webp",
+        }
+    }
+
+    /// The name gdk-pixbuf saves the format under.
+    pub fn pixbuf_type(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpeg",
+            Self::WebP => "webp",
+        }
+    }
+
+    /// Saver options; lossy formats keep a high quality.
+    pub fn options(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Png => &[],
+            Self::Jpeg | Self::WebP => &[("quality", "90")],
+        }
+    }
+
+    /// The formats among [`Self::ALL`] that gdk-pixbuf can save, given the
+    /// names of its writable formats.
+    pub fn writable<S: AsRef<str>>(savers: &[S]) -> Vec<Self> {
+        Self::ALL
+            .into_iter()
+            .filter(|format| {
+                savers
+                    .iter()
+                    .any(|saver| saver.as_ref() == format.pixbuf_type())
+            })
+            .collect()
+    }
+
+    /// Picks the format among `formats` by file extension, PNG when it is
+    /// unknown or not among them.
+    pub fn from_path(path: &Path, formats: &[Self]) -> Self {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        formats
+            .iter()
+            .copied()
+            .find(|format| format.extensions().contains(&extension.as_str()))
+            .unwrap_or(Self::Png)
+    }
+}
+
+pub fn zoom_in(zoom: f64) -> f64 {
+    (zoom * ZOOM_STEP).min(MAX_ZOOM)
+}
+
+pub fn zoom_out(zoom: f64) -> f64 {
+    (zoom / ZOOM_STEP).max(MIN_ZOOM)
+}
+
+/// Where an image is drawn inside an area: scaled by `scale` and moved by
+/// `x` and `y` from the area's top left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub scale: f64,
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Placement {
+    /// Centers an image of size `image` in `area`, at `zoom` or, for `None`,
+    /// as large as fits without cropping it. Fitting never enlarges it.
+    pub fn new(image: (i32, i32), area: (f64, f64), zoom: Option<f64>) -> Self {
+        let (width, height) = (f64::from(image.0.max(1)), f64::from(image.1.max(1)));
+        let scale = zoom.unwrap_or_else(|| (area.0 / width).min(area.1 / height).min(1.0));
+
+        Self {
+            scale,
+            x: ((area.0 - width * scale) / 2.0).max(0.0),
+            y: ((area.1 - height * scale) / 2.0).max(0.0),
+        }
+    }
+
+    pub fn to_image(self, point: (f64, f64)) -> (f64, f64) {
+        (
+            (point.0 - self.x) / self.scale,
+            (point.1 - self.y) / self.scale,
+        )
+    }
+
+    pub fn to_area(self, point: (f64, f64)) -> (f64, f64) {
+        (point.0 * self.scale + self.x, point.1 * self.scale + self.y)
+    }
+}
+
+/// A region of an image in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CropRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl CropRect {
+    /// The pixels between two corners given in image coordinates, cut to
+    /// an image of size `image`. `None` when nothing of it is left.
+    pub fn between(start: (f64, f64), end: (f64, f64), image: (i32, i32)) -> Option<Self> {
+        let clamp_x = |x: f64| x.clamp(0.0, f64::from(image.0));
+        let clamp_y = |y: f64| y.clamp(0.0, f64::from(image.1));
+
+        let left = clamp_x(start.0.min(end.0)).floor() as i32;
+        let right = clamp_x(start.0.max(end.0)).ceil() as i32;
+        let top = clamp_y(start.1.min(end.1)).floor() as i32;
+        let bottom = clamp_y(start.1.max(end.1)).ceil() as i32;
+
+        (right > left && bottom > top).then_some(Self {
+            x: left,
+            y: top,
+            width: right - left,
+            height: bottom - top,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_format_by_extension() {
+        let all = ImageFormat::ALL;
+        assert_eq!(
+            ImageFormat::from_path(Path::new("shot.JPG"), &all),
+            ImageFormat::Jpeg
+        );
+        assert_eq!(
+            ImageFormat::from_path(Path::new("shot.webp"), &all),
+            ImageFormat::WebP
+        );
+        assert_eq!(
+            ImageFormat::from_path(Path::new("shot"), &all),
+            ImageFormat::Png
+        );
+
+        let without_webp = ImageFormat::writable(&["png", "jpeg", "ico"]);
+        assert_eq!(without_webp, [ImageFormat::Png, ImageFormat::Jpeg]);
+        assert_eq!(
+            ImageFormat::from_path(Path::new("shot.webp"), &without_webp),
+            ImageFormat::Png
+        );
+    }
+
+    #[test]
+    fn maps_a_selection_to_image_pixels() {
+        // A 200×100 image fits into a 400×400 area as it is, centered.
+        assert_eq!(
+            Placement::new((200, 100), (400.0, 400.0), None),
+            Placement {
+                scale: 1.0,
+                x: 100.0,
+                y: 150.0
+            }
+        );
+
+        // An 800×400 image is drawn half as large and 100 pixels down.
+        let placement = Placement::new((800, 400), (400.0, 400.0), None);
+        assert_eq!(
+            placement,
+            Placement {
+                scale: 0.5,
+                x: 0.0,
+                y: 100.0
+            }
+        );
+
+        let start = placement.to_image((390.0, 120.0));
+        let end = placement.to_image((300.0, 500.0));
+        assert_eq!(
+            CropRect::between(start, end, (800, 400)),
+            Some(CropRect {
+                x: 600,
+                y: 40,
+                width: 180,
+                height: 360
+            })
+        );
+        assert_eq!(
+            CropRect::between((-5.0, 0.0), (0.0, 50.0), (200, 100)),
+            None
+        );
+    }
+}
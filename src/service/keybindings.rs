@@ -0,0 +1,339 @@
+use std::collections::BTreeMap;
+
+/// Where a binding is handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingScope {
+    /// Installed with `set_accels_for_action`; works anywhere in the window.
+    Application,
+    /// Handled by the keyboard service while the list or the search entry
+    /// has the focus.
+    List,
+}
+
+impl BindingScope {
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Application => "Application",
+            Self::List => "History",
+        }
+    }
+}
+
+/// A bindable action and its default accelerators.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BindingSpec {
+    /// A detailed action name for the application scope, an identifier
+    /// understood by the keyboard service for the list scope.
+    pub action: &'static str,
+    pub title: &'static str,
+    pub scope: BindingScope,
+    pub defaults: &'static [&'static str],
+    /// Shown in the shortcuts window but not changeable.
+    pub fixed: bool,
+}
+
+const fn binding(
+    action: &'static str,
+    title: &'static str,
+    scope: BindingScope,
+    defaults: &'static [&'static str],
+) -> BindingSpec {
+    BindingSpec {
+        action,
+        title,
+        scope,
+        defaults,
+        fixed: false,
+    }
+}
+
+pub const PREVIOUS_ENTRY: &str = "previous-entry";
+pub const NEXT_ENTRY: &str = "next-entry";
+pub const PAGE_UP: &str = "page-up";
+pub const PAGE_DOWN: &str = "page-down";
+pub const FIRST_ENTRY: &str = "first-entry";
+pub const LAST_ENTRY: &str = "last-entry";
+pub const COPY_AND_CLOSE: &str = "copy-and-close";
+pub const REMOVE_ENTRY: &str = "remove-entry";
+pub const TOGGLE_PIN: &str = "toggle-pin";
+pub const CANCEL: &str = "cancel";
+pub const COPY_NTH: &str = "copy-nth";
+
+pub const BINDINGS: &[BindingSpec] = &[
+    binding(
+        "win.search",
+        "Search",
+        BindingScope::Application,
+        &["<Control>f"],
+    ),
+    binding(
+        "win.copy-plain-text",
+        "Copy as Plain Text",
+        BindingScope::Application,
+        &["<Control><Shift>v"],
+    ),
+    binding(
+        "win.show-shortcuts",
+        "Keyboard Shortcuts",
+        BindingScope::Application,
+        &["<Control>question"],
+    ),
+    binding(
+        "app.quit",
+        "Quit",
+        BindingScope::Application,
+        &["<Control>q"],
+    ),
+    binding(
+        PREVIOUS_ENTRY,
+        "Previous Entry",
+        BindingScope::List,
+        &["Up", "k"],
+    ),
+    binding(NEXT_ENTRY, "Next Entry", BindingScope::List, &["Down", "j"]),
+    binding(PAGE_UP, "Page Up", BindingScope::List, &["Page_Up"]),
+    binding(PAGE_DOWN, "Page Down", BindingScope::List, &["Page_Down"]),
+    binding(FIRST_ENTRY, "First Entry", BindingScope::List, &["Home"]),
+    binding(LAST_ENTRY, "Last Entry", BindingScope::List, &["End"]),
+    binding(
+        COPY_AND_CLOSE,
+        "Copy and Close",
+        BindingScope::List,
+        &["Return", "KP_Enter"],
+    ),
+    BindingSpec {
+        action: COPY_NTH,
+        title: "Copy Entry 1–9",
+        scope: BindingScope::List,
+        defaults: &["<Alt>1...<Alt>9"],
+        fixed: true,
+    },
+    binding(
+        TOGGLE_PIN,
+        "Pin or Unpin",
+        BindingScope::List,
+        &["<Control>p"],
+    ),
+    binding(
+        REMOVE_ENTRY,
+        "Remove Entry",
+        BindingScope::List,
+        &["Delete"],
+    ),
+    binding(
+        CANCEL,
+        "Close Search or Window",
+        BindingScope::List,
+        &["Escape"],
+    ),
+];
+
+/// The accelerators of every bindable action, with the user's changes
+/// applied over the defaults.
+#[derive(Debug, Clone)]
+pub struct KeybindingRegistry {
+    accels: Vec<Vec<String>>,
+}
+
+impl KeybindingRegistry {
+    /// Applies `overrides` from the config. Unknown actions and changes to
+    /// fixed bindings are ignored.
+    pub fn new(overrides: &BTreeMap<String, Vec<String>>) -> Self {
+        let accels = BINDINGS
+            .iter()
+            .map(|spec| match overrides.get(spec.action) {
+                Some(accels) if !spec.fixed => accels.iter().map(|a| normalize(a)).collect(),
+                _ => defaults(spec),
+            })
+            .collect();
+
+        Self { accels }
+    }
+
+    pub fn bindings(&self) -> impl Iterator<Item = (&'static BindingSpec, &[String])> {
+        BINDINGS
+            .iter()
+            .zip(&self.accels)
+            .map(|(spec, accels)| (spec, accels.as_slice()))
+    }
+
+    pub fn accels(&self, action: &str) -> &[String] {
+        self.bindings()
+            .find(|(spec, _)| spec.action == action)
+            .map_or(&[], |(_, accels)| accels)
+    }
+
+    /// The list action bound to `accel`, if any.
+    pub fn list_action(&self, accel: &str) -> Option<&'static str> {
+        let accel = normalize(accel);
+        self.bindings()
+            .find(|(spec, accels)| {
+                spec.scope == BindingScope::List && !spec.fixed && accels.contains(&accel)
+            })
+            .map(|(spec, _)| spec.action)
+    }
+
+    /// The other binding already using `accel`. Application shortcuts are
+    /// handled before the list sees a key, so scopes do not separate them.
+    pub fn conflict(&self, action: &str, accel: &str) -> Option<&'static BindingSpec> {
+        let accel = normalize(accel);
+        self.bindings()
+            .find(|(spec, accels)| {
+                spec.action != action && accels.iter().any(|taken| expand(taken).contains(&accel))
+            })
+            .map(|(spec, _)| spec)
+    }
+
+    /// Replaces the accelerators of `action`, refusing ones in use elsewhere.
+    pub fn set(&mut self, action: &str, accels: &[String]) -> Result<(), String> {
+        let index = BINDINGS
+            .iter()
+            .position(|spec| spec.action == action && !spec.fixed)
+            .ok_or_else(|| format!("Unknown shortcut action: {action}"))?;
+
+        if let Some((accel, other)) = accels
+            .iter()
+            .find_map(|accel| Some((accel, self.conflict(action, accel)?)))
+        {
+            return Err(format!("{accel} is already used by “{}”", other.title));
+        }
+
+        self.accels[index] = accels.iter().map(|accel| normalize(accel)).collect();
+        Ok(())
+    }
+
+    pub fn reset(&mut self, action: &str) {
+        if let Some(index) = BINDINGS.iter().position(|spec| spec.action == action) {
+            self.accels[index] = defaults(&BINDINGS[index]);
+        }
+    }
+
+    pub fn is_default(&self, action: &str) -> bool {
+        self.bindings()
+            .find(|(spec, _)| spec.action == action)
+            .is_none_or(|(spec, accels)| accels == defaults(spec).as_slice())
+    }
+
+    /// The bindings that differ from the defaults, as stored in the config.
+    pub fn overrides(&self) -> BTreeMap<String, Vec<String>> {
+        self.bindings()
+            .filter(|(spec, _)| !self.is_default(spec.action))
+            .map(|(spec, accels)| (spec.action.to_string(), accels.to_vec()))
+            .collect()
+    }
+}
+
+fn defaults(spec: &BindingSpec) -> Vec<String> {
+    spec.defaults.iter().map(|accel| normalize(accel)).collect()
+}
+
+/// The accelerators a binding takes. A range such as `<Alt>1...<Alt>9`
+/// stands for every digit in it.
+fn expand(accel: &str) -> Vec<String> {
+    let range = accel.split_once("...").and_then(|(first, last)| {
+        let (first, last) = (normalize(first), normalize(last));
+        let prefix = first.strip_suffix(|c: char| c.is_ascii_digit())?;
+        let last_digit = last.strip_prefix(prefix)?.parse::<u8>().ok()?;
+        let first_digit = first[prefix.len()..].parse::<u8>().ok()?;
+
+        Some(
+            (first_digit..=last_digit)
+                .map(|digit| format!("{prefix}{digit}"))
+                .collect(),
+        )
+    });
+
+    range.unwrap_or_else(|| vec![accel.to_string()])
+}
+
+/// Brings an accelerator into one spelling so that `<Ctrl>F` and
+/// `<Control>f` compare equal.
+pub fn normalize(accel: &str) -> String {
+    let mut rest = accel.trim();
+    let mut modifiers = Vec::new();
+
+    while let Some(tail) = rest.strip_prefix('<') {
+        let Some(end) = tail.find('>') else {
+            break;
+        };
+        let modifier = match tail[..end].to_ascii_lowercase().as_str() {
+            "control" | "ctrl" | "ctl" | "primary" => "<Control>",
+            "shift" | "shft" => "<Shift>",
+            "alt" | "mod1" => "<Alt>",
+            "super" => "<Super>",
+            "meta" => "<Meta>",
+            "hyper" => "<Hyper>",
+            _ => "",
+        };
+        if !modifier.is_empty() && !modifiers.contains(&modifier) {
+            modifiers.push(modifier);
+        }
+        rest = &tail[end + 1..];
+    }
+
+    const ORDER: [&str; 6] = [
+        "<Shift>",
+        "<Control>",
+        "<Alt>",
+        "<Super>",
+        "<Meta>",
+        "<Hyper>",
+    ];
+    modifiers.sort_by_key(|modifier| ORDER.iter().position(|known| known == modifier));
+
+    let key = if rest.chars().count() == 1 {
+        rest.to_lowercase()
+    } else {
+        rest.to_string()
+    };
+
+    format!("{}{key}", modifiers.concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_modifier_spellings() {
+        assert_eq!(normalize("<Ctrl><Shift>V"), "<Shift><Control>v");
+        assert_eq!(normalize("<Primary>f"), "<Control>f");
+        assert_eq!(normalize("Page_Up"), "Page_Up");
+    }
+
+    #[test]
+    fn applies_overrides_and_detects_conflicts() {
+        let overrides = BTreeMap::from([
+            ("win.search".to_string(), vec!["<Control>s".to_string()]),
+            (COPY_NTH.to_string(), vec!["<Control>1".to_string()]),
+        ]);
+        let mut registry = KeybindingRegistry::new(&overrides);
+
+        assert_eq!(registry.accels("win.search"), ["<Control>s"]);
+        assert_eq!(registry.accels(COPY_NTH), ["<Alt>1...<Alt>9"]);
+        assert_eq!(registry.list_action("K"), Some(PREVIOUS_ENTRY));
+
+        let error = registry
+            .set(TOGGLE_PIN, &["<Ctrl>Q".to_string()])
+            .unwrap_err();
+        assert!(error.contains("Quit"));
+
+        let error = registry
+            .set(TOGGLE_PIN, &["<Mod1>3".to_string()])
+            .unwrap_err();
+        assert!(error.contains("Copy Entry"));
+        assert_eq!(registry.conflict(TOGGLE_PIN, "<Alt>0"), None);
+
+        registry
+            .set(TOGGLE_PIN, &["<Control>b".to_string()])
+            .unwrap();
+        assert_eq!(registry.list_action("<Control>b"), Some(TOGGLE_PIN));
+        assert_eq!(registry.list_action("<Control>p"), None);
+
+        registry.reset("win.search");
+        assert_eq!(
+            registry.overrides(),
+            BTreeMap::from([(TOGGLE_PIN.to_string(), vec!["<Control>b".to_string()])])
+        );
+    }
+}
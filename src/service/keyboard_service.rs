@@ -1,3 +1,5 @@
+use std::{cell::RefCell, rc::Rc};
+
 use gtk::{
     gdk::{self, Key},
     glib::{self},
@@ -5,12 +7,19 @@ use gtk::{
 };
 use libadwaita as adw;
 
-#[derive(Default, Clone)]
-pub struct KeyboardService;
+use crate::{
+    service::keybindings::{self, KeybindingRegistry},
+    ui::list::{self, NavigationDirection},
+};
+
+#[derive(Clone)]
+pub struct KeyboardService {
+    registry: Rc<RefCell<KeybindingRegistry>>,
+}
 
 impl KeyboardService {
-    pub fn new() -> Self {
-        Self
+    pub fn new(registry: Rc<RefCell<KeybindingRegistry>>) -> Self {
+        Self { registry }
     }
 
     pub fn setup(
@@ -22,6 +31,8 @@ impl KeyboardService {
         let key_controller = gtk::EventControllerKey::new();
 
         key_controller.connect_key_pressed(glib::clone!(
+            #[strong(rename_to = service)]
+            self,
             #[strong]
             window,
             #[strong]
@@ -29,33 +40,147 @@ impl KeyboardService {
             #[strong]
             list_view,
             move |_, key, _key_code, state| {
-                Self::handle_key_press(&window, &search_button, &list_view, key, state)
+                service.handle_key_press(&window, &search_button, &list_view, key, state)
             }
         ));
 
         window.add_controller(key_controller);
+
+        // Runs before the focused widget so that the list's own Enter
+        // handling and the search entry's key capture do not get these keys.
+        let navigation_controller = gtk::EventControllerKey::new();
+        navigation_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+
+        navigation_controller.connect_key_pressed(glib::clone!(
+            #[strong(rename_to = service)]
+            self,
+            #[strong]
+            window,
+            #[strong]
+            search_button,
+            #[strong]
+            list_view,
+            move |_, key, _key_code, state| {
+                service.handle_navigation(&window, &search_button, &list_view, key, state)
+            }
+        ));
+
+        window.add_controller(navigation_controller);
     }
 
-    fn handle_key_press(
+    /// List navigation and entry shortcuts from the registry. Only applies
+    /// while the list or the search entry has the focus, never inside
+    /// dialogs.
+    fn handle_navigation(
+        &self,
         window: &adw::ApplicationWindow,
         search_button: &gtk::ToggleButton,
-        _list_view: &adw::Clamp,
+        list_view: &adw::Clamp,
         key: Key,
         state: gdk::ModifierType,
     ) -> glib::Propagation {
-        if state.contains(gdk::ModifierType::CONTROL_MASK)
-            || state.contains(gdk::ModifierType::ALT_MASK)
-            || state.contains(gdk::ModifierType::SUPER_MASK)
+        let in_list = list::list_contains_focus(list_view);
+        let in_search = RootExt::focus(window)
+            .and_then(|focus| focus.ancestor(gtk::SearchEntry::static_type()))
+            .is_some();
+        if !in_list && !in_search {
+            return glib::Propagation::Proceed;
+        }
+
+        let modifiers = state & gtk::accelerator_get_default_mod_mask();
+
+        if modifiers == gdk::ModifierType::ALT_MASK
+            && let Some(number) = key
+                .to_unicode()
+                .and_then(|ch| ch.to_digit(10))
+                .filter(|number| (1..=9).contains(number))
         {
+            ActionGroupExt::activate_action(window, "copy-nth", Some(&number.to_variant()));
+            return glib::Propagation::Stop;
+        }
+
+        // Keys that edit the search text belong to the entry while it has
+        // the focus.
+        if in_search && edits_text(key, modifiers) {
             return glib::Propagation::Proceed;
         }
 
-        if key == Key::Escape {
-            if search_button.is_active() {
-                search_button.set_active(false);
-            } else {
-                window.close();
+        let accel = gtk::accelerator_name(key, modifiers);
+        let Some(action) = self.registry.borrow().list_action(&accel) else {
+            return glib::Propagation::Proceed;
+        };
+
+        Self::run_list_action(window, search_button, list_view, action)
+    }
+
+    fn run_list_action(
+        window: &adw::ApplicationWindow,
+        search_button: &gtk::ToggleButton,
+        list_view: &adw::Clamp,
+        action: &str,
+    ) -> glib::Propagation {
+        let direction = match action {
+            keybindings::PREVIOUS_ENTRY => Some(NavigationDirection::Up),
+            keybindings::NEXT_ENTRY => Some(NavigationDirection::Down),
+            keybindings::PAGE_UP => Some(NavigationDirection::PageUp),
+            keybindings::PAGE_DOWN => Some(NavigationDirection::PageDown),
+            keybindings::FIRST_ENTRY => Some(NavigationDirection::First),
+            keybindings::LAST_ENTRY => Some(NavigationDirection::Last),
+            _ => None,
+        };
+
+        if let Some(direction) = direction {
+            list::move_selection(list_view, direction);
+            return glib::Propagation::Stop;
+        }
+
+        match action {
+            keybindings::COPY_AND_CLOSE => {
+                // Close after copy may already have hidden the window.
+                if list::activate_selected(list_view) && window.is_visible() {
+                    window.close();
+                }
+            }
+            keybindings::REMOVE_ENTRY => {
+                ActionGroupExt::activate_action(window, "remove-selected", None);
+            }
+            keybindings::TOGGLE_PIN => {
+                ActionGroupExt::activate_action(window, "toggle-pin", None);
             }
+            keybindings::CANCEL => {
+                if search_button.is_active() {
+                    search_button.set_active(false);
+                } else {
+                    window.close();
+                }
+            }
+            _ => return glib::Propagation::Proceed,
+        }
+
+        glib::Propagation::Stop
+    }
+
+    fn handle_key_press(
+        &self,
+        window: &adw::ApplicationWindow,
+        search_button: &gtk::ToggleButton,
+        list_view: &adw::Clamp,
+        key: Key,
+        state: gdk::ModifierType,
+    ) -> glib::Propagation {
+        let modifiers = state & gtk::accelerator_get_default_mod_mask();
+
+        // Cancelling also works while the focus is elsewhere, such as on a
+        // header button.
+        let accel = gtk::accelerator_name(key, modifiers);
+        if self.registry.borrow().list_action(&accel) == Some(keybindings::CANCEL) {
+            return Self::run_list_action(window, search_button, list_view, keybindings::CANCEL);
+        }
+
+        if state.contains(gdk::ModifierType::CONTROL_MASK)
+            || state.contains(gdk::ModifierType::ALT_MASK)
+            || state.contains(gdk::ModifierType::SUPER_MASK)
+        {
             return glib::Propagation::Proceed;
         }
 
@@ -71,3 +196,26 @@ impl KeyboardService {
         glib::Propagation::Proceed
     }
 }
+
+/// Whether a single-line entry uses the key itself.
+fn edits_text(key: Key, modifiers: gdk::ModifierType) -> bool {
+    if !(modifiers - gdk::ModifierType::SHIFT_MASK).is_empty() {
+        return false;
+    }
+
+    key.to_unicode().is_some_and(|ch| !ch.is_control())
+        || matches!(
+            key,
+            Key::Home
+                | Key::End
+                | Key::Left
+                | Key::Right
+                | Key::Delete
+                | Key::BackSpace
+                | Key::KP_Home
+                | Key::KP_End
+                | Key::KP_Left
+                | Key::KP_Right
+                | Key::KP_Delete
+        )
+}
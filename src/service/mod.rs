@@ -1,6 +1,31 @@
+pub mod auto_paste;
 pub mod cliboard_history;
 pub mod cliboard_monitor;
 pub mod cliboard_provider;
+pub mod clipboard_filter;
+pub mod cliphist_meta;
 pub mod cliphist_provider;
+pub mod clipman_provider;
+pub mod config_service;
+pub mod content_filter;
+pub mod copyq_provider;
+pub mod entry_groups;
+pub mod entry_sort;
+pub mod file_list;
+pub mod file_provider;
+pub mod gpaste_provider;
+pub mod greenclip_provider;
+pub mod history_transfer;
+pub mod image_edit;
+pub mod keybindings;
 pub mod keyboard_service;
+pub mod native_store_provider;
+pub mod popup_placement;
+pub mod recording_state;
+pub mod rich_text;
+pub mod secret_keyring;
+pub mod selection_sync;
+pub mod sensitivity;
+pub mod store_cipher;
 pub mod style_service;
+pub mod time_format;
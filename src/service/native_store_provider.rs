@@ -0,0 +1,353 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
+
+use crate::service::{
+    cliboard_history::ClipboardEntry,
+    cliboard_provider::{self, IClipboardProvider, ProviderEntry},
+    cliphist_meta::CliphistMeta,
+    config_service, history_transfer,
+    secret_keyring::{self, ISecretKeyring},
+    store_cipher::{self, StoreCipher},
+};
+
+const NAME: &str = "native";
+const STORE_FILE: &str = "history.json";
+/// Kept next to the store, with the same protection.
+const CLIPHIST_FILE: &str = "cliphist.json";
+const PRIVATE_MODE: u32 = 0o600;
+const LOCKED: &str = "History store is locked";
+
+/// Whether the store file is encrypted and, if so, whether its key is known.
+#[derive(Clone, Default)]
+pub enum StoreProtection {
+    #[default]
+    Plain,
+    /// Encrypted, with the key not available: nothing is read or written.
+    Locked,
+    Unlocked(StoreCipher),
+}
+
+/// The application's own history file. It keeps the entries the
+/// application recorded or imported, not those of other providers, and
+/// what cliphist does not record about its own entries.
+#[derive(Clone)]
+pub struct NativeStoreProvider {
+    path: PathBuf,
+    protection: StoreProtection,
+}
+
+impl NativeStoreProvider {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            protection: StoreProtection::Plain,
+        }
+    }
+
+    pub fn with_protection(mut self, protection: StoreProtection) -> Self {
+        self.protection = protection;
+        self
+    }
+
+    pub fn save(&self, entries: &[ClipboardEntry]) -> Result<(), String> {
+        let owned: Vec<ClipboardEntry> = entries
+            .iter()
+            .filter(|entry| !cliboard_provider::is_external(&entry.source))
+            .cloned()
+            .collect();
+
+        self.write(&self.path, history_transfer::to_json(&owned)?.into_bytes())?;
+
+        let meta = CliphistMeta::from_entries(entries);
+        let meta_path = self.cliphist_path();
+        if meta.is_empty() && !meta_path.exists() {
+            return Ok(());
+        }
+        let data = serde_json::to_vec(&meta).map_err(|err| format!("JSON encode failed: {err}"))?;
+        self.write(&meta_path, data)
+    }
+
+    /// What was remembered about cliphist entries on the last save.
+    pub fn cliphist_meta(&self) -> Result<CliphistMeta, String> {
+        let Some(data) = self.read(&self.cliphist_path())? else {
+            return Ok(CliphistMeta::default());
+        };
+
+        serde_json::from_slice(&data).map_err(|err| format!("Invalid cliphist data: {err}"))
+    }
+
+    fn cliphist_path(&self) -> PathBuf {
+        self.path.with_file_name(CLIPHIST_FILE)
+    }
+
+    fn write(&self, path: &Path, data: Vec<u8>) -> Result<(), String> {
+        let data = match &self.protection {
+            StoreProtection::Plain => data,
+            StoreProtection::Locked => return Err(LOCKED.to_string()),
+            StoreProtection::Unlocked(cipher) => cipher.encrypt(&data)?,
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|err| format!("Cannot create {}: {err}", dir.display()))?;
+        }
+
+        write_private(path, &data)
+    }
+
+    /// Reads and, when needed, decrypts `path`. Plain files are still read
+    /// when encryption is on, so existing data is migrated by the next save.
+    fn read(&self, path: &Path) -> Result<Option<Vec<u8>>, String> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let data =
+            fs::read(path).map_err(|err| format!("Cannot read {}: {err}", path.display()))?;
+        let data = match (&self.protection, store_cipher::is_encrypted(&data)) {
+            (_, false) => data,
+            (StoreProtection::Unlocked(cipher), true) => cipher.decrypt(&data)?,
+            (_, true) => return Err(LOCKED.to_string()),
+        };
+
+        Ok(Some(data))
+    }
+
+    /// Derives the key from `passphrase`. An existing encrypted file must
+    /// decrypt with it; otherwise a new salt is used.
+    pub fn unlock_with_passphrase(&self, passphrase: &str) -> Result<StoreCipher, String> {
+        match self.read_encrypted()? {
+            Some(data) => {
+                let salt = store_cipher::salt_of(&data)
+                    .ok_or_else(|| "Corrupted history file".to_string())?;
+                let cipher = StoreCipher::from_passphrase(passphrase, salt)?;
+                cipher.decrypt(&data)?;
+                Ok(cipher)
+            }
+            None => StoreCipher::from_passphrase(passphrase, StoreCipher::generate_salt()),
+        }
+    }
+
+    pub fn unlock_with_keyring(
+        &self,
+        keyring: &impl ISecretKeyring,
+    ) -> Result<StoreCipher, String> {
+        let cipher = secret_keyring::keyring_cipher(keyring)?;
+
+        if let Some(data) = self.read_encrypted()? {
+            cipher.decrypt(&data)?;
+        }
+
+        Ok(cipher)
+    }
+
+    /// Whether a passphrase or key was set before, i.e. the file is
+    /// encrypted already.
+    pub fn is_encrypted(&self) -> Result<bool, String> {
+        Ok(self.read_encrypted()?.is_some())
+    }
+
+    fn read_encrypted(&self) -> Result<Option<Vec<u8>>, String> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        let data = fs::read(&self.path)
+            .map_err(|err| format!("Cannot read {}: {err}", self.path.display()))?;
+        Ok(store_cipher::is_encrypted(&data).then_some(data))
+    }
+}
+
+impl Default for NativeStoreProvider {
+    fn default() -> Self {
+        Self::new(config_service::app_data_dir().join(STORE_FILE))
+    }
+}
+
+impl IClipboardProvider for NativeStoreProvider {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn list_entries(&self) -> Result<Vec<ProviderEntry>, String> {
+        let Some(data) = self.read(&self.path)? else {
+            return Ok(Vec::new());
+        };
+
+        // Stores written before other providers' entries were left out may
+        // still hold some.
+        let json = String::from_utf8(data).map_err(|err| format!("Invalid history file: {err}"))?;
+        Ok(history_transfer::from_json(&json)?
+            .into_iter()
+            .filter(|entry| !cliboard_provider::is_external(&entry.source))
+            .map(ProviderEntry::from)
+            .collect())
+    }
+}
+
+/// Writes `data` readable by the current user only. The data goes to a
+/// temporary file first, so a crash never leaves a half-written store.
+fn write_private(path: &Path, data: &[u8]) -> Result<(), String> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{file_name}.tmp"));
+    let error = |err: std::io::Error| format!("Cannot write {}: {err}", path.display());
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(PRIVATE_MODE)
+        .open(&temp_path)
+        .map_err(error)?;
+
+    // The mode only applies to new files.
+    let written = file
+        .set_permissions(fs::Permissions::from_mode(PRIVATE_MODE))
+        .and_then(|_| file.write_all(data))
+        .and_then(|_| file.sync_all())
+        .and_then(|_| fs::rename(&temp_path, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    written.map_err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::service::{
+        cliboard_history::{ClipboardContent, ClipboardFormat, IClipboardEntry},
+        cliphist_provider::CliphistProvider,
+    };
+
+    use super::*;
+
+    fn store_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "clipboard_manager_store_{}_{name}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(STORE_FILE)
+    }
+
+    #[test]
+    fn encrypted_store_round_trips_and_stays_private() {
+        let path = store_path("encrypted");
+        let plain = NativeStoreProvider::new(path.clone());
+        let cipher = plain.unlock_with_passphrase("hunter2").unwrap();
+        let store = plain.with_protection(StoreProtection::Unlocked(cipher));
+
+        let entry = ClipboardEntry::new(
+            ClipboardContent::Text("top secret note".to_string()),
+            Some("clipboard".to_string()),
+            None,
+        );
+        store.save(&[entry]).unwrap();
+
+        let data = fs::read(&path).unwrap();
+        assert!(store_cipher::is_encrypted(&data));
+        assert!(!String::from_utf8_lossy(&data).contains("top secret"));
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            PRIVATE_MODE
+        );
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+
+        let entries = store.list_entries().unwrap();
+        assert_eq!(entries[0].content.as_text(), "top secret note");
+
+        let reopened = NativeStoreProvider::new(path.clone());
+        assert!(reopened.unlock_with_passphrase("wrong").is_err());
+        assert!(reopened.list_entries().is_err());
+        assert!(
+            reopened
+                .clone()
+                .with_protection(StoreProtection::Locked)
+                .save(&[])
+                .is_err()
+        );
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn keeps_every_stored_format() {
+        let path = store_path("formats");
+        let store = NativeStoreProvider::new(path.clone());
+
+        let mut entry = ClipboardEntry::new(
+            ClipboardContent::Text("cell".to_string()),
+            Some("clipboard".to_string()),
+            None,
+        );
+        entry.formats = vec![
+            ClipboardFormat::new("text/plain;charset=utf-8", b"cell"),
+            ClipboardFormat::new("application/x-qt-windows-mime;value=\"Csv\"", b"cell\r\n"),
+        ];
+        store.save(&[entry.clone()]).unwrap();
+
+        let entries = store.list_entries().unwrap();
+        assert_eq!(entries[0].formats, entry.formats);
+        assert!(!path.with_file_name(CLIPHIST_FILE).exists());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn remembers_cliphist_entries_separately() {
+        let path = store_path("cliphist");
+        let store = NativeStoreProvider::new(path.clone());
+
+        let mut entry = ClipboardEntry::new(
+            ClipboardContent::Text("from cliphist".to_string()),
+            Some(CliphistProvider.name().to_string()),
+            Some("42".to_string()),
+        );
+        entry.timestamp -= chrono::Duration::days(2);
+        store.save(&[entry.clone()]).unwrap();
+
+        assert!(store.list_entries().unwrap().is_empty());
+        assert_eq!(
+            store.cliphist_meta().unwrap(),
+            CliphistMeta::from_entries(&[entry])
+        );
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn keeps_only_its_own_entries() {
+        let path = store_path("own");
+        let store = NativeStoreProvider::new(path.clone());
+        let entry = |text: &str, source: &str| {
+            ClipboardEntry::new(
+                ClipboardContent::Text(text.to_string()),
+                Some(source.to_string()),
+                None,
+            )
+        };
+
+        store
+            .save(&[
+                entry("copied", "clipboard"),
+                entry("from copyq", "copyq"),
+                entry("from a file", "file"),
+                entry("imported", "import"),
+            ])
+            .unwrap();
+
+        let texts: Vec<String> = store
+            .list_entries()
+            .unwrap()
+            .iter()
+            .map(|entry| entry.content.as_text())
+            .collect();
+        assert_eq!(texts, ["copied", "imported"]);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,100 @@
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+/// Where the popup window opens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PopupAnchor {
+    /// Next to the pointer when its position is known, centered otherwise.
+    #[default]
+    Pointer,
+    Center,
+}
+
+/// A monitor's area in global coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Area {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Area {
+    pub fn contains(&self, (x, y): (i32, i32)) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+}
+
+/// Gap between the pointer and the popup, and between the popup and the
+/// monitor edges.
+const GAP: i32 = 8;
+
+/// The popup's offset from the top left corner of `monitor` so that it opens
+/// below and right of `pointer`, or flipped to stay on the monitor.
+pub fn offset_near(pointer: (i32, i32), monitor: Area, size: (i32, i32)) -> (i32, i32) {
+    let place = |pointer: i32, start: i32, length: i32, size: i32| {
+        let local = pointer - start;
+        let offset = if local + GAP + size <= length - GAP {
+            local + GAP
+        } else {
+            local - GAP - size
+        };
+        offset.clamp(0, (length - size).max(0))
+    };
+
+    (
+        place(pointer.0, monitor.x, monitor.width, size.0),
+        place(pointer.1, monitor.y, monitor.height, size.1),
+    )
+}
+
+/// Wayland does not let clients read the global pointer position, so it is
+/// asked from the compositor where that is possible. Only Hyprland offers it.
+pub fn pointer_position() -> Option<(i32, i32)> {
+    std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE")?;
+
+    let output = Command::new("hyprctl").arg("cursorpos").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    parse_position(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parses `x, y` as printed by `hyprctl cursorpos`.
+fn parse_position(text: &str) -> Option<(i32, i32)> {
+    let (x, y) = text.trim().split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MONITOR: Area = Area {
+        x: 1920,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    };
+
+    #[test]
+    fn opens_next_to_the_pointer_and_stays_on_the_monitor() {
+        assert_eq!(offset_near((2020, 100), MONITOR, (400, 300)), (108, 108));
+        // Flipped above and left of the pointer near the bottom right.
+        assert_eq!(offset_near((3800, 1000), MONITOR, (400, 300)), (1472, 692));
+        // Larger than the monitor: pinned to the corner.
+        assert_eq!(offset_near((2000, 10), MONITOR, (2000, 300)), (0, 18));
+        assert!(MONITOR.contains((1920, 0)));
+        assert!(!MONITOR.contains((3840, 0)));
+    }
+
+    #[test]
+    fn parses_cursor_positions() {
+        assert_eq!(parse_position("1280, 720\n"), Some((1280, 720)));
+        assert_eq!(parse_position("-5,10"), Some((-5, 10)));
+        assert_eq!(parse_position("error"), None);
+    }
+}
@@ -0,0 +1,70 @@
+use chrono::{DateTime, Duration, Local};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RecordingState {
+    #[default]
+    Recording,
+    /// New clipboard content is ignored until `until`, or until recording is
+    /// resumed when there is no end.
+    Paused { until: Option<DateTime<Local>> },
+}
+
+impl RecordingState {
+    /// Pauses for `minutes` from `now`; 0 pauses until resumed.
+    pub fn paused_for(minutes: u32, now: DateTime<Local>) -> Self {
+        Self::Paused {
+            until: (minutes > 0).then(|| now + Duration::minutes(minutes.into())),
+        }
+    }
+
+    pub fn is_paused_at(&self, now: DateTime<Local>) -> bool {
+        match self {
+            Self::Recording => false,
+            Self::Paused { until: None } => true,
+            Self::Paused { until: Some(until) } => now < *until,
+        }
+    }
+
+    /// The header indicator text, if recording is paused.
+    pub fn description(&self) -> Option<String> {
+        match self {
+            Self::Recording => None,
+            Self::Paused { until: None } => Some("Recording paused".to_string()),
+            Self::Paused { until: Some(until) } => {
+                Some(format!("Recording paused until {}", until.format("%H:%M")))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 3, 19, 16, 45, 0).unwrap()
+    }
+
+    #[test]
+    fn timed_pause_expires() {
+        let state = RecordingState::paused_for(15, now());
+
+        assert!(state.is_paused_at(now() + Duration::minutes(14)));
+        assert!(!state.is_paused_at(now() + Duration::minutes(15)));
+        assert_eq!(
+            state.description().as_deref(),
+            Some("Recording paused until 17:00")
+        );
+    }
+
+    #[test]
+    fn open_pause_lasts_until_resumed() {
+        let state = RecordingState::paused_for(0, now());
+
+        assert!(state.is_paused_at(now() + Duration::days(30)));
+        assert!(!RecordingState::Recording.is_paused_at(now()));
+        assert_eq!(RecordingState::Recording.description(), None);
+    }
+}
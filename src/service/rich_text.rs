@@ -0,0 +1,385 @@
+use gtk::glib;
+
+pub const HTML_MIME: &str = "text/html";
+pub const RTF_MIMES: [&str; 3] = ["text/rtf", "application/rtf", "text/richtext"];
+
+/// Elements whose content is never shown.
+const HIDDEN_ELEMENTS: [&str; 4] = ["head", "script", "style", "title"];
+
+/// Elements that start on a new line.
+const BLOCK_ELEMENTS: [&str; 17] = [
+    "address",
+    "blockquote",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "tr",
+];
+
+/// Decodes text read from the clipboard. Some applications write UTF-16
+/// with a byte order mark instead of UTF-8.
+pub fn decode_text(bytes: &[u8]) -> String {
+    let utf16 = match bytes {
+        [0xFF, 0xFE, rest @ ..] => Some((rest, u16::from_le_bytes as fn([u8; 2]) -> u16)),
+        [0xFE, 0xFF, rest @ ..] => Some((rest, u16::from_be_bytes as fn([u8; 2]) -> u16)),
+        _ => None,
+    };
+
+    match utf16 {
+        Some((rest, decode)) => {
+            let units: Vec<u16> = rest
+                .chunks_exact(2)
+                .map(|pair| decode([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        None => String::from_utf8_lossy(bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes))
+            .trim_end_matches('\0')
+            .to_string(),
+    }
+}
+
+/// The visible text of an HTML fragment, used when no plain text was offered.
+pub fn html_to_plain(html: &str) -> String {
+    Renderer::new(false, None).render(html)
+}
+
+/// Converts an HTML fragment to Pango markup for the row preview, keeping
+/// emphasis, code and links, and stopping after `max_chars` visible
+/// characters.
+pub fn html_to_markup(html: &str, max_chars: usize) -> String {
+    Renderer::new(true, Some(max_chars)).render(html)
+}
+
+struct Renderer {
+    markup: bool,
+    limit: Option<usize>,
+    out: String,
+    visible: usize,
+    open: Vec<&'static str>,
+    hidden: Option<String>,
+    preformatted: usize,
+    pending_space: bool,
+    pending_break: bool,
+    truncated: bool,
+}
+
+impl Renderer {
+    fn new(markup: bool, limit: Option<usize>) -> Self {
+        Self {
+            markup,
+            limit,
+            out: String::new(),
+            visible: 0,
+            open: Vec::new(),
+            hidden: None,
+            preformatted: 0,
+            pending_space: false,
+            pending_break: false,
+            truncated: false,
+        }
+    }
+
+    fn render(mut self, html: &str) -> String {
+        let mut rest = html;
+
+        while !rest.is_empty() && !self.truncated {
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+                continue;
+            }
+
+            match rest.find('<') {
+                Some(0) => match rest.find('>') {
+                    Some(end) => {
+                        self.tag(&rest[1..end]);
+                        rest = &rest[end + 1..];
+                    }
+                    None => {
+                        self.text(rest);
+                        rest = "";
+                    }
+                },
+                Some(start) => {
+                    self.text(&rest[..start]);
+                    rest = &rest[start..];
+                }
+                None => {
+                    self.text(rest);
+                    rest = "";
+                }
+            }
+        }
+
+        if self.truncated {
+            self.out.push('…');
+        }
+
+        while let Some(tag) = self.open.pop() {
+            self.close_markup(tag);
+        }
+
+        self.out
+    }
+
+    fn tag(&mut self, raw: &str) {
+        let raw = raw.trim();
+        let (closing, body) = match raw.strip_prefix('/') {
+            Some(body) => (true, body),
+            None => (false, raw),
+        };
+        let name_end = body
+            .find(|ch: char| ch.is_whitespace() || ch == '/')
+            .unwrap_or(body.len());
+        let name = body[..name_end].to_ascii_lowercase();
+
+        if let Some(hidden) = &self.hidden {
+            if closing && *hidden == name {
+                self.hidden = None;
+            }
+            return;
+        }
+
+        if HIDDEN_ELEMENTS.contains(&name.as_str()) {
+            if !closing && !body.ends_with('/') {
+                self.hidden = Some(name);
+            }
+            return;
+        }
+
+        if name == "br" {
+            self.line_break();
+            return;
+        }
+
+        if BLOCK_ELEMENTS.contains(&name.as_str()) {
+            self.line_break();
+            if name == "pre" {
+                self.preformatted = if closing {
+                    self.preformatted.saturating_sub(1)
+                } else {
+                    self.preformatted + 1
+                };
+            }
+            if name == "li" && !closing {
+                self.push_visible("• ");
+            }
+        } else if matches!(name.as_str(), "td" | "th") && !closing {
+            self.pending_space = true;
+        }
+
+        let Some(markup_tag) = markup_tag(&name) else {
+            return;
+        };
+
+        if closing {
+            if let Some(position) = self.open.iter().rposition(|open| *open == markup_tag) {
+                for tag in self.open.split_off(position).into_iter().rev() {
+                    self.close_markup(tag);
+                }
+            }
+        } else {
+            // Keep the space before the element outside of it.
+            if self.pending_space && !self.pending_break && self.visible > 0 {
+                self.out.push(' ');
+                self.visible += 1;
+                self.pending_space = false;
+            }
+            self.open.push(markup_tag);
+            if self.markup {
+                self.out.push_str(&format!("<{markup_tag}>"));
+            }
+        }
+    }
+
+    fn close_markup(&mut self, tag: &str) {
+        if self.markup {
+            self.out.push_str(&format!("</{tag}>"));
+        }
+    }
+
+    fn text(&mut self, raw: &str) {
+        if self.hidden.is_some() {
+            return;
+        }
+
+        let decoded = decode_entities(raw);
+
+        if self.preformatted > 0 {
+            for (index, line) in decoded.split('\n').enumerate() {
+                if index > 0 {
+                    self.line_break();
+                }
+                self.push_visible(line);
+            }
+            return;
+        }
+
+        let mut words = decoded.split_ascii_whitespace().peekable();
+        if decoded.starts_with(|ch: char| ch.is_ascii_whitespace()) {
+            self.pending_space = true;
+        }
+
+        while let Some(word) = words.next() {
+            self.push_visible(word);
+            if words.peek().is_some() {
+                self.pending_space = true;
+            }
+        }
+
+        if decoded.ends_with(|ch: char| ch.is_ascii_whitespace()) {
+            self.pending_space = true;
+        }
+    }
+
+    fn line_break(&mut self) {
+        self.pending_break = self.visible > 0;
+        self.pending_space = false;
+    }
+
+    /// Appends visible text, emitting the separators pending before it.
+    fn push_visible(&mut self, text: &str) {
+        if text.is_empty() || self.truncated {
+            return;
+        }
+
+        if self.pending_break {
+            self.out.push('\n');
+            self.visible += 1;
+        } else if self.pending_space && self.visible > 0 {
+            self.out.push(' ');
+            self.visible += 1;
+        }
+        self.pending_break = false;
+        self.pending_space = false;
+
+        let mut shown = text;
+        if let Some(limit) = self.limit {
+            let remaining = limit.saturating_sub(self.visible);
+            if let Some((cut, _)) = text.char_indices().nth(remaining) {
+                shown = &text[..cut];
+                self.truncated = true;
+            }
+        }
+
+        self.visible += shown.chars().count();
+        if self.markup {
+            self.out.push_str(&glib::markup_escape_text(shown));
+        } else {
+            self.out.push_str(shown);
+        }
+    }
+}
+
+fn markup_tag(name: &str) -> Option<&'static str> {
+    match name {
+        "b" | "strong" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => Some("b"),
+        "i" | "em" | "cite" | "var" => Some("i"),
+        "u" | "ins" | "a" => Some("u"),
+        "s" | "strike" | "del" => Some("s"),
+        "code" | "tt" | "kbd" | "samp" | "pre" => Some("tt"),
+        "sub" => Some("sub"),
+        "sup" => Some("sup"),
+        _ => None,
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| Some((decode_entity(&rest[1..end])?, end)));
+
+        match entity {
+            Some((ch, end)) => {
+                decoded.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAGMENT: &str = "<html><head><style>p { color: red }</style></head><body>\
+        <!--StartFragment--><h1>Release&nbsp;notes</h1>\n<p>Use <b>bold</b> and <a href=\"https://example.com\">links</a> &amp; more.</p>\
+        <ul><li>one</li><li><code>two</code></li></ul><!--EndFragment--></body></html>";
+
+    #[test]
+    fn extracts_visible_text() {
+        assert_eq!(
+            html_to_plain(FRAGMENT),
+            "Release\u{a0}notes\nUse bold and links & more.\n• one\n• two"
+        );
+    }
+
+    #[test]
+    fn renders_pango_markup() {
+        assert_eq!(
+            html_to_markup(FRAGMENT, 100),
+            "<b>Release\u{a0}notes</b>\nUse <b>bold</b> and <u>links</u> &amp; more.\n• one\n• <tt>two</tt>"
+        );
+        assert_eq!(
+            html_to_markup("<p><i>unfinished <b>emphasis</b> text", 15),
+            "<i>unfinished <b>emph…</b></i>"
+        );
+    }
+
+    #[test]
+    fn decodes_utf16_and_utf8_with_bom() {
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("<b>hé</b>".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+
+        assert_eq!(decode_text(&utf16), "<b>hé</b>");
+        assert_eq!(decode_text(b"\xEF\xBB\xBFplain\0"), "plain");
+    }
+}
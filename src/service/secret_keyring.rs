@@ -0,0 +1,122 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+
+use crate::service::store_cipher::StoreCipher;
+
+const COMMAND: &str = "secret-tool";
+const LABEL: &str = "Clipboard Manager history key";
+const ATTRIBUTES: [&str; 4] = ["application", "clipboard_manager", "kind", "history-key"];
+
+pub trait ISecretKeyring {
+    fn lookup(&self) -> Result<Option<Vec<u8>>, String>;
+
+    fn store(&self, secret: &[u8]) -> Result<(), String>;
+}
+
+/// The Secret Service keyring (GNOME Keyring, KWallet), through `secret-tool`.
+#[derive(Clone, Copy, Default)]
+pub struct SecretServiceKeyring;
+
+impl ISecretKeyring for SecretServiceKeyring {
+    fn lookup(&self) -> Result<Option<Vec<u8>>, String> {
+        let output = Command::new(COMMAND)
+            .arg("lookup")
+            .args(ATTRIBUTES)
+            .output()
+            .map_err(|err| format!("{COMMAND} lookup failed: {err}"))?;
+
+        // secret-tool exits with 1 and prints nothing when there is no match.
+        if !output.status.success() {
+            return if output.stderr.is_empty() {
+                Ok(None)
+            } else {
+                Err(format!(
+                    "{COMMAND} lookup failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ))
+            };
+        }
+
+        BASE64
+            .decode(output.stdout.trim_ascii())
+            .map(Some)
+            .map_err(|err| format!("Invalid key in keyring: {err}"))
+    }
+
+    fn store(&self, secret: &[u8]) -> Result<(), String> {
+        let mut child = Command::new(COMMAND)
+            .arg("store")
+            .arg(format!("--label={LABEL}"))
+            .args(ATTRIBUTES)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("{COMMAND} store failed: {err}"))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(BASE64.encode(secret).as_bytes())
+                .map_err(|err| format!("{COMMAND} store failed: {err}"))?;
+        }
+
+        let status = child
+            .wait()
+            .map_err(|err| format!("{COMMAND} store failed: {err}"))?;
+
+        if !status.success() {
+            return Err(format!("{COMMAND} store non-zero exit: {status}"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the cipher for the key kept in `keyring`, creating and storing a
+/// random key on first use.
+pub fn keyring_cipher(keyring: &impl ISecretKeyring) -> Result<StoreCipher, String> {
+    if let Some(key) = keyring.lookup()? {
+        return StoreCipher::from_key(&key);
+    }
+
+    let key = StoreCipher::generate_key();
+    keyring.store(&key)?;
+    StoreCipher::from_key(&key)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MemoryKeyring {
+        secret: RefCell<Option<Vec<u8>>>,
+    }
+
+    impl ISecretKeyring for MemoryKeyring {
+        fn lookup(&self) -> Result<Option<Vec<u8>>, String> {
+            Ok(self.secret.borrow().clone())
+        }
+
+        fn store(&self, secret: &[u8]) -> Result<(), String> {
+            *self.secret.borrow_mut() = Some(secret.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn creates_key_once_and_reuses_it() {
+        let keyring = MemoryKeyring::default();
+
+        let first = keyring_cipher(&keyring).unwrap();
+        let data = first.encrypt(b"history").unwrap();
+
+        let second = keyring_cipher(&keyring).unwrap();
+        assert_eq!(second.decrypt(&data).unwrap(), b"history");
+        assert_eq!(keyring.secret.borrow().as_ref().map(Vec::len), Some(32));
+    }
+}
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+/// The two selections a display offers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Clipboard,
+    /// The selection pasted with a middle click.
+    Primary,
+}
+
+/// Which way new text is mirrored between the selections.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncDirection {
+    #[default]
+    Off,
+    PrimaryToClipboard,
+    ClipboardToPrimary,
+    Both,
+}
+
+impl SyncDirection {
+    pub const ALL: [Self; 4] = [
+        Self::Off,
+        Self::PrimaryToClipboard,
+        Self::ClipboardToPrimary,
+        Self::Both,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::PrimaryToClipboard => "primary-to-clipboard",
+            Self::ClipboardToPrimary => "clipboard-to-primary",
+            Self::Both => "both",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|direction| direction.name() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::PrimaryToClipboard => "Primary to Clipboard",
+            Self::ClipboardToPrimary => "Clipboard to Primary",
+            Self::Both => "Both Ways",
+        }
+    }
+
+    pub fn mirrors_from(self, selection: Selection) -> bool {
+        matches!(
+            (self, selection),
+            (Self::Both, _)
+                | (Self::PrimaryToClipboard, Selection::Primary)
+                | (Self::ClipboardToPrimary, Selection::Clipboard)
+        )
+    }
+}
+
+/// Decides which selection changes are mirrored into the other selection.
+#[derive(Debug, Clone, Default)]
+pub struct SelectionSync {
+    pub direction: SyncDirection,
+    /// Shorter text, such as a stray double-click on a word fragment, is not
+    /// mirrored.
+    pub min_length: usize,
+    last_mirrored: Option<String>,
+}
+
+impl SelectionSync {
+    pub fn new(direction: SyncDirection, min_length: usize) -> Self {
+        Self {
+            direction,
+            min_length,
+            last_mirrored: None,
+        }
+    }
+
+    /// Whether `text`, newly seen in `from`, should be written to the other
+    /// selection. Called for changes in both selections. Text that was just
+    /// mirrored is not sent back, which breaks loops with other tools that
+    /// sync the selections; any other text ends that.
+    pub fn should_mirror(&mut self, from: Selection, text: &str) -> bool {
+        if self.last_mirrored.as_deref() == Some(text) {
+            return false;
+        }
+        self.last_mirrored = None;
+
+        if !self.direction.mirrors_from(from)
+            || text.trim().chars().count() < self.min_length.max(1)
+        {
+            return false;
+        }
+
+        self.last_mirrored = Some(text.to_string());
+        true
+    }
+
+    /// Forgets the mirrored text after a selection changed to content that
+    /// is not mirrored.
+    pub fn reset(&mut self) {
+        self.last_mirrored = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mirrors_only_in_the_configured_direction() {
+        let mut sync = SelectionSync::new(SyncDirection::PrimaryToClipboard, 1);
+
+        assert!(sync.should_mirror(Selection::Primary, "selected"));
+        assert!(!sync.should_mirror(Selection::Clipboard, "copied"));
+
+        sync.direction = SyncDirection::Off;
+        assert!(!sync.should_mirror(Selection::Primary, "other"));
+    }
+
+    #[test]
+    fn does_not_send_mirrored_text_back() {
+        let mut sync = SelectionSync::new(SyncDirection::Both, 1);
+
+        assert!(sync.should_mirror(Selection::Primary, "ping"));
+        assert!(!sync.should_mirror(Selection::Clipboard, "ping"));
+        assert!(sync.should_mirror(Selection::Clipboard, "pong"));
+        assert!(sync.should_mirror(Selection::Primary, "ping"));
+    }
+
+    #[test]
+    fn mirrors_the_same_text_again_after_other_changes() {
+        let mut sync = SelectionSync::new(SyncDirection::PrimaryToClipboard, 1);
+
+        assert!(sync.should_mirror(Selection::Primary, "selected"));
+        assert!(!sync.should_mirror(Selection::Clipboard, "unrelated copy"));
+        assert!(sync.should_mirror(Selection::Primary, "selected"));
+
+        sync.reset();
+        assert!(sync.should_mirror(Selection::Primary, "selected"));
+    }
+
+    #[test]
+    fn skips_short_and_blank_text() {
+        let mut sync = SelectionSync::new(SyncDirection::Both, 3);
+
+        assert!(!sync.should_mirror(Selection::Primary, "ab"));
+        assert!(!sync.should_mirror(Selection::Primary, "  a  "));
+        assert!(sync.should_mirror(Selection::Primary, "abc"));
+        assert_eq!(
+            SyncDirection::from_name("clipboard-to-primary"),
+            Some(SyncDirection::ClipboardToPrimary)
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use argon2::Argon2;
+use chacha20poly1305::{
+    Key, KeyInit, XChaCha20Poly1305, XNonce,
+    aead::{Aead, AeadCore, OsRng, rand_core::RngCore},
+};
+
+/// Prefix of every encrypted store file, followed by the salt, the nonce and
+/// the ciphertext.
+const MAGIC: &[u8; 8] = b"CLIPENC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+pub const KEY_LEN: usize = 32;
+
+pub type Salt = [u8; SALT_LEN];
+
+/// Encrypts store files with XChaCha20-Poly1305. The key either comes from a
+/// passphrase stretched with Argon2 or is a random key kept in a keyring.
+#[derive(Clone)]
+pub struct StoreCipher {
+    key: Key,
+    salt: Salt,
+}
+
+impl StoreCipher {
+    pub fn from_passphrase(passphrase: &str, salt: Salt) -> Result<Self, String> {
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|err| format!("Key derivation failed: {err}"))?;
+
+        Ok(Self { key, salt })
+    }
+
+    pub fn from_key(key: &[u8]) -> Result<Self, String> {
+        if key.len() != KEY_LEN {
+            return Err(format!("Expected a {KEY_LEN}-byte key, got {}", key.len()));
+        }
+
+        Ok(Self {
+            key: *Key::from_slice(key),
+            salt: Salt::default(),
+        })
+    }
+
+    pub fn generate_key() -> Vec<u8> {
+        XChaCha20Poly1305::generate_key(&mut OsRng).to_vec()
+    }
+
+    pub fn generate_salt() -> Salt {
+        let mut salt = Salt::default();
+        OsRng.fill_bytes(&mut salt);
+        salt
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = XChaCha20Poly1305::new(&self.key)
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| "Encryption failed".to_string())?;
+
+        let mut data = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&self.salt);
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&ciphertext);
+
+        Ok(data)
+    }
+
+    /// Fails when `data` was encrypted with another key or was tampered with.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let body = data
+            .strip_prefix(MAGIC.as_slice())
+            .filter(|body| body.len() >= SALT_LEN + NONCE_LEN)
+            .ok_or_else(|| "Not an encrypted history file".to_string())?;
+
+        let (nonce, ciphertext) = body[SALT_LEN..].split_at(NONCE_LEN);
+        XChaCha20Poly1305::new(&self.key)
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Wrong passphrase or corrupted history file".to_string())
+    }
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// The salt a passphrase must be stretched with to decrypt `data`.
+pub fn salt_of(data: &[u8]) -> Option<Salt> {
+    data.strip_prefix(MAGIC.as_slice())?
+        .get(..SALT_LEN)?
+        .try_into()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_with_the_same_passphrase() {
+        let salt = StoreCipher::generate_salt();
+        let cipher = StoreCipher::from_passphrase("correct horse", salt).unwrap();
+        let data = cipher.encrypt(b"clipboard secret").unwrap();
+
+        assert!(is_encrypted(&data));
+        assert_eq!(salt_of(&data), Some(salt));
+        assert!(!data.windows(6).any(|window| window == b"secret"));
+
+        let reopened =
+            StoreCipher::from_passphrase("correct horse", salt_of(&data).unwrap()).unwrap();
+        assert_eq!(reopened.decrypt(&data).unwrap(), b"clipboard secret");
+    }
+
+    #[test]
+    fn rejects_wrong_key_and_tampering() {
+        let cipher = StoreCipher::from_key(&StoreCipher::generate_key()).unwrap();
+        let mut data = cipher.encrypt(b"payload").unwrap();
+
+        let other = StoreCipher::from_key(&StoreCipher::generate_key()).unwrap();
+        assert!(other.decrypt(&data).is_err());
+
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert!(cipher.decrypt(&data).is_err());
+        assert!(StoreCipher::from_key(b"short").is_err());
+    }
+}
@@ -10,7 +10,7 @@ impl StyleService {
     pub fn apply_styles(&self, display: &gdk::Display) {
         let provider = gtk::CssProvider::new();
         provider.load_from_string(
-            "listboxrow.current-clipboard {\n  background-color: alpha(@accent_bg_color, 0.15);\n}\nlistboxrow.current-clipboard:selected {\n  background-color: alpha(@accent_bg_color, 0.35);\n}\n",
+            "listboxrow.current-clipboard {\n  background-color: alpha(@accent_bg_color, 0.15);\n}\nlistboxrow.current-clipboard:selected {\n  background-color: alpha(@accent_bg_color, 0.35);\n}\nheaderbar.recording-paused {\n  background-color: alpha(@warning_bg_color, 0.25);\n}\nlabel.row-badge {\n  min-width: 1em;\n  font-weight: bold;\n}\nlistboxrow.collapsed-group {\n  min-height: 0;\n  padding: 0;\n}\nbutton.group-header {\n  border-radius: 0;\n  padding: 6px 12px;\n}\nbutton.filter-chip {\n  min-height: 0;\n  padding: 2px 10px;\n}\nwindow.popup row > box.header {\n  min-height: 0;\n  padding-top: 4px;\n  padding-bottom: 4px;\n}\nwindow.popup row .subtitle {\n  font-size: smaller;\n}\n",
         );
         gtk::style_context_add_provider_for_display(
             display,
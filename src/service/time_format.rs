@@ -0,0 +1,279 @@
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Local};
+use gtk::glib;
+use serde::{Deserialize, Serialize};
+
+pub trait IClock {
+    fn now(&self) -> DateTime<Local>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl IClock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeStyle {
+    #[default]
+    Relative,
+    Absolute,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    Portuguese,
+    Spanish,
+    German,
+    French,
+}
+
+#[derive(Clone, Copy)]
+enum Unit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Language {
+    /// The first supported language in the user's locale preferences.
+    pub fn current() -> Self {
+        static CURRENT: OnceLock<Language> = OnceLock::new();
+
+        *CURRENT.get_or_init(|| {
+            glib::language_names()
+                .iter()
+                .find_map(|name| Self::from_locale(name))
+                .unwrap_or(Self::English)
+        })
+    }
+
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let code = locale
+            .split(['_', '.', '@', '-'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        match code.as_str() {
+            "en" => Some(Self::English),
+            "pt" => Some(Self::Portuguese),
+            "es" => Some(Self::Spanish),
+            "de" => Some(Self::German),
+            "fr" => Some(Self::French),
+            _ => None,
+        }
+    }
+
+    fn just_now(self) -> &'static str {
+        match self {
+            Self::English => "Just Now",
+            Self::Portuguese => "Agora mesmo",
+            Self::Spanish => "Justo ahora",
+            Self::German => "Gerade eben",
+            Self::French => "À l'instant",
+        }
+    }
+
+    fn yesterday(self) -> &'static str {
+        match self {
+            Self::English => "Yesterday",
+            Self::Portuguese => "Ontem",
+            Self::Spanish => "Ayer",
+            Self::German => "Gestern",
+            Self::French => "Hier",
+        }
+    }
+
+    fn absolute_format(self) -> &'static str {
+        match self {
+            Self::English => "%Y-%m-%d %H:%M",
+            Self::German => "%d.%m.%Y %H:%M",
+            Self::Portuguese | Self::Spanish | Self::French => "%d/%m/%Y %H:%M",
+        }
+    }
+
+    fn is_singular(self, count: i64) -> bool {
+        match self {
+            Self::French => count <= 1,
+            _ => count == 1,
+        }
+    }
+
+    fn unit(self, unit: Unit, count: i64) -> &'static str {
+        let (one, other) = match (self, unit) {
+            (Self::English, Unit::Minute) => ("minute", "minutes"),
+            (Self::English, Unit::Hour) => ("hour", "hours"),
+            (Self::English, Unit::Day) => ("day", "days"),
+            (Self::English, Unit::Week) => ("week", "weeks"),
+            (Self::English, Unit::Month) => ("month", "months"),
+            (Self::English, Unit::Year) => ("year", "years"),
+            (Self::Portuguese, Unit::Minute) => ("minuto", "minutos"),
+            (Self::Portuguese, Unit::Hour) => ("hora", "horas"),
+            (Self::Portuguese, Unit::Day) => ("dia", "dias"),
+            (Self::Portuguese, Unit::Week) => ("semana", "semanas"),
+            (Self::Portuguese, Unit::Month) => ("mês", "meses"),
+            (Self::Portuguese, Unit::Year) => ("ano", "anos"),
+            (Self::Spanish, Unit::Minute) => ("minuto", "minutos"),
+            (Self::Spanish, Unit::Hour) => ("hora", "horas"),
+            (Self::Spanish, Unit::Day) => ("día", "días"),
+            (Self::Spanish, Unit::Week) => ("semana", "semanas"),
+            (Self::Spanish, Unit::Month) => ("mes", "meses"),
+            (Self::Spanish, Unit::Year) => ("año", "años"),
+            (Self::German, Unit::Minute) => ("Minute", "Minuten"),
+            (Self::German, Unit::Hour) => ("Stunde", "Stunden"),
+            (Self::German, Unit::Day) => ("Tag", "Tagen"),
+            (Self::German, Unit::Week) => ("Woche", "Wochen"),
+            (Self::German, Unit::Month) => ("Monat", "Monaten"),
+            (Self::German, Unit::Year) => ("Jahr", "Jahren"),
+            (Self::French, Unit::Minute) => ("minute", "minutes"),
+            (Self::French, Unit::Hour) => ("heure", "heures"),
+            (Self::French, Unit::Day) => ("jour", "jours"),
+            (Self::French, Unit::Week) => ("semaine", "semaines"),
+            (Self::French, Unit::Month) => ("mois", "mois"),
+            (Self::French, Unit::Year) => ("an", "ans"),
+        };
+
+        if self.is_singular(count) { one } else { other }
+    }
+
+    fn ago(self, count: i64, unit: Unit) -> String {
+        let amount = format!("{count} {}", self.unit(unit, count));
+
+        match self {
+            Self::English => format!("{amount} ago"),
+            Self::Portuguese => format!("há {amount}"),
+            Self::Spanish => format!("hace {amount}"),
+            Self::German => format!("vor {amount}"),
+            Self::French => format!("il y a {amount}"),
+        }
+    }
+}
+
+/// Formats `timestamp` as seen at `now`. Relative labels go from minutes up
+/// to years, with the previous calendar day shown as "Yesterday HH:MM".
+pub fn format_timestamp(
+    timestamp: DateTime<Local>,
+    now: DateTime<Local>,
+    style: TimeStyle,
+    language: Language,
+) -> String {
+    if style == TimeStyle::Absolute {
+        return timestamp.format(language.absolute_format()).to_string();
+    }
+
+    let duration = now.signed_duration_since(timestamp);
+    let calendar_days = now
+        .date_naive()
+        .signed_duration_since(timestamp.date_naive())
+        .num_days();
+
+    if duration.num_seconds() < 60 {
+        language.just_now().to_string()
+    } else if duration.num_minutes() < 60 {
+        language.ago(duration.num_minutes(), Unit::Minute)
+    } else if duration.num_hours() < 24 && calendar_days == 0 {
+        language.ago(duration.num_hours(), Unit::Hour)
+    } else if calendar_days <= 1 {
+        format!("{} {}", language.yesterday(), timestamp.format("%H:%M"))
+    } else if calendar_days < 7 {
+        language.ago(calendar_days, Unit::Day)
+    } else if calendar_days < 30 {
+        language.ago(calendar_days / 7, Unit::Week)
+    } else if calendar_days < 365 {
+        language.ago(calendar_days / 30, Unit::Month)
+    } else {
+        language.ago(calendar_days / 365, Unit::Year)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+
+    struct FixedClock(DateTime<Local>);
+
+    impl IClock for FixedClock {
+        fn now(&self) -> DateTime<Local> {
+            self.0
+        }
+    }
+
+    fn clock() -> FixedClock {
+        FixedClock(Local.with_ymd_and_hms(2025, 3, 19, 16, 45, 0).unwrap())
+    }
+
+    fn relative(ago: Duration, language: Language) -> String {
+        let now = clock().now();
+        format_timestamp(now - ago, now, TimeStyle::Relative, language)
+    }
+
+    #[test]
+    fn formats_relative_ranges() {
+        let english = Language::English;
+
+        assert_eq!(relative(Duration::seconds(20), english), "Just Now");
+        assert_eq!(relative(Duration::minutes(1), english), "1 minute ago");
+        assert_eq!(relative(Duration::minutes(42), english), "42 minutes ago");
+        assert_eq!(relative(Duration::hours(3), english), "3 hours ago");
+        assert_eq!(relative(Duration::hours(20), english), "Yesterday 20:45");
+        assert_eq!(relative(Duration::days(3), english), "3 days ago");
+        assert_eq!(relative(Duration::days(15), english), "2 weeks ago");
+        assert_eq!(relative(Duration::days(65), english), "2 months ago");
+        assert_eq!(relative(Duration::days(400), english), "1 year ago");
+    }
+
+    #[test]
+    fn pluralizes_per_language() {
+        assert_eq!(relative(Duration::days(3), Language::German), "vor 3 Tagen");
+        assert_eq!(relative(Duration::days(7), Language::German), "vor 1 Woche");
+        assert_eq!(
+            relative(Duration::days(70), Language::Portuguese),
+            "há 2 meses"
+        );
+        assert_eq!(
+            relative(Duration::hours(20), Language::French),
+            "Hier 20:45"
+        );
+        assert_eq!(
+            relative(Duration::minutes(1), Language::Spanish),
+            "hace 1 minuto"
+        );
+    }
+
+    #[test]
+    fn formats_absolute_time() {
+        let now = clock().now();
+        let timestamp = now - Duration::days(2);
+
+        assert_eq!(
+            format_timestamp(timestamp, now, TimeStyle::Absolute, Language::English),
+            "2025-03-17 16:45"
+        );
+        assert_eq!(
+            format_timestamp(timestamp, now, TimeStyle::Absolute, Language::German),
+            "17.03.2025 16:45"
+        );
+    }
+
+    #[test]
+    fn parses_locale_names() {
+        assert_eq!(
+            Language::from_locale("pt_BR.UTF-8"),
+            Some(Language::Portuguese)
+        );
+        assert_eq!(Language::from_locale("de"), Some(Language::German));
+        assert_eq!(Language::from_locale("C"), None);
+    }
+}
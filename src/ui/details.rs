@@ -0,0 +1,88 @@
+use std::rc::Rc;
+
+use gtk::{glib, prelude::*};
+use libadwaita::{self as adw, prelude::*};
+
+use crate::service::cliboard_history::{ClipboardEntry, ClipboardFormat};
+
+/// Lists every format stored for `entry` with its size. Choosing one calls
+/// `on_copy` with it and closes the dialog.
+pub fn show_formats(
+    parent: &impl IsA<gtk::Widget>,
+    entry: &ClipboardEntry,
+    on_copy: impl Fn(&ClipboardFormat) + 'static,
+) {
+    let list_box = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+
+    let dialog = adw::Dialog::builder()
+        .title("Formats")
+        .content_width(420)
+        .build();
+    let on_copy = Rc::new(on_copy);
+
+    for format in &entry.formats {
+        let row = adw::ActionRow::builder()
+            .title(glib::markup_escape_text(&format.mime_type))
+            .subtitle(glib::format_size(format.data.len() as u64))
+            .activatable(true)
+            .build();
+
+        let copy_button = gtk::Button::builder()
+            .icon_name("edit-copy-symbolic")
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .tooltip_text("Copy only this format")
+            .build();
+        row.add_suffix(&copy_button);
+        row.set_activatable_widget(Some(&copy_button));
+
+        copy_button.connect_clicked(glib::clone!(
+            #[weak]
+            dialog,
+            #[strong]
+            on_copy,
+            #[strong]
+            format,
+            move |_| {
+                on_copy(&format);
+                dialog.close();
+            }
+        ));
+
+        list_box.append(&row);
+    }
+
+    let content = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(12)
+        .margin_top(12)
+        .margin_bottom(24)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+    content.append(
+        &gtk::Label::builder()
+            .label("The application offered this entry in several formats. Copy one to paste exactly that representation.")
+            .wrap(true)
+            .xalign(0.0)
+            .css_classes(["dim-label"])
+            .build(),
+    );
+    content.append(&list_box);
+
+    let toolbar_view = adw::ToolbarView::new();
+    toolbar_view.add_top_bar(&adw::HeaderBar::new());
+    toolbar_view.set_content(Some(
+        &gtk::ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .propagate_natural_height(true)
+            .child(&content)
+            .build(),
+    ));
+
+    dialog.set_child(Some(&toolbar_view));
+    dialog.present(Some(parent));
+}
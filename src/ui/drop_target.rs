@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use gtk::{gdk, glib, prelude::*};
+use libadwaita as adw;
+
+use crate::service::{cliboard_history::ClipboardContent, cliboard_monitor::ClipboardMonitor};
+
+/// Adds files, images and text dropped onto `widget` to the history.
+pub fn setup_drop_target(
+    widget: &impl IsA<gtk::Widget>,
+    clipboard_monitor: ClipboardMonitor,
+    toast_overlay: &adw::ToastOverlay,
+) {
+    let drop_target = gtk::DropTarget::new(glib::Type::INVALID, gdk::DragAction::COPY);
+    drop_target.set_types(&[
+        gdk::FileList::static_type(),
+        gdk::Texture::static_type(),
+        String::static_type(),
+    ]);
+
+    drop_target.connect_drop(glib::clone!(
+        #[weak]
+        toast_overlay,
+        #[upgrade_or]
+        false,
+        move |target, value, _, _| {
+            let from_this_app = target.current_drop().and_then(|drop| drop.drag()).is_some();
+            if !accepts_drop(from_this_app, clipboard_monitor.is_store_locked()) {
+                return false;
+            }
+            let Some(content) = dropped_content(value) else {
+                return false;
+            };
+
+            let message = if clipboard_monitor.add_dropped(content) {
+                "Added to history"
+            } else {
+                "Already in history"
+            };
+            toast_overlay.add_toast(adw::Toast::new(message));
+            true
+        }
+    ));
+
+    widget.add_controller(drop_target);
+}
+
+/// Rows dragged out of the list are entries already, and nothing is added
+/// while the store is locked.
+fn accepts_drop(from_this_app: bool, store_locked: bool) -> bool {
+    !from_this_app && !store_locked
+}
+
+fn dropped_content(value: &glib::Value) -> Option<ClipboardContent> {
+    if let Ok(files) = value.get::<gdk::FileList>() {
+        return files_content(
+            files
+                .files()
+                .iter()
+                .map(|file| file.uri().to_string())
+                .collect(),
+        );
+    }
+
+    if let Ok(texture) = value.get::<gdk::Texture>() {
+        return Some(ClipboardContent::Image(Some(Arc::from(
+            texture.save_to_png_bytes().as_ref(),
+        ))));
+    }
+
+    text_content(value.get::<String>().ok()?)
+}
+
+fn files_content(uris: Vec<String>) -> Option<ClipboardContent> {
+    (!uris.is_empty()).then_some(ClipboardContent::Files {
+        uris,
+        operation: None,
+    })
+}
+
+fn text_content(text: String) -> Option<ClipboardContent> {
+    (!text.trim().is_empty()).then_some(ClipboardContent::Text(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_rows_dragged_from_the_list() {
+        assert!(accepts_drop(false, false));
+        assert!(!accepts_drop(true, false));
+        assert!(!accepts_drop(false, true));
+    }
+
+    #[test]
+    fn turns_dropped_files_and_text_into_entries() {
+        let uri = "file:///home/user/notes.txt".to_string();
+        assert!(matches!(
+            files_content(vec![uri.clone()]),
+            Some(ClipboardContent::Files { uris, operation: None }) if uris == [uri]
+        ));
+        assert!(files_content(Vec::new()).is_none());
+
+        assert!(matches!(
+            text_content("hello".to_string()),
+            Some(ClipboardContent::Text(text)) if text == "hello"
+        ));
+        assert!(text_content(" \n".to_string()).is_none());
+    }
+}
@@ -0,0 +1,87 @@
+use std::{cell::RefCell, collections::BTreeSet};
+
+use gtk::{glib, subclass::prelude::*};
+
+use crate::service::{
+    cliboard_history::{ClipboardEntry, EntryKey},
+    content_filter::ContentFilter,
+    entry_groups::EntryGroup,
+};
+
+/// What the list needs to know about the entry a row shows, to sort,
+/// group and filter without going back to the history.
+#[derive(Debug, Clone)]
+pub struct RowMeta {
+    pub key: EntryKey,
+    /// Where the entry was in the history, to find it again without a
+    /// search.
+    pub index: usize,
+    pub group: EntryGroup,
+    pub kinds: BTreeSet<ContentFilter>,
+    /// The lowercased plain text searches match, whatever the row shows.
+    /// Empty for sensitive entries, so search leaves them out and typing
+    /// cannot probe them.
+    pub text: String,
+}
+
+impl RowMeta {
+    pub fn new(entry: &ClipboardEntry, index: usize, group: EntryGroup) -> Self {
+        let text = match entry.sensitivity {
+            Some(_) => String::new(),
+            None => entry.content.as_text().to_lowercase(),
+        };
+
+        Self {
+            key: entry.key,
+            index,
+            group,
+            kinds: ContentFilter::kinds_of(entry),
+            text,
+        }
+    }
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Default)]
+    pub struct EntryRow {
+        pub meta: RefCell<Option<RowMeta>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for EntryRow {
+        const NAME: &'static str = "ClipboardManagerEntryRow";
+        type Type = super::EntryRow;
+        type ParentType = gtk::ListBoxRow;
+    }
+
+    impl ObjectImpl for EntryRow {}
+    impl WidgetImpl for EntryRow {}
+    impl ListBoxRowImpl for EntryRow {}
+}
+
+glib::wrapper! {
+    /// A list row showing a history entry.
+    pub struct EntryRow(ObjectSubclass<imp::EntryRow>)
+        @extends gtk::ListBoxRow, gtk::Widget,
+        @implements gtk::Accessible, gtk::Actionable, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl EntryRow {
+    pub fn new(meta: RowMeta) -> Self {
+        let row: Self = glib::Object::new();
+        row.imp().meta.replace(Some(meta));
+        row
+    }
+
+    pub fn with_meta<T>(&self, read: impl FnOnce(&RowMeta) -> T) -> Option<T> {
+        self.imp().meta.borrow().as_ref().map(read)
+    }
+
+    pub fn update_meta(&self, update: impl FnOnce(&mut RowMeta)) {
+        if let Some(meta) = self.imp().meta.borrow_mut().as_mut() {
+            update(meta);
+        }
+    }
+}
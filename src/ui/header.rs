@@ -2,15 +2,63 @@ use gtk;
 use gtk::gio;
 use libadwaita as adw;
 
-pub fn build() -> (adw::HeaderBar, gtk::ToggleButton) {
+use crate::{
+    service::entry_sort::SortMode,
+    ui::{paste, selection},
+};
+
+pub fn build(sources: &gio::Menu) -> (adw::HeaderBar, gtk::ToggleButton, adw::WindowTitle) {
     let search_button = gtk::ToggleButton::builder()
         .icon_name("system-search-symbolic")
         .tooltip_text("Search clipboard history")
         .build();
 
+    let pause_button = gtk::ToggleButton::builder()
+        .icon_name("media-playback-pause-symbolic")
+        .tooltip_text("Pause recording")
+        .action_name("app.recording-paused")
+        .build();
+
+    let history_section = gio::Menu::new();
+    history_section.append(Some("Import History…"), Some("win.import-history"));
+    history_section.append(Some("Export History…"), Some("win.export-history"));
+    history_section.append_submenu(Some("Sources"), sources);
+
+    let recording_section = gio::Menu::new();
+    recording_section.append(
+        Some("Pause for 15 Minutes"),
+        Some("app.pause-recording(uint32 15)"),
+    );
+    recording_section.append(
+        Some("Pause for 1 Hour"),
+        Some("app.pause-recording(uint32 60)"),
+    );
+    recording_section.append(
+        Some("Also Pause cliphist"),
+        Some("app.pause-cliphist-watcher"),
+    );
+    recording_section.append(Some("Record Primary Selection"), Some("win.track-primary"));
+    recording_section.append_submenu(Some("Sync Selections"), &selection::build_sync_menu());
+
+    let copy_section = gio::Menu::new();
+    copy_section.append(Some("Close After Copy"), Some("win.close-after-copy"));
+    copy_section.append(Some("Paste After Copy"), Some("win.auto-paste"));
+    copy_section.append_submenu(Some("Paste With"), &paste::build_paste_keys_menu());
+
+    let view_section = gio::Menu::new();
+    view_section.append(Some("Absolute Timestamps"), Some("win.absolute-time"));
+
+    let app_section = gio::Menu::new();
+    app_section.append(Some("Shortcuts"), Some("win.show-shortcuts"));
+    app_section.append(Some("Customize Shortcuts…"), Some("win.edit-shortcuts"));
+    app_section.append(Some("About"), Some("win.show-about"));
+
     let menu = gio::Menu::new();
-    menu.append(Some("Shortcuts"), Some("win.show-shortcuts"));
-    menu.append(Some("About"), Some("win.show-about"));
+    menu.append_section(None, &history_section);
+    menu.append_section(None, &recording_section);
+    menu.append_section(None, &copy_section);
+    menu.append_section(None, &view_section);
+    menu.append_section(None, &app_section);
 
     let menu_button = gtk::MenuButton::builder()
         .icon_name("open-menu-symbolic")
@@ -18,13 +66,31 @@ pub fn build() -> (adw::HeaderBar, gtk::ToggleButton) {
         .tooltip_text("Main Menu")
         .build();
 
+    let title = adw::WindowTitle::new("Clipboard Manager", "");
+
     let header = adw::HeaderBar::builder()
-        .title_widget(&gtk::Label::new(Some("Clipboard Manager")))
+        .title_widget(&title)
         .show_title(true)
         .build();
 
     header.pack_start(&search_button);
     header.pack_end(&menu_button);
+    header.pack_end(&pause_button);
+
+    (header, search_button, title)
+}
+
+/// Picks how entries are ordered within their groups.
+pub fn build_sort_dropdown(selected: SortMode) -> gtk::DropDown {
+    let labels: Vec<&str> = SortMode::ALL.iter().map(|mode| mode.label()).collect();
+    let selected = SortMode::ALL
+        .iter()
+        .position(|mode| *mode == selected)
+        .unwrap_or_default();
 
-    (header, search_button)
+    gtk::DropDown::builder()
+        .model(&gtk::StringList::new(&labels))
+        .selected(selected as u32)
+        .tooltip_text("Sort entries")
+        .build()
 }
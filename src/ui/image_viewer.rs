@@ -0,0 +1,565 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+    sync::Arc,
+};
+
+use gtk::{
+    cairo, gdk,
+    gdk_pixbuf::{Pixbuf, PixbufRotation},
+    gio, glib,
+    prelude::*,
+};
+use libadwaita::{self as adw, prelude::*};
+
+use crate::{
+    service::{
+        cliboard_history::ClipboardContent,
+        cliboard_monitor::ClipboardMonitor,
+        image_edit::{self, CropRect, ImageFormat, Placement},
+    },
+    ui::popup,
+};
+
+/// Shows the image in `bytes` in a window of its own, with tools to zoom,
+/// rotate, crop and save it. `file_name` is suggested when saving; crops are
+/// added to the history of `monitor`.
+pub fn show(
+    parent: &impl IsA<gtk::Widget>,
+    bytes: &glib::Bytes,
+    file_name: &str,
+    monitor: ClipboardMonitor,
+) -> Result<(), String> {
+    let pixbuf = Pixbuf::from_stream(
+        &gio::MemoryInputStream::from_bytes(bytes),
+        None::<&gio::Cancellable>,
+    )
+    .map_err(|err| format!("Cannot open image: {err}"))?;
+
+    let viewer = Viewer::new(pixbuf);
+    let toast_overlay = adw::ToastOverlay::new();
+    // The popup neither resizes nor goes full screen, so the viewer gets a
+    // window of its own.
+    let popup = parent.root().and_downcast::<gtk::Window>();
+    let window = adw::Window::builder()
+        .title("Image")
+        .default_width(900)
+        .default_height(700)
+        .resizable(true)
+        .destroy_with_parent(true)
+        .build();
+    window.set_transient_for(popup.as_ref());
+
+    let zoom_out = icon_button("zoom-out-symbolic", "Zoom out");
+    let zoom_in = icon_button("zoom-in-symbolic", "Zoom in");
+    let fit = icon_button("zoom-fit-best-symbolic", "Fit to window");
+    let actual_size = icon_button("zoom-original-symbolic", "Actual size");
+    let rotate_left = icon_button("object-rotate-left-symbolic", "Rotate left");
+    let rotate_right = icon_button("object-rotate-right-symbolic", "Rotate right");
+    let save = icon_button("document-save-as-symbolic", "Save as…");
+    let crop = gtk::ToggleButton::builder()
+        .label("Crop")
+        .tooltip_text("Select an area to add as a new entry")
+        .build();
+    let fullscreen = gtk::ToggleButton::builder()
+        .icon_name("view-fullscreen-symbolic")
+        .tooltip_text("Full screen")
+        .build();
+
+    let header_bar = adw::HeaderBar::new();
+    header_bar.set_title_widget(Some(&viewer.zoom_label));
+    header_bar.pack_start(&linked([&zoom_out, &zoom_in]));
+    header_bar.pack_start(&linked([&fit, &actual_size]));
+    header_bar.pack_start(&linked([&rotate_left, &rotate_right]));
+    header_bar.pack_end(&fullscreen);
+    header_bar.pack_end(&save);
+    header_bar.pack_end(&crop);
+
+    let crop_bar = gtk::ActionBar::builder().revealed(false).build();
+    crop_bar.pack_start(
+        &gtk::Label::builder()
+            .label("Drag over the image to choose the area to keep")
+            .css_classes(["dim-label"])
+            .build(),
+    );
+    crop_bar.pack_end(&viewer.add_crop);
+
+    let toolbar_view = adw::ToolbarView::new();
+    toolbar_view.add_top_bar(&header_bar);
+    toolbar_view.add_bottom_bar(&crop_bar);
+    toolbar_view.set_content(Some(&viewer.scrolled));
+    toast_overlay.set_child(Some(&toolbar_view));
+    window.set_content(Some(&toast_overlay));
+
+    zoom_out.connect_clicked(glib::clone!(
+        #[strong]
+        viewer,
+        move |_| viewer.set_zoom(Some(image_edit::zoom_out(viewer.current_zoom())))
+    ));
+    zoom_in.connect_clicked(glib::clone!(
+        #[strong]
+        viewer,
+        move |_| viewer.set_zoom(Some(image_edit::zoom_in(viewer.current_zoom())))
+    ));
+    fit.connect_clicked(glib::clone!(
+        #[strong]
+        viewer,
+        move |_| viewer.set_zoom(None)
+    ));
+    actual_size.connect_clicked(glib::clone!(
+        #[strong]
+        viewer,
+        move |_| viewer.set_zoom(Some(1.0))
+    ));
+    rotate_left.connect_clicked(glib::clone!(
+        #[strong]
+        viewer,
+        move |_| viewer.rotate(PixbufRotation::Counterclockwise)
+    ));
+    rotate_right.connect_clicked(glib::clone!(
+        #[strong]
+        viewer,
+        move |_| viewer.rotate(PixbufRotation::Clockwise)
+    ));
+
+    crop.connect_toggled(glib::clone!(
+        #[strong]
+        viewer,
+        #[weak]
+        crop_bar,
+        move |crop| {
+            viewer.set_cropping(crop.is_active());
+            crop_bar.set_revealed(crop.is_active());
+        }
+    ));
+    // Weak, as the viewer owns the button.
+    viewer.add_crop.connect_clicked(glib::clone!(
+        #[weak]
+        viewer,
+        #[weak]
+        toast_overlay,
+        #[weak]
+        crop,
+        move |_| {
+            let Some(cropped) = viewer.cropped() else {
+                return;
+            };
+            let message = match cropped.save_to_bufferv("png", &[]) {
+                Ok(bytes) => {
+                    let content = ClipboardContent::Image(Some(Arc::from(bytes)));
+                    if monitor.add_cropped(content) {
+                        "Crop added to history".to_string()
+                    } else {
+                        "Already in history".to_string()
+                    }
+                }
+                Err(err) => format!("Cropping failed: {err}"),
+            };
+
+            toast_overlay.add_toast(adw::Toast::new(&message));
+            crop.set_active(false);
+        }
+    ));
+
+    save.connect_clicked(glib::clone!(
+        #[strong]
+        viewer,
+        #[weak]
+        window,
+        #[weak]
+        toast_overlay,
+        #[strong]
+        popup,
+        #[to_owned]
+        file_name,
+        move |_| save_as(
+            &window,
+            popup.as_ref(),
+            &toast_overlay,
+            &viewer.pixbuf.borrow(),
+            &file_name
+        )
+    ));
+
+    window
+        .bind_property("fullscreened", &fullscreen, "active")
+        .bidirectional()
+        .sync_create()
+        .build();
+
+    let shortcuts = gtk::ShortcutController::new();
+    shortcuts.add_shortcut(gtk::Shortcut::new(
+        gtk::ShortcutTrigger::parse_string("Escape"),
+        Some(gtk::NamedAction::new("window.close")),
+    ));
+    window.add_controller(shortcuts);
+
+    // The popup stays open while the viewer is.
+    if let Some(popup) = &popup {
+        popup::dialog_opened(popup);
+    }
+    window.connect_close_request(move |_| {
+        if let Some(popup) = &popup {
+            popup::dialog_closed(popup);
+        }
+        glib::Propagation::Proceed
+    });
+
+    window.present();
+    Ok(())
+}
+
+/// The corners of a crop selection, in image coordinates.
+type Selection = ((f64, f64), (f64, f64));
+
+/// The image being viewed and how it is shown.
+struct Viewer {
+    /// The image as rotated so far.
+    pixbuf: RefCell<Pixbuf>,
+    /// `None` fits the image into the window, without enlarging it.
+    zoom: Cell<Option<f64>>,
+    cropping: Cell<bool>,
+    selection: Cell<Option<Selection>>,
+    canvas: gtk::DrawingArea,
+    scrolled: gtk::ScrolledWindow,
+    zoom_label: gtk::Label,
+    add_crop: gtk::Button,
+}
+
+impl Viewer {
+    fn new(pixbuf: Pixbuf) -> Rc<Self> {
+        let canvas = gtk::DrawingArea::builder()
+            .hexpand(true)
+            .vexpand(true)
+            .build();
+        canvas.set_cursor_from_name(Some("grab"));
+
+        let viewer = Rc::new(Self {
+            pixbuf: RefCell::new(pixbuf),
+            zoom: Cell::new(None),
+            cropping: Cell::new(false),
+            selection: Cell::new(None),
+            scrolled: gtk::ScrolledWindow::builder().child(&canvas).build(),
+            canvas,
+            zoom_label: gtk::Label::builder()
+                .label("Fit")
+                .width_chars(6)
+                .css_classes(["numeric"])
+                .build(),
+            add_crop: gtk::Button::builder()
+                .label("Add to History")
+                .css_classes(["suggested-action"])
+                .sensitive(false)
+                .build(),
+        });
+
+        // The canvas only holds weak references, as the viewer owns it.
+        let weak = Rc::downgrade(&viewer);
+        viewer.canvas.set_draw_func(glib::clone!(
+            #[strong]
+            weak,
+            move |_, cr, _, _| {
+                if let Some(viewer) = weak.upgrade() {
+                    viewer.draw(cr);
+                }
+            }
+        ));
+        viewer.setup_crop_gesture(weak.clone());
+        viewer.setup_pan_gesture(weak.clone());
+        viewer.setup_scroll_zoom(weak);
+        viewer
+    }
+
+    fn size(&self) -> (i32, i32) {
+        let pixbuf = self.pixbuf.borrow();
+        (pixbuf.width(), pixbuf.height())
+    }
+
+    fn placement(&self) -> Placement {
+        let area = (
+            f64::from(self.canvas.width()),
+            f64::from(self.canvas.height()),
+        );
+        Placement::new(self.size(), area, self.zoom.get())
+    }
+
+    /// The zoom the image is shown at, also when fitted.
+    fn current_zoom(&self) -> f64 {
+        self.zoom.get().unwrap_or_else(|| self.placement().scale)
+    }
+
+    fn set_zoom(&self, zoom: Option<f64>) {
+        let zoom = zoom.map(|zoom| zoom.clamp(image_edit::MIN_ZOOM, image_edit::MAX_ZOOM));
+        self.zoom.set(zoom);
+
+        // A zoomed image asks for its full size and scrolls; a fitted one
+        // takes whatever space there is.
+        let (width, height) = self.size();
+        let scaled = |length: i32| zoom.map_or(0, |zoom| (f64::from(length) * zoom).round() as i32);
+        self.canvas.set_content_width(scaled(width));
+        self.canvas.set_content_height(scaled(height));
+
+        self.zoom_label.set_label(&match zoom {
+            Some(zoom) => format!("{:.0}%", zoom * 100.0),
+            None => "Fit".to_string(),
+        });
+        self.canvas.queue_draw();
+    }
+
+    fn rotate(&self, rotation: PixbufRotation) {
+        let Some(rotated) = self.pixbuf.borrow().rotate_simple(rotation) else {
+            return;
+        };
+        *self.pixbuf.borrow_mut() = rotated;
+
+        self.set_selection(None);
+        self.set_zoom(self.zoom.get());
+    }
+
+    fn set_cropping(&self, cropping: bool) {
+        self.cropping.set(cropping);
+        self.canvas
+            .set_cursor_from_name(Some(if cropping { "crosshair" } else { "grab" }));
+        self.set_selection(None);
+    }
+
+    fn set_selection(&self, selection: Option<Selection>) {
+        self.selection.set(selection);
+        self.add_crop.set_sensitive(self.crop_rect().is_some());
+        self.canvas.queue_draw();
+    }
+
+    fn crop_rect(&self) -> Option<CropRect> {
+        let (start, end) = self.selection.get()?;
+        CropRect::between(start, end, self.size())
+    }
+
+    fn cropped(&self) -> Option<Pixbuf> {
+        let rect = self.crop_rect()?;
+        Some(
+            self.pixbuf
+                .borrow()
+                .new_subpixbuf(rect.x, rect.y, rect.width, rect.height),
+        )
+    }
+
+    fn draw(&self, cr: &cairo::Context) {
+        let placement = self.placement();
+        let pixbuf = self.pixbuf.borrow();
+
+        cr.save().ok();
+        cr.translate(placement.x, placement.y);
+        cr.scale(placement.scale, placement.scale);
+        cr.set_source_pixbuf(&pixbuf, 0.0, 0.0);
+        // Enlarged screenshots stay sharp instead of blurring.
+        if placement.scale > 1.0 {
+            cr.source().set_filter(cairo::Filter::Nearest);
+        }
+        cr.paint().ok();
+        cr.restore().ok();
+
+        let Some(rect) = self.crop_rect() else {
+            return;
+        };
+        let (x, y) = placement.to_area((f64::from(rect.x), f64::from(rect.y)));
+        let width = f64::from(rect.width) * placement.scale;
+        let height = f64::from(rect.height) * placement.scale;
+
+        // Dims what the crop leaves out.
+        cr.set_fill_rule(cairo::FillRule::EvenOdd);
+        cr.rectangle(
+            placement.x,
+            placement.y,
+            f64::from(pixbuf.width()) * placement.scale,
+            f64::from(pixbuf.height()) * placement.scale,
+        );
+        cr.rectangle(x, y, width, height);
+        cr.set_source_rgba(0.0, 0.0, 0.0, 0.5);
+        cr.fill().ok();
+
+        cr.set_source_rgb(1.0, 1.0, 1.0);
+        cr.set_line_width(1.0);
+        cr.rectangle(x + 0.5, y + 0.5, width - 1.0, height - 1.0);
+        cr.stroke().ok();
+    }
+
+    /// Dragging over the image selects the area to crop while cropping.
+    fn setup_crop_gesture(&self, weak: Weak<Self>) {
+        let gesture = gtk::GestureDrag::new();
+
+        gesture.connect_drag_begin(glib::clone!(
+            #[strong]
+            weak,
+            move |gesture, x, y| {
+                let Some(viewer) = weak.upgrade().filter(|viewer| viewer.cropping.get()) else {
+                    gesture.set_state(gtk::EventSequenceState::Denied);
+                    return;
+                };
+                let start = viewer.placement().to_image((x, y));
+                viewer.set_selection(Some((start, start)));
+            }
+        ));
+        gesture.connect_drag_update(move |gesture, dx, dy| {
+            let Some(viewer) = weak.upgrade() else {
+                return;
+            };
+            let (Some((start, _)), Some((x, y))) = (viewer.selection.get(), gesture.start_point())
+            else {
+                return;
+            };
+            let end = viewer.placement().to_image((x + dx, y + dy));
+            viewer.set_selection(Some((start, end)));
+        });
+
+        self.canvas.add_controller(gesture);
+    }
+
+    /// Dragging a zoomed image scrolls it. Attached to the scrolled window,
+    /// whose coordinates do not move along.
+    fn setup_pan_gesture(&self, weak: Weak<Self>) {
+        let gesture = gtk::GestureDrag::new();
+        let origin = Rc::new(Cell::new((0.0, 0.0)));
+
+        gesture.connect_drag_begin(glib::clone!(
+            #[strong]
+            weak,
+            #[strong]
+            origin,
+            move |gesture, _, _| {
+                let Some(viewer) = weak.upgrade().filter(|viewer| !viewer.cropping.get()) else {
+                    gesture.set_state(gtk::EventSequenceState::Denied);
+                    return;
+                };
+                origin.set((
+                    viewer.scrolled.hadjustment().value(),
+                    viewer.scrolled.vadjustment().value(),
+                ));
+            }
+        ));
+        gesture.connect_drag_update(move |_, dx, dy| {
+            let Some(viewer) = weak.upgrade() else {
+                return;
+            };
+            let (x, y) = origin.get();
+            viewer.scrolled.hadjustment().set_value(x - dx);
+            viewer.scrolled.vadjustment().set_value(y - dy);
+        });
+
+        self.scrolled.add_controller(gesture);
+    }
+
+    /// Scrolling with Ctrl held zooms.
+    fn setup_scroll_zoom(&self, weak: Weak<Self>) {
+        let controller = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+
+        controller.connect_scroll(move |controller, _, dy| {
+            let Some(viewer) = weak.upgrade() else {
+                return glib::Propagation::Proceed;
+            };
+            if !controller
+                .current_event_state()
+                .contains(gdk::ModifierType::CONTROL_MASK)
+            {
+                return glib::Propagation::Proceed;
+            }
+
+            let zoom = viewer.current_zoom();
+            viewer.set_zoom(Some(if dy < 0.0 {
+                image_edit::zoom_in(zoom)
+            } else {
+                image_edit::zoom_out(zoom)
+            }));
+            glib::Propagation::Stop
+        });
+
+        self.scrolled.add_controller(controller);
+    }
+}
+
+/// Asks where to save `pixbuf`; the format follows the chosen extension,
+/// among those gdk-pixbuf can write. `popup` is kept open meanwhile.
+fn save_as(
+    window: &adw::Window,
+    popup: Option<&gtk::Window>,
+    toast_overlay: &adw::ToastOverlay,
+    pixbuf: &Pixbuf,
+    file_name: &str,
+) {
+    let savers: Vec<String> = Pixbuf::formats()
+        .iter()
+        .filter(|format| format.is_writable())
+        .filter_map(|format| format.name())
+        .map(String::from)
+        .collect();
+    let formats = ImageFormat::writable(&savers);
+
+    let filters = gio::ListStore::new::<gtk::FileFilter>();
+    for &format in &formats {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(format.label()));
+        filter.add_mime_type(format.mime_type());
+        for extension in format.extensions() {
+            filter.add_suffix(extension);
+        }
+        filters.append(&filter);
+    }
+
+    let file_dialog = gtk::FileDialog::builder()
+        .title("Save Image")
+        .initial_name(file_name)
+        .filters(&filters)
+        .modal(true)
+        .build();
+    let window = window.clone().upcast::<gtk::Window>();
+    let popup = popup.cloned();
+    let pixbuf = pixbuf.clone();
+
+    glib::MainContext::default().spawn_local(glib::clone!(
+        #[weak]
+        toast_overlay,
+        async move {
+            let chosen = file_dialog.save_future(Some(&window));
+            let chosen = match &popup {
+                Some(popup) => popup::keep_open_during(popup, chosen).await,
+                None => chosen.await,
+            };
+            let Ok(file) = chosen else {
+                return;
+            };
+            let Some(path) = file.path() else {
+                return;
+            };
+
+            // Encoded here, as pixbufs stay on the main thread.
+            let format = ImageFormat::from_path(&path, &formats);
+            let result = match pixbuf.save_to_bufferv(format.pixbuf_type(), format.options()) {
+                Ok(bytes) => gio::spawn_blocking(move || std::fs::write(&path, bytes))
+                    .await
+                    .map(|written| written.map_err(|err| err.to_string())),
+                Err(err) => Ok(Err(err.to_string())),
+            };
+
+            let message = match result {
+                Ok(Ok(())) => format!("Saved as {}", format.label()),
+                Ok(Err(err)) => format!("Saving failed: {err}"),
+                Err(_) => "Saving failed".to_string(),
+            };
+            toast_overlay.add_toast(adw::Toast::new(&message));
+        }
+    ));
+}
+
+fn icon_button(icon_name: &str, tooltip: &str) -> gtk::Button {
+    gtk::Button::builder()
+        .icon_name(icon_name)
+        .tooltip_text(tooltip)
+        .build()
+}
+
+fn linked<const N: usize>(buttons: [&gtk::Button; N]) -> gtk::Box {
+    let group = gtk::Box::builder().css_classes(["linked"]).build();
+    for button in buttons {
+        group.append(button);
+    }
+    group
+}
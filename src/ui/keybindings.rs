@@ -0,0 +1,236 @@
+use std::{cell::RefCell, rc::Rc};
+
+use gtk::{gdk::Key, gio, glib, prelude::*};
+use libadwaita::{self as adw, prelude::*};
+
+use crate::service::{
+    config_service::{AppConfig, ConfigService},
+    keybindings::{BindingScope, BindingSpec, KeybindingRegistry},
+};
+
+/// Installs the accelerators of the application-scope bindings.
+pub fn apply_accels(app: &adw::Application, registry: &KeybindingRegistry) {
+    for (spec, accels) in registry.bindings() {
+        if spec.scope == BindingScope::Application {
+            let accels: Vec<&str> = accels.iter().map(String::as_str).collect();
+            app.set_accels_for_action(spec.action, &accels);
+        }
+    }
+}
+
+/// Adds `win.edit-shortcuts`, which opens the shortcut editor.
+pub fn setup_keybindings_action(
+    app: &adw::Application,
+    window: &adw::ApplicationWindow,
+    registry: Rc<RefCell<KeybindingRegistry>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let action = gio::SimpleAction::new("edit-shortcuts", None);
+
+    action.connect_activate(glib::clone!(
+        #[weak]
+        app,
+        #[weak]
+        window,
+        move |_, _| show_editor(&app, &window, registry.clone(), config.clone())
+    ));
+
+    window.add_action(&action);
+}
+
+fn show_editor(
+    app: &adw::Application,
+    window: &adw::ApplicationWindow,
+    registry: Rc<RefCell<KeybindingRegistry>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let dialog = adw::PreferencesDialog::builder()
+        .title("Customize Shortcuts")
+        .build();
+    let page = adw::PreferencesPage::new();
+
+    for scope in [BindingScope::Application, BindingScope::List] {
+        let group = adw::PreferencesGroup::builder()
+            .title(scope.title())
+            .build();
+
+        for (spec, _) in registry.borrow().bindings() {
+            if spec.scope == scope && !spec.fixed {
+                group.add(&binding_row(
+                    app,
+                    &dialog,
+                    spec,
+                    registry.clone(),
+                    config.clone(),
+                ));
+            }
+        }
+
+        page.add(&group);
+    }
+
+    dialog.add(&page);
+    dialog.present(Some(window));
+}
+
+fn binding_row(
+    app: &adw::Application,
+    dialog: &adw::PreferencesDialog,
+    spec: &'static BindingSpec,
+    registry: Rc<RefCell<KeybindingRegistry>>,
+    config: Rc<RefCell<AppConfig>>,
+) -> adw::ActionRow {
+    let label = gtk::ShortcutLabel::builder()
+        .disabled_text("Disabled")
+        .valign(gtk::Align::Center)
+        .build();
+    let reset_button = gtk::Button::builder()
+        .icon_name("edit-undo-symbolic")
+        .valign(gtk::Align::Center)
+        .css_classes(["flat"])
+        .tooltip_text("Reset to default")
+        .build();
+    let row = adw::ActionRow::builder()
+        .title(spec.title)
+        .activatable(true)
+        .build();
+    row.add_suffix(&label);
+    row.add_suffix(&reset_button);
+
+    let update = Rc::new(glib::clone!(
+        #[weak]
+        app,
+        #[weak]
+        label,
+        #[weak]
+        reset_button,
+        #[strong]
+        registry,
+        #[strong]
+        config,
+        move |save: bool| {
+            let registry = registry.borrow();
+            label.set_accelerator(&registry.accels(spec.action).join(" "));
+            reset_button.set_visible(!registry.is_default(spec.action));
+
+            if save {
+                apply_accels(&app, &registry);
+                config.borrow_mut().keybindings = registry.overrides();
+                if let Err(err) = ConfigService::new().save(&config.borrow()) {
+                    eprintln!("Error saving config: {err}");
+                }
+            }
+        }
+    ));
+    update(false);
+
+    reset_button.connect_clicked(glib::clone!(
+        #[strong]
+        registry,
+        #[strong]
+        update,
+        move |_| {
+            registry.borrow_mut().reset(spec.action);
+            update(true);
+        }
+    ));
+
+    row.connect_activated(glib::clone!(
+        #[weak]
+        dialog,
+        move |_| {
+            capture_accel(
+                &dialog,
+                spec,
+                glib::clone!(
+                    #[weak]
+                    dialog,
+                    #[strong]
+                    registry,
+                    #[strong]
+                    update,
+                    move |accels: Vec<String>| {
+                        let result = registry.borrow_mut().set(spec.action, &accels);
+                        match result {
+                            Ok(()) => update(true),
+                            Err(err) => dialog.add_toast(adw::Toast::new(&err)),
+                        }
+                    }
+                ),
+            );
+        }
+    ));
+
+    row
+}
+
+/// Waits for the next key combination. Escape cancels and Backspace
+/// disables the shortcut.
+fn capture_accel(
+    parent: &adw::PreferencesDialog,
+    spec: &BindingSpec,
+    on_captured: impl Fn(Vec<String>) + 'static,
+) {
+    let alert = adw::AlertDialog::new(
+        Some("Set Shortcut"),
+        Some(&format!(
+            "Press the new shortcut for “{}”, Escape to cancel or Backspace to disable it.",
+            spec.title
+        )),
+    );
+    alert.add_response("cancel", "Cancel");
+    alert.set_close_response("cancel");
+
+    let controller = gtk::EventControllerKey::new();
+    controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+    controller.connect_key_pressed(glib::clone!(
+        #[weak]
+        alert,
+        #[upgrade_or]
+        glib::Propagation::Proceed,
+        move |_, key, _, state| {
+            if is_modifier(key) {
+                return glib::Propagation::Proceed;
+            }
+
+            let modifiers = state & gtk::accelerator_get_default_mod_mask();
+            if modifiers.is_empty() && key == Key::Escape {
+                alert.close();
+                return glib::Propagation::Stop;
+            }
+
+            let accels = if modifiers.is_empty() && key == Key::BackSpace {
+                Vec::new()
+            } else {
+                vec![gtk::accelerator_name(key, modifiers).to_string()]
+            };
+
+            on_captured(accels);
+            alert.close();
+            glib::Propagation::Stop
+        }
+    ));
+    alert.add_controller(controller);
+
+    alert.present(Some(parent));
+}
+
+fn is_modifier(key: Key) -> bool {
+    matches!(
+        key,
+        Key::Shift_L
+            | Key::Shift_R
+            | Key::Control_L
+            | Key::Control_R
+            | Key::Alt_L
+            | Key::Alt_R
+            | Key::Super_L
+            | Key::Super_R
+            | Key::Meta_L
+            | Key::Meta_R
+            | Key::Hyper_L
+            | Key::Hyper_R
+            | Key::ISO_Level3_Shift
+            | Key::Caps_Lock
+    )
+}
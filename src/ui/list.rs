@@ -1,17 +1,70 @@
-use std::{cell::RefCell, process::Command, rc::Rc};
-
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeSet, HashMap, HashSet},
+    io::Write,
+    os::unix::fs::{DirBuilderExt, OpenOptionsExt},
+    path::PathBuf,
+    rc::Rc,
+};
+
+use chrono::Local;
 use gtk::{gdk, gio, glib, prelude::*};
 use libadwaita::{self as adw, prelude::*};
 
-use crate::service::cliboard_history::{ClipboardHistory, IClipboardEntry, IClipboardHistory};
+use crate::{
+    service::{
+        cliboard_history::{
+            ClipboardEntry, ClipboardFormat, ClipboardHistory, EntryKey, IClipboardEntry,
+            IClipboardHistory,
+        },
+        cliboard_monitor::{ClipboardMonitor, IClipboardMonitor},
+        cliboard_provider::IClipboardProvider,
+        cliphist_provider::CliphistProvider,
+        config_service,
+        content_filter::{self, ContentFilter},
+        entry_groups::{self, EntryGroup},
+        entry_sort::SortMode,
+        file_list::{self, FileSummary},
+        history_transfer, rich_text,
+        time_format::{SystemClock, TimeStyle},
+    },
+    ui::{
+        details,
+        entry_row::{EntryRow, RowMeta},
+        image_viewer,
+        search_bar::FilterChips,
+    },
+};
 
 const CURRENT_CLIPBOARD_CLASS: &str = "current-clipboard";
+const PREVIEW_CHARS: usize = 100;
+
+thread_local! {
+    /// Decoded images of the entries in the history, so that rebuilding the
+    /// list on every copy does not decode them all again.
+    static TEXTURES: RefCell<HashMap<EntryKey, gdk::Texture>> = RefCell::default();
+}
+const MAX_FILES_SHOWN: usize = 5;
+const PAGE_ROWS: usize = 10;
+/// Rows reachable with Alt+1…9.
+const NUMBERED_ROWS: usize = 9;
+const BADGE_CLASS: &str = "row-badge";
+/// The first row of a collapsed group, kept empty so its header stays.
+const COLLAPSED_CLASS: &str = "collapsed-group";
+/// How long a dragged image stays on disk after the drag has ended.
+const DRAGGED_FILE_SECONDS: u32 = 30;
+
+/// The groups the user has folded away.
+pub type CollapsedGroups = Rc<RefCell<BTreeSet<EntryGroup>>>;
 
 pub fn build(
     history: Rc<RefCell<ClipboardHistory>>,
     display: &gdk::Display,
-    current_clipboard: Rc<RefCell<Option<String>>>,
     toast_overlay: adw::ToastOverlay,
+    time_style: Rc<Cell<TimeStyle>>,
+    collapsed_groups: CollapsedGroups,
+    sort_mode: Rc<Cell<SortMode>>,
+    clipboard_monitor: ClipboardMonitor,
 ) -> adw::Clamp {
     let list_box = gtk::ListBox::builder()
         .selection_mode(gtk::SelectionMode::Single)
@@ -26,74 +79,47 @@ pub fn build(
 
     let display_clone = display.clone();
     let history_clone = history.clone();
-    let current_clipboard_clone = current_clipboard.clone();
     let toast_overlay_clone = toast_overlay.clone();
 
     list_box.connect_row_activated(move |list_box, row| {
-        let index = row.index();
-
-        if index < 0 {
+        let Some(entry) = row_entry(row, &history_clone.borrow()) else {
             return;
-        }
-
-        let entry = {
-            let history = history_clone.borrow();
-            let Some(entry) = history.entries().get(index as usize) else {
-                return;
-            };
-            entry.clone()
         };
 
-        match entry.content {
-            crate::service::cliboard_history::ClipboardContent::Text(text) => {
-                let final_text = if let Some(id) = &entry.id {
-                    fetch_full_content(id).unwrap_or_else(|| text.clone())
-                } else {
-                    text.clone()
-                };
-
-                display_clone.clipboard().set_text(&final_text);
-                set_current_clipboard(
-                    list_box,
-                    &history_clone,
-                    &current_clipboard_clone,
-                    &final_text,
-                );
-
-                let toast = adw::Toast::new("Copied to clipboard");
-                toast_overlay_clone.add_toast(toast);
-            }
-            crate::service::cliboard_history::ClipboardContent::Image(_) => {
-                if let Some(id) = &entry.id
-                    && let Some(bytes) = fetch_binary_content(id)
-                {
-                    let temp_path = std::env::temp_dir().join("clipboard_manager_temp_image");
-                    if std::fs::write(&temp_path, &bytes).is_ok() {
-                        let file = gio::File::for_path(&temp_path);
-                        if let Ok(texture) = gdk::Texture::from_file(&file) {
-                            display_clone.clipboard().set_texture(&texture);
-                            set_current_clipboard(
-                                list_box,
-                                &history_clone,
-                                &current_clipboard_clone,
-                                "[Image]",
-                            );
-
-                            let toast = adw::Toast::new("Image copied to clipboard");
-                            toast_overlay_clone.add_toast(toast);
-                        }
-                    }
-                }
-            }
-        }
+        copy_entry(
+            list_box,
+            &history_clone,
+            &display_clone,
+            CopyTarget::Clipboard,
+            &toast_overlay_clone,
+            &entry,
+        );
     });
 
+    setup_context_menu(&list_box);
+    setup_drag_source(&list_box, history.clone(), clipboard_monitor);
+
+    list_box.set_sort_func(glib::clone!(
+        #[strong]
+        history,
+        #[strong]
+        sort_mode,
+        move |a, b| compare_rows(a, b, &history.borrow(), sort_mode.get())
+    ));
+    list_box.set_header_func(glib::clone!(
+        #[strong]
+        collapsed_groups,
+        move |row, before| update_header(row, before, &collapsed_groups)
+    ));
+
     populate_list(
         &list_box,
         history,
         display,
-        current_clipboard,
         toast_overlay,
+        time_style,
+        &collapsed_groups,
+        sort_mode.get(),
     );
 
     let scrolled_window = gtk::ScrolledWindow::builder()
@@ -112,21 +138,25 @@ pub fn refresh_list(
     clamp: &adw::Clamp,
     history: Rc<RefCell<ClipboardHistory>>,
     display: &gdk::Display,
-    current_clipboard: Rc<RefCell<Option<String>>>,
     toast_overlay: adw::ToastOverlay,
+    time_style: Rc<Cell<TimeStyle>>,
+    collapsed_groups: &CollapsedGroups,
+    sort_mode: SortMode,
 ) {
     if let Some(scrolled) = clamp.child().and_downcast::<gtk::ScrolledWindow>() {
         if let Some(list_box) = find_list_box(&scrolled) {
-            while let Some(child) = list_box.first_child() {
-                list_box.remove(&child);
+            while let Some(row) = list_box.row_at_index(0) {
+                list_box.remove(&row);
             }
 
             populate_list(
                 &list_box,
                 history,
                 display,
-                current_clipboard,
                 toast_overlay,
+                time_style,
+                collapsed_groups,
+                sort_mode,
             );
             select_first_row(clamp);
         } else {
@@ -141,11 +171,16 @@ fn populate_list(
     list_box: &gtk::ListBox,
     history: Rc<RefCell<ClipboardHistory>>,
     display: &gdk::Display,
-    current_clipboard: Rc<RefCell<Option<String>>>,
     toast_overlay: adw::ToastOverlay,
+    time_style: Rc<Cell<TimeStyle>>,
+    collapsed_groups: &CollapsedGroups,
+    sort_mode: SortMode,
 ) {
     let entries = history.borrow().entries().to_vec();
 
+    let keys: HashSet<EntryKey> = entries.iter().map(|entry| entry.key).collect();
+    TEXTURES.with_borrow_mut(|textures| textures.retain(|key, _| keys.contains(key)));
+
     if entries.is_empty() {
         let row = adw::ActionRow::builder()
             .title("No clipboard history yet")
@@ -158,53 +193,101 @@ fn populate_list(
         return;
     }
 
-    for entry in entries {
+    for (group, index, entry) in
+        entry_groups::group_entries(&entries, Local::now(), sort_mode.groups_by_day())
+    {
+        let entry = entry.clone();
         let content_text = entry.content.as_text();
-        let content_preview: String = content_text.chars().take(100).collect();
+        let content_preview: String = content_text.chars().take(PREVIEW_CHARS).collect();
         let content_preview = if content_preview.len() < content_text.len() {
             format!("{}...", content_preview)
         } else {
             content_preview
         };
 
-        let safe_title = glib::markup_escape_text(&content_preview);
-        let safe_subtitle =
-            glib::markup_escape_text(&format!("{} • {}", entry.source, entry.format_time()));
+        let safe_title = match &entry.content {
+            crate::service::cliboard_history::ClipboardContent::Rich {
+                html: Some(html), ..
+            } => rich_text::html_to_markup(html, PREVIEW_CHARS).into(),
+            crate::service::cliboard_history::ClipboardContent::Files { uris, .. } => {
+                glib::markup_escape_text(&files_title(uris, None))
+            }
+            _ => glib::markup_escape_text(&content_preview),
+        };
+        let safe_subtitle = entry_subtitle(&entry, time_style.get());
+        let shown_title = match entry.sensitivity {
+            Some(kind) => glib::markup_escape_text(&kind.mask()),
+            None => safe_title.clone(),
+        };
 
         let row = adw::ActionRow::builder()
-            .title(safe_title)
+            .title(shown_title)
             .subtitle(safe_subtitle)
             .activatable(true)
             .can_focus(false)
             .build();
 
-        let list_row = gtk::ListBoxRow::new();
+        let list_row: gtk::ListBoxRow = EntryRow::new(RowMeta::new(&entry, index, group)).upcast();
         list_row.set_child(Some(&row));
 
-        if entry.content.is_image() {
-            let mut shown_preview = false;
+        row.add_prefix(
+            &gtk::Label::builder()
+                .css_classes([BADGE_CLASS, "dim-label", "numeric"])
+                .visible(false)
+                .build(),
+        );
+
+        if entry.pinned {
+            row.add_prefix(
+                &gtk::Image::builder()
+                    .icon_name("view-pin-symbolic")
+                    .tooltip_text("Pinned")
+                    .build(),
+            );
+        }
 
-            if let Some(id) = &entry.id
-                && let Some(bytes) = fetch_binary_content(id)
-            {
-                let temp_path =
-                    std::env::temp_dir().join(format!("clipboard_manager_preview_{}", id));
-                if std::fs::write(&temp_path, &bytes).is_ok() {
-                    let file = gio::File::for_path(&temp_path);
-                    if let Ok(texture) = gdk::Texture::from_file(&file) {
-                        let picture = gtk::Picture::for_paintable(&texture);
-                        picture.set_size_request(48, 48);
-                        picture.set_content_fit(gtk::ContentFit::ScaleDown);
-                        row.add_prefix(&picture);
-                        shown_preview = true;
-                    }
+        if entry.content.is_image() {
+            let view_button = gtk::Button::builder()
+                .icon_name("view-fullscreen-symbolic")
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .tooltip_text("View image")
+                .can_focus(false)
+                .build();
+
+            view_button.connect_clicked(glib::clone!(
+                #[weak]
+                list_box,
+                #[weak]
+                list_row,
+                move |_| {
+                    list_box.select_row(Some(&list_row));
+                    list_box.activate_action("win.view-image", None).ok();
                 }
-            }
+            ));
+
+            row.add_suffix(&view_button);
 
-            if !shown_preview {
-                let icon = gtk::Image::from_icon_name("image-x-generic-symbolic");
-                row.add_prefix(&icon);
+            show_thumbnail(&row, &entry);
+        }
+
+        if let crate::service::cliboard_history::ClipboardContent::Files { uris, .. } =
+            &entry.content
+        {
+            let icon = gtk::Image::new();
+            if let Some(first) = uris.first() {
+                icon.set_from_gicon(&file_icon(&file_list::file_name(first), false));
             }
+            row.add_prefix(&icon);
+
+            let missing = gtk::Image::builder()
+                .icon_name("dialog-warning-symbolic")
+                .tooltip_text("Some files no longer exist")
+                .visible(false)
+                .build();
+            row.add_suffix(&missing);
+
+            show_file_summaries(&row, &icon, &missing, uris, entry.sensitivity.is_none());
         }
 
         let copy_button = gtk::Button::builder()
@@ -215,8 +298,7 @@ fn populate_list(
             .can_focus(false)
             .build();
 
-        let clipboard = display.clipboard();
-        let current_clipboard_for_button = current_clipboard.clone();
+        let display_for_button = display.clone();
         let history_for_button = history.clone();
         let entry_for_button = entry.clone();
         let toast_overlay_for_button = toast_overlay.clone();
@@ -225,39 +307,425 @@ fn populate_list(
             #[weak]
             list_box,
             move |_| {
-                if let crate::service::cliboard_history::ClipboardContent::Text(text) =
-                    &entry_for_button.content
-                {
-                    let final_text = if let Some(id) = &entry_for_button.id {
-                        fetch_full_content(id).unwrap_or_else(|| text.clone())
-                    } else {
-                        text.clone()
-                    };
+                copy_entry(
+                    &list_box,
+                    &history_for_button,
+                    &display_for_button,
+                    CopyTarget::Clipboard,
+                    &toast_overlay_for_button,
+                    &entry_for_button,
+                );
+            }
+        ));
 
-                    clipboard.set_text(&final_text);
-                    set_current_clipboard(
-                        &list_box,
-                        &history_for_button,
-                        &current_clipboard_for_button,
-                        &final_text,
+        if matches!(
+            entry.content,
+            crate::service::cliboard_history::ClipboardContent::Rich { .. }
+        ) {
+            let plain_button = gtk::Button::builder()
+                .icon_name("text-x-generic-symbolic")
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .tooltip_text("Copy as plain text")
+                .can_focus(false)
+                .build();
+
+            plain_button.connect_clicked(glib::clone!(
+                #[weak]
+                list_box,
+                #[strong]
+                history,
+                #[strong]
+                display,
+                #[strong]
+                toast_overlay,
+                #[strong]
+                entry,
+                move |_| {
+                    copy_plain_text(&list_box, &history, &display, &toast_overlay, &entry);
+                }
+            ));
+
+            row.add_suffix(&plain_button);
+        }
+
+        if entry.formats.len() > 1 {
+            let formats_button = gtk::Button::builder()
+                .icon_name("view-list-symbolic")
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .tooltip_text("Show formats")
+                .can_focus(false)
+                .build();
+
+            formats_button.connect_clicked(glib::clone!(
+                #[weak]
+                list_box,
+                #[strong]
+                history,
+                #[strong]
+                display,
+                #[strong]
+                toast_overlay,
+                #[strong]
+                entry,
+                move |button| {
+                    details::show_formats(
+                        button,
+                        &entry,
+                        glib::clone!(
+                            #[weak]
+                            list_box,
+                            #[strong]
+                            history,
+                            #[strong]
+                            display,
+                            #[strong]
+                            toast_overlay,
+                            #[strong]
+                            entry,
+                            move |format| {
+                                display
+                                    .clipboard()
+                                    .set_content(Some(&format_content_provider(format)))
+                                    .ok();
+                                set_current_entry(&list_box, &history, &entry);
+                                toast_overlay.add_toast(adw::Toast::new(&format!(
+                                    "Copied as {}",
+                                    format.mime_type
+                                )));
+                            }
+                        ),
                     );
+                }
+            ));
 
-                    let toast = adw::Toast::new("Copied to clipboard");
-                    toast_overlay_for_button.add_toast(toast);
+            row.add_suffix(&formats_button);
+        }
+
+        if let Some(kind) = entry.sensitivity {
+            let reveal_button = gtk::ToggleButton::builder()
+                .icon_name("view-reveal-symbolic")
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .tooltip_text("Reveal content")
+                .can_focus(false)
+                .build();
+
+            reveal_button.connect_toggled(glib::clone!(
+                #[weak]
+                row,
+                move |button| {
+                    if button.is_active() {
+                        row.set_title(&safe_title);
+                        button.set_icon_name("view-conceal-symbolic");
+                        button.set_tooltip_text(Some("Hide content"));
+                    } else {
+                        row.set_title(&glib::markup_escape_text(&kind.mask()));
+                        button.set_icon_name("view-reveal-symbolic");
+                        button.set_tooltip_text(Some("Reveal content"));
+                    }
                 }
-            }
-        ));
+            ));
+
+            row.add_suffix(&reveal_button);
+        }
 
         row.add_suffix(&copy_button);
         list_box.append(&list_row);
     }
 
-    let history_snapshot = history.borrow();
-    apply_current_highlight(
-        list_box,
-        &history_snapshot,
-        current_clipboard.borrow().as_deref(),
+    apply_current_highlight(list_box, &history.borrow());
+    apply_collapsed_groups(list_box, &collapsed_groups.borrow());
+}
+
+fn row_meta<T>(row: &gtk::ListBoxRow, read: impl FnOnce(&RowMeta) -> T) -> Option<T> {
+    row.downcast_ref::<EntryRow>()?.with_meta(read)
+}
+
+fn update_row_meta(row: &gtk::ListBoxRow, update: impl FnOnce(&mut RowMeta)) {
+    if let Some(row) = row.downcast_ref::<EntryRow>() {
+        row.update_meta(update);
+    }
+}
+
+fn row_key(row: &gtk::ListBoxRow) -> Option<EntryKey> {
+    row_meta(row, |meta| meta.key)
+}
+
+/// Where the entry of `row` is in `entries`. The cached index is checked
+/// and only searched for when the history changed since.
+fn entry_index(row: &gtk::ListBoxRow, entries: &[ClipboardEntry]) -> Option<usize> {
+    let (key, index) = row_meta(row, |meta| (meta.key, meta.index))?;
+    Some(index)
+        .filter(|&index| entries.get(index).is_some_and(|entry| entry.key == key))
+        .or_else(|| entries.iter().position(|entry| entry.key == key))
+}
+
+/// Caches the current history index on every row, after entries moved.
+fn update_row_indexes(list_box: &gtk::ListBox, entries: &[ClipboardEntry]) {
+    let indexes: HashMap<EntryKey, usize> = entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| (entry.key, index))
+        .collect();
+
+    for row in (0..).map_while(|index| list_box.row_at_index(index)) {
+        update_row_meta(&row, |meta| {
+            if let Some(&index) = indexes.get(&meta.key) {
+                meta.index = index;
+            }
+        });
+    }
+}
+
+fn set_row_group(row: &gtk::ListBoxRow, group: EntryGroup) {
+    update_row_meta(row, |meta| meta.group = group);
+}
+
+fn row_group(row: &gtk::ListBoxRow) -> Option<EntryGroup> {
+    row_meta(row, |meta| meta.group)
+}
+
+fn row_kinds(row: &gtk::ListBoxRow) -> BTreeSet<ContentFilter> {
+    row_meta(row, |meta| meta.kinds.clone()).unwrap_or_default()
+}
+
+fn row_entry(row: &gtk::ListBoxRow, history: &ClipboardHistory) -> Option<ClipboardEntry> {
+    let entries = history.entries();
+    entry_index(row, entries).map(|index| entries[index].clone())
+}
+
+fn row_for_key(list_box: &gtk::ListBox, key: EntryKey) -> Option<gtk::ListBoxRow> {
+    (0..)
+        .map_while(|index| list_box.row_at_index(index))
+        .find(|row| row_key(row) == Some(key))
+}
+
+/// Puts a header with the group's title and size above the first row of
+/// each group. Clicking it folds the group.
+fn update_header(
+    row: &gtk::ListBoxRow,
+    before: Option<&gtk::ListBoxRow>,
+    collapsed_groups: &CollapsedGroups,
+) {
+    let group = row_group(row);
+    let Some(group) = group.filter(|group| before.and_then(row_group) != Some(*group)) else {
+        row.set_header(None::<&gtk::Widget>);
+        return;
+    };
+    let Some(list_box) = row.parent().and_downcast::<gtk::ListBox>() else {
+        return;
+    };
+
+    let count = shown_in_group(&list_box, row, group);
+    let collapsed = collapsed_groups.borrow().contains(&group);
+
+    let content = gtk::Box::builder().spacing(6).build();
+    content.append(&gtk::Image::from_icon_name(if collapsed {
+        "pan-end-symbolic"
+    } else {
+        "pan-down-symbolic"
+    }));
+    content.append(
+        &gtk::Label::builder()
+            .label(group.title())
+            .css_classes(["heading"])
+            .build(),
     );
+    content.append(
+        &gtk::Label::builder()
+            .label(count.to_string())
+            .css_classes(["dim-label", "numeric"])
+            .build(),
+    );
+
+    let header = gtk::Button::builder()
+        .child(&content)
+        .css_classes(["flat", "group-header"])
+        .can_focus(false)
+        .tooltip_text(if collapsed { "Expand" } else { "Collapse" })
+        .build();
+
+    header.connect_clicked(glib::clone!(
+        #[weak]
+        list_box,
+        #[strong]
+        collapsed_groups,
+        move |_| {
+            {
+                let mut collapsed = collapsed_groups.borrow_mut();
+                if !collapsed.remove(&group) {
+                    collapsed.insert(group);
+                }
+            }
+            apply_collapsed_groups(&list_box, &collapsed_groups.borrow());
+        }
+    ));
+
+    row.set_header(Some(&header));
+}
+
+/// Counts the rows of `group` that pass the filter, from `first` on. Rows
+/// are sorted by group, so the count stops at the next group. Rows of a
+/// collapsed group still count.
+fn shown_in_group(list_box: &gtk::ListBox, first: &gtk::ListBoxRow, group: EntryGroup) -> usize {
+    (first.index()..)
+        .map_while(|index| list_box.row_at_index(index))
+        .take_while(|other| row_group(other) == Some(group))
+        .filter(|other| other.is_child_visible())
+        .count()
+}
+
+fn collapsed_groups_in(list_box: &gtk::ListBox) -> BTreeSet<EntryGroup> {
+    (0..)
+        .map_while(|index| list_box.row_at_index(index))
+        .filter(|row| row.has_css_class(COLLAPSED_CLASS))
+        .filter_map(|row| row_group(&row))
+        .collect()
+}
+
+/// Orders rows by group, then by `sort_mode`, then in history order.
+fn compare_rows(
+    a: &gtk::ListBoxRow,
+    b: &gtk::ListBoxRow,
+    history: &ClipboardHistory,
+    sort_mode: SortMode,
+) -> gtk::Ordering {
+    let entries = history.entries();
+    let (Some(a_position), Some(b_position)) = (entry_index(a, entries), entry_index(b, entries))
+    else {
+        return gtk::Ordering::Equal;
+    };
+
+    row_group(a)
+        .cmp(&row_group(b))
+        .then_with(|| sort_mode.compare(&entries[a_position], &entries[b_position], Local::now()))
+        .then(a_position.cmp(&b_position))
+        .into()
+}
+
+/// Orders the list again after the sort mode changed. Rows move between
+/// the day groups and a single one, as `sort_mode` needs.
+pub fn resort(clamp: &adw::Clamp, history: &ClipboardHistory, sort_mode: SortMode) {
+    let Some(list_box) = list_box_of(clamp) else {
+        return;
+    };
+
+    let collapsed = collapsed_groups_in(&list_box);
+    let entries = history.entries();
+    let now = Local::now();
+    for row in (0..).map_while(|index| list_box.row_at_index(index)) {
+        if let Some(index) = entry_index(&row, entries) {
+            set_row_group(
+                &row,
+                EntryGroup::of(&entries[index], now, sort_mode.groups_by_day()),
+            );
+        }
+    }
+    list_box.invalidate_sort();
+    apply_collapsed_groups(&list_box, &collapsed);
+}
+
+/// Hides the rows of collapsed groups. The first row of each stays in the
+/// list, emptied, as the header is attached to it.
+fn apply_collapsed_groups(list_box: &gtk::ListBox, collapsed: &BTreeSet<EntryGroup>) {
+    let mut previous = None;
+
+    for row in (0..).map_while(|index| list_box.row_at_index(index)) {
+        let Some(group) = row_group(&row) else {
+            continue;
+        };
+        let first = previous != Some(group);
+        previous = Some(group);
+
+        let folded = collapsed.contains(&group);
+        let placeholder = folded && first;
+
+        row.set_visible(!folded || first);
+        row.set_selectable(!placeholder);
+        row.set_activatable(!placeholder);
+        row.set_can_focus(!placeholder);
+        if let Some(child) = row.child() {
+            child.set_visible(!placeholder);
+        }
+        if placeholder {
+            row.add_css_class(COLLAPSED_CLASS);
+        } else {
+            row.remove_css_class(COLLAPSED_CLASS);
+        }
+    }
+
+    list_box.invalidate_filter();
+    list_box.invalidate_headers();
+    ensure_visible_selection(list_box);
+    update_badges(list_box);
+}
+
+/// Numbers the first visible rows for the Alt+1…9 shortcuts.
+fn update_badges(list_box: &gtk::ListBox) {
+    let mut number = 0;
+
+    for row in (0..).map_while(|index| list_box.row_at_index(index)) {
+        let Some(badge) = find_badge(&row) else {
+            continue;
+        };
+
+        if is_row_shown(&row) && number < NUMBERED_ROWS {
+            number += 1;
+            badge.set_label(&number.to_string());
+            badge.set_visible(true);
+        } else {
+            badge.set_visible(false);
+        }
+    }
+}
+
+fn find_badge(widget: &impl IsA<gtk::Widget>) -> Option<gtk::Label> {
+    let mut child = widget.first_child();
+
+    while let Some(current) = child {
+        if current.has_css_class(BADGE_CLASS) {
+            return current.downcast().ok();
+        }
+        if let Some(badge) = find_badge(&current) {
+            return Some(badge);
+        }
+        child = current.next_sibling();
+    }
+
+    None
+}
+
+/// Re-renders the timestamps of the existing rows so that relative labels
+/// keep up with the clock without rebuilding the list.
+pub fn refresh_timestamps(clamp: &adw::Clamp, history: &ClipboardHistory, time_style: TimeStyle) {
+    let Some(scrolled) = clamp.child().and_downcast::<gtk::ScrolledWindow>() else {
+        return;
+    };
+    let Some(list_box) = find_list_box(&scrolled) else {
+        return;
+    };
+
+    let mut row = list_box.first_child().and_downcast::<gtk::ListBoxRow>();
+
+    while let Some(current) = row {
+        if let Some(entry) = row_entry(&current, history)
+            && let Some(action_row) = current.child().and_downcast::<adw::ActionRow>()
+        {
+            action_row.set_subtitle(&entry_subtitle(&entry, time_style));
+        }
+
+        row = current.next_sibling().and_downcast::<gtk::ListBoxRow>();
+    }
+}
+
+fn entry_subtitle(entry: &ClipboardEntry, time_style: TimeStyle) -> glib::GString {
+    glib::markup_escape_text(&format!(
+        "{} • {}",
+        entry.source,
+        entry.format_time(&SystemClock, time_style)
+    ))
 }
 
 fn find_list_box(scrolled: &gtk::ScrolledWindow) -> Option<gtk::ListBox> {
@@ -269,38 +737,67 @@ fn find_list_box(scrolled: &gtk::ScrolledWindow) -> Option<gtk::ListBox> {
     viewport.child().and_downcast::<gtk::ListBox>()
 }
 
-pub fn setup_search(clamp: &adw::Clamp, search_entry: &gtk::SearchEntry) {
+/// Filters the list by the search text and, like the text, by the chosen
+/// kinds while the search bar is open.
+pub fn setup_search(
+    clamp: &adw::Clamp,
+    search_bar: &gtk::SearchBar,
+    search_entry: &gtk::SearchEntry,
+    chips: &FilterChips,
+) {
     if let Some(scrolled) = clamp.child().and_downcast::<gtk::ScrolledWindow>()
         && let Some(list_box) = find_list_box(&scrolled)
     {
         list_box.set_filter_func(glib::clone!(
+            #[strong]
+            search_bar,
             #[strong]
             search_entry,
+            #[strong]
+            chips,
             move |row| {
-                let text = search_entry.text();
-
-                if text.is_empty() {
+                if row.has_css_class(COLLAPSED_CLASS) {
                     return true;
                 }
 
-                if let Some(action_row) = row.child().and_downcast::<adw::ActionRow>() {
-                    let title = action_row.title();
+                if search_bar.is_search_mode()
+                    && !content_filter::passes(&chips.active(), &row_kinds(row))
+                {
+                    return false;
+                }
+
+                let text = search_entry.text();
 
-                    return title.to_lowercase().contains(&text.to_lowercase());
+                if text.is_empty() {
+                    return true;
                 }
 
-                true
+                row_meta(row, |meta| meta.text.contains(&text.to_lowercase())).unwrap_or(true)
             }
         ));
 
-        search_entry.connect_search_changed(glib::clone!(
+        let refilter = glib::clone!(
             #[weak]
             list_box,
-            move |_| {
+            move || {
                 list_box.invalidate_filter();
                 ensure_visible_selection(&list_box);
+                update_badges(&list_box);
             }
+        );
+        let refilter = Rc::new(refilter);
+
+        search_entry.connect_search_changed(glib::clone!(
+            #[strong]
+            refilter,
+            move |_| refilter()
+        ));
+        search_bar.connect_search_mode_enabled_notify(glib::clone!(
+            #[strong]
+            refilter,
+            move |_| refilter()
         ));
+        chips.connect_changed(move |_| refilter());
     }
 }
 
@@ -323,6 +820,10 @@ pub fn focus_list(clamp: &adw::Clamp) {
 pub enum NavigationDirection {
     Up,
     Down,
+    PageUp,
+    PageDown,
+    First,
+    Last,
 }
 
 pub fn move_selection(clamp: &adw::Clamp, direction: NavigationDirection) -> bool {
@@ -337,22 +838,34 @@ pub fn move_selection(clamp: &adw::Clamp, direction: NavigationDirection) -> boo
         return false;
     }
 
-    list_box.grab_focus();
-
-    let selected = list_box.selected_row().filter(|row| row.is_visible());
-    let next_row = match (direction, selected.as_ref()) {
-        (NavigationDirection::Down, Some(row)) => next_visible_row(row),
-        (NavigationDirection::Up, Some(row)) => prev_visible_row(row),
-        (NavigationDirection::Down, None) => first_visible_row(&list_box),
-        (NavigationDirection::Up, None) => last_visible_row(&list_box),
+    let rows = visible_rows(&list_box);
+    let Some(last) = rows.len().checked_sub(1) else {
+        return false;
+    };
+    let selected = list_box
+        .selected_row()
+        .and_then(|selected| rows.iter().position(|row| *row == selected));
+
+    let target = match (direction, selected) {
+        (NavigationDirection::First, _) => 0,
+        (NavigationDirection::Last, _) => last,
+        (NavigationDirection::Down | NavigationDirection::PageDown, None) => 0,
+        (NavigationDirection::Up | NavigationDirection::PageUp, None) => last,
+        (NavigationDirection::Down, Some(index)) => (index + 1).min(last),
+        (NavigationDirection::Up, Some(index)) => index.saturating_sub(1),
+        (NavigationDirection::PageDown, Some(index)) => (index + PAGE_ROWS).min(last),
+        (NavigationDirection::PageUp, Some(index)) => index.saturating_sub(PAGE_ROWS),
     };
 
-    if let Some(row) = next_row {
-        list_box.select_row(Some(&row));
-        return true;
+    if selected == Some(target) {
+        return false;
     }
 
-    false
+    let row = &rows[target];
+    list_box.select_row(Some(row));
+    // Focusing the row scrolls it into view.
+    row.grab_focus();
+    true
 }
 
 pub fn select_first_row(clamp: &adw::Clamp) -> bool {
@@ -402,41 +915,42 @@ fn is_placeholder_row(list_box: &gtk::ListBox) -> bool {
     row.title() == "No clipboard history yet"
 }
 
-fn set_current_clipboard(
+/// Makes `entry` the current entry. Its row moves to wherever the sort mode
+/// now puts it, possibly into another group.
+fn set_current_entry(
     list_box: &gtk::ListBox,
     history: &Rc<RefCell<ClipboardHistory>>,
-    current_clipboard: &Rc<RefCell<Option<String>>>,
-    text: &str,
+    entry: &ClipboardEntry,
 ) {
-    *current_clipboard.borrow_mut() = Some(text.to_string());
-    let history_snapshot = history.borrow();
-    apply_current_highlight(list_box, &history_snapshot, Some(text));
+    if !history.borrow_mut().mark_copied(entry.key) {
+        return;
+    }
+
+    update_row_indexes(list_box, history.borrow().entries());
+    if let Some(row) = row_for_key(list_box, entry.key)
+        && let Some(copied) = row_entry(&row, &history.borrow())
+    {
+        let collapsed = collapsed_groups_in(list_box);
+        // Rows only leave their group by age while the list is split by day.
+        let by_day = row_group(&row) != Some(EntryGroup::Unpinned);
+        set_row_group(&row, EntryGroup::of(&copied, Local::now(), by_day));
+        // Sorts the row again, now that it is newer and used once more.
+        row.changed();
+        apply_collapsed_groups(list_box, &collapsed);
+    }
+
+    apply_current_highlight(list_box, &history.borrow());
 }
 
-fn apply_current_highlight(
-    list_box: &gtk::ListBox,
-    history: &ClipboardHistory,
-    current_text: Option<&str>,
-) {
-    let entries = history.entries();
+fn apply_current_highlight(list_box: &gtk::ListBox, history: &ClipboardHistory) {
+    let current_key = history.current_key();
     let mut row = list_box.first_child().and_downcast::<gtk::ListBoxRow>();
-    let mut matched = false;
 
     while let Some(current) = row {
-        let row_text = usize::try_from(current.index())
-            .ok()
-            .and_then(|index| entries.get(index))
-            .and_then(|entry| match &entry.content {
-                crate::service::cliboard_history::ClipboardContent::Text(text) => Some(text),
-                crate::service::cliboard_history::ClipboardContent::Image(_) => None,
-            });
-        let is_match = !matched
-            && current_text.is_some()
-            && row_text.is_some_and(|value| current_text == Some(value.as_str()));
+        let is_current = current_key.is_some() && row_key(&current) == current_key;
 
-        if is_match {
+        if is_current {
             current.add_css_class(CURRENT_CLIPBOARD_CLASS);
-            matched = true;
         } else {
             current.remove_css_class(CURRENT_CLIPBOARD_CLASS);
         }
@@ -449,7 +963,7 @@ fn first_visible_row(list_box: &gtk::ListBox) -> Option<gtk::ListBoxRow> {
     let mut row = list_box.first_child().and_downcast::<gtk::ListBoxRow>();
 
     while let Some(current) = row {
-        if current.is_visible() {
+        if is_row_shown(&current) {
             return Some(current);
         }
 
@@ -459,91 +973,835 @@ fn first_visible_row(list_box: &gtk::ListBox) -> Option<gtk::ListBoxRow> {
     None
 }
 
-fn last_visible_row(list_box: &gtk::ListBox) -> Option<gtk::ListBoxRow> {
-    let mut row = list_box.last_child().and_downcast::<gtk::ListBoxRow>();
+/// The rows left by the search filter, in order.
+fn visible_rows(list_box: &gtk::ListBox) -> Vec<gtk::ListBoxRow> {
+    (0..)
+        .map_while(|index| list_box.row_at_index(index))
+        .filter(is_row_shown)
+        .collect()
+}
+
+/// The filter hides rows through their child visibility, collapsed groups
+/// through their visibility.
+fn is_row_shown(row: &gtk::ListBoxRow) -> bool {
+    row.is_visible() && row.is_child_visible() && !row.has_css_class(COLLAPSED_CLASS)
+}
 
-    while let Some(current) = row {
-        if current.is_visible() {
-            return Some(current);
+fn ensure_visible_selection(list_box: &gtk::ListBox) {
+    match list_box.selected_row() {
+        Some(selected) => {
+            if is_row_shown(&selected) {
+                return;
+            }
         }
+        None => {
+            return;
+        }
+    }
 
-        row = current.prev_sibling().and_downcast::<gtk::ListBoxRow>();
+    if let Some(row) = first_visible_row(list_box) {
+        list_box.select_row(Some(&row));
+    } else {
+        list_box.select_row(None::<&gtk::ListBoxRow>);
     }
+}
 
-    None
+/// The cliphist id of `entry`. Ids of other sources mean nothing to
+/// cliphist.
+fn cliphist_id(entry: &ClipboardEntry) -> Option<&str> {
+    let cliphist = IClipboardProvider::name(&CliphistProvider);
+    entry.id.as_deref().filter(|_| entry.source == cliphist)
+}
+
+fn fetch_full_content(entry: &ClipboardEntry) -> Option<String> {
+    CliphistProvider
+        .decode(cliphist_id(entry)?)
+        .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+}
+
+fn fetch_binary_content(entry: &ClipboardEntry) -> Option<glib::Bytes> {
+    CliphistProvider
+        .decode(cliphist_id(entry)?)
+        .map(glib::Bytes::from_owned)
+}
+
+fn entry_image_bytes(entry: &ClipboardEntry) -> Option<glib::Bytes> {
+    if let Some(bytes) = entry.content.image_bytes() {
+        return Some(glib::Bytes::from(bytes));
+    }
+
+    fetch_binary_content(entry)
 }
 
-fn next_visible_row(row: &gtk::ListBoxRow) -> Option<gtk::ListBoxRow> {
-    let mut next = row.next_sibling().and_downcast::<gtk::ListBoxRow>();
+/// Which selections a copy goes to.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CopyTarget {
+    Clipboard,
+    /// The selection pasted with a middle click.
+    Primary,
+    Both,
+}
 
-    while let Some(candidate) = next {
-        if candidate.is_visible() {
-            return Some(candidate);
+impl CopyTarget {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "clipboard" => Some(Self::Clipboard),
+            "primary" => Some(Self::Primary),
+            "both" => Some(Self::Both),
+            _ => None,
         }
+    }
 
-        next = candidate.next_sibling().and_downcast::<gtk::ListBoxRow>();
+    fn clipboards(self, display: &gdk::Display) -> Vec<gdk::Clipboard> {
+        match self {
+            Self::Clipboard => vec![display.clipboard()],
+            Self::Primary => vec![display.primary_clipboard()],
+            Self::Both => vec![display.clipboard(), display.primary_clipboard()],
+        }
     }
+}
 
-    None
+/// Puts `entry` on the `target` selections in the formats it was recorded
+/// with.
+fn copy_entry(
+    list_box: &gtk::ListBox,
+    history: &Rc<RefCell<ClipboardHistory>>,
+    display: &gdk::Display,
+    target: CopyTarget,
+    toast_overlay: &adw::ToastOverlay,
+    entry: &ClipboardEntry,
+) {
+    let Some((provider, message)) = entry_content_provider(entry) else {
+        return;
+    };
+
+    for clipboard in target.clipboards(display) {
+        clipboard.set_content(Some(&provider)).ok();
+    }
+
+    let message = match target {
+        CopyTarget::Clipboard => message,
+        CopyTarget::Primary => "Copied to primary selection",
+        CopyTarget::Both => "Copied to clipboard and primary selection",
+    };
+
+    // PRIMARY alone leaves the clipboard, and so the current entry, as is.
+    if target != CopyTarget::Primary {
+        set_current_entry(list_box, history, entry);
+    }
+    toast_overlay.add_toast(adw::Toast::new(message));
+    list_box.activate_action("win.after-copy", None).ok();
 }
 
-fn prev_visible_row(row: &gtk::ListBoxRow) -> Option<gtk::ListBoxRow> {
-    let mut prev = row.prev_sibling().and_downcast::<gtk::ListBoxRow>();
+/// The content of `entry` in the formats it was recorded with, and the
+/// message confirming a copy of it.
+fn entry_content_provider(entry: &ClipboardEntry) -> Option<(gdk::ContentProvider, &'static str)> {
+    let (provider, message) = match &entry.content {
+        crate::service::cliboard_history::ClipboardContent::Text(text) => {
+            let final_text = fetch_full_content(entry).unwrap_or_else(|| text.clone());
+
+            (
+                gdk::ContentProvider::for_value(&final_text.to_value()),
+                "Copied to clipboard",
+            )
+        }
+        crate::service::cliboard_history::ClipboardContent::Rich { plain, html, rtf } => (
+            rich_content_provider(plain, html, rtf),
+            "Copied to clipboard",
+        ),
+        crate::service::cliboard_history::ClipboardContent::Files { uris, operation } => (
+            files_content_provider(uris, operation.as_deref()),
+            "Files copied to clipboard",
+        ),
+        crate::service::cliboard_history::ClipboardContent::Image(_) => (
+            gdk::ContentProvider::for_value(&entry_texture(entry)?.to_value()),
+            "Image copied to clipboard",
+        ),
+    };
+
+    if entry.formats.is_empty() {
+        return Some((provider, message));
+    }
 
-    while let Some(candidate) = prev {
-        if candidate.is_visible() {
-            return Some(candidate);
+    // The recorded formats come first; the content adds those that were
+    // not kept because they repeat it.
+    let mut providers: Vec<gdk::ContentProvider> =
+        entry.formats.iter().map(format_content_provider).collect();
+    providers.push(provider);
+    Some((gdk::ContentProvider::new_union(&providers), message))
+}
+
+fn format_content_provider(format: &ClipboardFormat) -> gdk::ContentProvider {
+    gdk::ContentProvider::for_bytes(&format.mime_type, &glib::Bytes::from(&*format.data))
+}
+
+/// Restores the formats file managers paste from: the GNOME copied-files
+/// list when it was offered, the URI list and the paths as plain text.
+fn files_content_provider(uris: &[String], operation: Option<&str>) -> gdk::ContentProvider {
+    let mut providers = Vec::new();
+
+    if let Some(operation) = operation {
+        providers.push(gdk::ContentProvider::for_bytes(
+            file_list::GNOME_COPIED_FILES_MIME,
+            &glib::Bytes::from_owned(file_list::render_gnome_copied_files(operation, uris)),
+        ));
+    }
+    providers.push(gdk::ContentProvider::for_bytes(
+        file_list::URI_LIST_MIME,
+        &glib::Bytes::from_owned(file_list::render_uri_list(uris)),
+    ));
+    providers.push(gdk::ContentProvider::for_value(
+        &file_list::render_plain(uris).to_value(),
+    ));
+
+    gdk::ContentProvider::new_union(&providers)
+}
+
+/// Looks the copied files up off the main thread, once per row, then shows
+/// their sizes, the icon of the first and whether any is gone.
+fn show_file_summaries(
+    row: &adw::ActionRow,
+    icon: &gtk::Image,
+    missing: &gtk::Image,
+    uris: &[String],
+    update_title: bool,
+) {
+    let row = row.downgrade();
+    let icon = icon.downgrade();
+    let missing = missing.downgrade();
+    let uris = uris.to_vec();
+
+    glib::spawn_future_local(async move {
+        let lookup = uris.clone();
+        let Ok(summaries) = gio::spawn_blocking(move || {
+            lookup
+                .iter()
+                .map(|uri| file_list::summarize(uri))
+                .collect::<Vec<_>>()
+        })
+        .await
+        else {
+            return;
+        };
+        let (Some(row), Some(icon), Some(missing)) =
+            (row.upgrade(), icon.upgrade(), missing.upgrade())
+        else {
+            return;
+        };
+
+        if update_title {
+            row.set_title(&glib::markup_escape_text(&files_title(
+                &uris,
+                Some(&summaries),
+            )));
         }
+        if let Some(first) = summaries.first() {
+            icon.set_from_gicon(&file_icon(&first.name, first.is_dir));
+        }
+        missing.set_visible(summaries.iter().any(|summary| !summary.exists));
+    });
+}
 
-        prev = candidate.prev_sibling().and_downcast::<gtk::ListBoxRow>();
+/// One line per file with its size, or a note when it is gone. Only the
+/// names until the files were looked up.
+fn files_title(uris: &[String], summaries: Option<&[FileSummary]>) -> String {
+    let mut lines: Vec<String> = match summaries {
+        Some(summaries) => summaries
+            .iter()
+            .take(MAX_FILES_SHOWN)
+            .map(|summary| match (summary.exists, summary.size) {
+                (false, _) => format!("{} — missing", summary.name),
+                (true, Some(size)) => format!("{} — {}", summary.name, glib::format_size(size)),
+                (true, None) => summary.name.clone(),
+            })
+            .collect(),
+        None => uris
+            .iter()
+            .take(MAX_FILES_SHOWN)
+            .map(|uri| file_list::file_name(uri))
+            .collect(),
+    };
+
+    if uris.len() > MAX_FILES_SHOWN {
+        lines.push(format!("and {} more", uris.len() - MAX_FILES_SHOWN));
     }
 
-    None
+    lines.join("\n")
 }
 
-fn ensure_visible_selection(list_box: &gtk::ListBox) {
-    match list_box.selected_row() {
-        Some(selected) => {
-            if selected.is_visible() {
+fn file_icon(name: &str, is_dir: bool) -> gio::Icon {
+    if is_dir {
+        return gio::ThemedIcon::new("folder-symbolic").upcast();
+    }
+
+    let (content_type, _) = gio::content_type_guess(Some(name), None);
+    gio::content_type_get_symbolic_icon(&content_type)
+}
+
+/// Offers the formatted versions next to plain text, so each target picks
+/// the richest format it understands.
+fn rich_content_provider(
+    plain: &str,
+    html: &Option<String>,
+    rtf: &Option<ClipboardFormat>,
+) -> gdk::ContentProvider {
+    let mut providers = Vec::new();
+
+    if let Some(html) = html {
+        providers.push(gdk::ContentProvider::for_bytes(
+            rich_text::HTML_MIME,
+            &glib::Bytes::from(html.as_bytes()),
+        ));
+    }
+    if let Some(rtf) = rtf {
+        providers.push(format_content_provider(rtf));
+    }
+    providers.push(gdk::ContentProvider::for_value(&plain.to_value()));
+
+    gdk::ContentProvider::new_union(&providers)
+}
+
+fn copy_plain_text(
+    list_box: &gtk::ListBox,
+    history: &Rc<RefCell<ClipboardHistory>>,
+    display: &gdk::Display,
+    toast_overlay: &adw::ToastOverlay,
+    entry: &ClipboardEntry,
+) {
+    if entry.content.is_image() {
+        return;
+    }
+
+    let text = match &entry.content {
+        crate::service::cliboard_history::ClipboardContent::Text(text) => {
+            fetch_full_content(entry).unwrap_or_else(|| text.clone())
+        }
+        content => content.as_text(),
+    };
+
+    display.clipboard().set_text(&text);
+    set_current_entry(list_box, history, entry);
+    toast_overlay.add_toast(adw::Toast::new("Copied as plain text"));
+    list_box.activate_action("win.after-copy", None).ok();
+}
+
+/// Copies the selected entry without formatting.
+pub fn setup_plain_text_action(
+    window: &adw::ApplicationWindow,
+    clamp: &adw::Clamp,
+    history: Rc<RefCell<ClipboardHistory>>,
+    display: &gdk::Display,
+    toast_overlay: &adw::ToastOverlay,
+) {
+    let action = gio::SimpleAction::new("copy-plain-text", None);
+
+    action.connect_activate(glib::clone!(
+        #[weak]
+        clamp,
+        #[strong]
+        display,
+        #[weak]
+        toast_overlay,
+        move |_, _| {
+            let Some((list_box, entry)) = selected_entry(&clamp, &history) else {
+                return;
+            };
+
+            copy_plain_text(&list_box, &history, &display, &toast_overlay, &entry);
+        }
+    ));
+
+    window.add_action(&action);
+}
+
+/// Adds the keyboard-driven entry actions: `copy-nth` copies the Nth
+/// visible entry, `remove-selected` and `toggle-pin` act on the selected one.
+pub fn setup_entry_actions(
+    window: &adw::ApplicationWindow,
+    clamp: &adw::Clamp,
+    monitor: ClipboardMonitor,
+    toast_overlay: &adw::ToastOverlay,
+) {
+    let history = monitor.history();
+
+    let action_copy_nth = gio::SimpleAction::new("copy-nth", Some(glib::VariantTy::UINT32));
+    action_copy_nth.connect_activate(glib::clone!(
+        #[weak]
+        clamp,
+        move |_, parameter| {
+            let Some(number) = parameter.and_then(|parameter| parameter.get::<u32>()) else {
+                return;
+            };
+            let Some(list_box) = list_box_of(&clamp) else {
+                return;
+            };
+            if is_placeholder_row(&list_box) {
                 return;
             }
+
+            if let Some(row) = number
+                .checked_sub(1)
+                .and_then(|index| visible_rows(&list_box).into_iter().nth(index as usize))
+            {
+                list_box.select_row(Some(&row));
+                row.activate();
+            }
         }
-        None => {
-            return;
+    ));
+    window.add_action(&action_copy_nth);
+
+    let action_remove = gio::SimpleAction::new("remove-selected", None);
+    action_remove.connect_activate(glib::clone!(
+        #[weak]
+        clamp,
+        #[strong]
+        monitor,
+        #[strong]
+        history,
+        #[weak]
+        toast_overlay,
+        move |_, _| {
+            let Some((list_box, entry)) = selected_entry(&clamp, &history) else {
+                return;
+            };
+            let index = list_box
+                .selected_row()
+                .and_then(|row| {
+                    visible_rows(&list_box)
+                        .iter()
+                        .position(|shown| *shown == row)
+                })
+                .unwrap_or(0);
+
+            monitor.remove_entry(entry.key);
+
+            let rows = list_box_of(&clamp)
+                .filter(|list_box| !is_placeholder_row(list_box))
+                .map(|list_box| visible_rows(&list_box))
+                .unwrap_or_default();
+            if let Some(row) = rows.get(index).or(rows.last()) {
+                list_box.select_row(Some(row));
+                row.grab_focus();
+            }
+            toast_overlay.add_toast(adw::Toast::new("Entry removed"));
         }
+    ));
+    window.add_action(&action_remove);
+
+    let action_view = gio::SimpleAction::new("view-image", None);
+    action_view.connect_activate(glib::clone!(
+        #[weak]
+        clamp,
+        #[weak]
+        toast_overlay,
+        #[strong]
+        monitor,
+        #[strong]
+        history,
+        move |_, _| {
+            let Some((list_box, entry)) = selected_entry(&clamp, &history) else {
+                return;
+            };
+            if !entry.content.is_image() {
+                return;
+            }
+
+            let result = entry_image_bytes(&entry)
+                .ok_or_else(|| "The image is no longer available".to_string())
+                .and_then(|bytes| {
+                    image_viewer::show(
+                        &list_box,
+                        &bytes,
+                        &image_file_name(&entry, "png"),
+                        monitor.clone(),
+                    )
+                });
+            if let Err(err) = result {
+                toast_overlay.add_toast(adw::Toast::new(&err));
+            }
+        }
+    ));
+    window.add_action(&action_view);
+
+    // Only image rows can be viewed.
+    if let Some(list_box) = list_box_of(clamp) {
+        list_box.connect_row_selected(glib::clone!(
+            #[weak]
+            action_view,
+            move |_, row| {
+                action_view.set_enabled(
+                    row.is_some_and(|row| row_kinds(row).contains(&ContentFilter::Images)),
+                );
+            }
+        ));
     }
 
-    if let Some(row) = first_visible_row(list_box) {
-        list_box.select_row(Some(&row));
-    } else {
-        list_box.select_row(None::<&gtk::ListBoxRow>);
+    let action_pin = gio::SimpleAction::new("toggle-pin", None);
+    action_pin.connect_activate(glib::clone!(
+        #[weak]
+        clamp,
+        #[weak]
+        toast_overlay,
+        move |_, _| {
+            let Some((_, entry)) = selected_entry(&clamp, &history) else {
+                return;
+            };
+            let Some(pinned) = monitor.toggle_pinned(entry.key) else {
+                return;
+            };
+
+            if let Some(list_box) = list_box_of(&clamp)
+                && let Some(row) = row_for_key(&list_box, entry.key)
+                && is_row_shown(&row)
+            {
+                list_box.select_row(Some(&row));
+                row.grab_focus();
+            }
+            toast_overlay.add_toast(adw::Toast::new(if pinned {
+                "Entry pinned"
+            } else {
+                "Entry unpinned"
+            }));
+        }
+    ));
+    window.add_action(&action_pin);
+}
+
+/// Activates the selected row, which copies its entry. Returns false when
+/// nothing is selected.
+pub fn activate_selected(clamp: &adw::Clamp) -> bool {
+    let Some(list_box) = list_box_of(clamp) else {
+        return false;
+    };
+    if is_placeholder_row(&list_box) {
+        return false;
+    }
+
+    let Some(row) = list_box
+        .selected_row()
+        .filter(is_row_shown)
+        .or_else(|| first_visible_row(&list_box))
+    else {
+        return false;
+    };
+
+    row.activate();
+    true
+}
+
+fn list_box_of(clamp: &adw::Clamp) -> Option<gtk::ListBox> {
+    clamp
+        .child()
+        .and_downcast::<gtk::ScrolledWindow>()
+        .and_then(|scrolled| find_list_box(&scrolled))
+}
+
+/// Copies the selected entry to the selections named by the parameter:
+/// `clipboard`, `primary` or `both`.
+pub fn setup_copy_to_action(
+    window: &adw::ApplicationWindow,
+    clamp: &adw::Clamp,
+    history: Rc<RefCell<ClipboardHistory>>,
+    display: &gdk::Display,
+    toast_overlay: &adw::ToastOverlay,
+) {
+    let action = gio::SimpleAction::new("copy-to", Some(glib::VariantTy::STRING));
+
+    action.connect_activate(glib::clone!(
+        #[weak]
+        clamp,
+        #[strong]
+        display,
+        #[weak]
+        toast_overlay,
+        move |_, parameter| {
+            let Some(target) = parameter
+                .and_then(|parameter| parameter.str())
+                .and_then(CopyTarget::from_name)
+            else {
+                return;
+            };
+            let Some((list_box, entry)) = selected_entry(&clamp, &history) else {
+                return;
+            };
+
+            copy_entry(
+                &list_box,
+                &history,
+                &display,
+                target,
+                &toast_overlay,
+                &entry,
+            );
+        }
+    ));
+
+    window.add_action(&action);
+}
+
+fn selected_entry(
+    clamp: &adw::Clamp,
+    history: &Rc<RefCell<ClipboardHistory>>,
+) -> Option<(gtk::ListBox, ClipboardEntry)> {
+    let list_box = list_box_of(clamp)?;
+    let entry = row_entry(&list_box.selected_row()?, &history.borrow())?;
+
+    Some((list_box, entry))
+}
+
+/// Opens the copy menu for the row under a right click.
+fn setup_context_menu(list_box: &gtk::ListBox) {
+    let menu = gio::Menu::new();
+    menu.append(Some("Copy to Clipboard"), Some("win.copy-to('clipboard')"));
+    menu.append(
+        Some("Copy to Primary Selection"),
+        Some("win.copy-to('primary')"),
+    );
+    menu.append(Some("Copy to Both"), Some("win.copy-to('both')"));
+    menu.append(Some("Copy as Plain Text"), Some("win.copy-plain-text"));
+
+    let entry_section = gio::Menu::new();
+    entry_section.append(Some("View Image"), Some("win.view-image"));
+    entry_section.append(Some("Pin or Unpin"), Some("win.toggle-pin"));
+    entry_section.append(Some("Remove"), Some("win.remove-selected"));
+    menu.append_section(None, &entry_section);
+
+    let gesture = gtk::GestureClick::builder()
+        .button(gdk::BUTTON_SECONDARY)
+        .build();
+
+    gesture.connect_pressed(glib::clone!(
+        #[weak]
+        list_box,
+        move |gesture, _, x, y| {
+            let Some(row) = list_box.row_at_y(y as i32) else {
+                return;
+            };
+            if is_placeholder_row(&list_box) {
+                return;
+            }
+
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            list_box.select_row(Some(&row));
+
+            let popover = gtk::PopoverMenu::from_model(Some(&menu));
+            popover.set_parent(&list_box);
+            popover.set_has_arrow(false);
+            popover.set_halign(gtk::Align::Start);
+            // Unparent only after the chosen item's action has run.
+            popover.connect_closed(|popover| {
+                glib::idle_add_local_once(glib::clone!(
+                    #[weak]
+                    popover,
+                    move || popover.unparent()
+                ));
+            });
+            popover.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+            popover.popup();
+        }
+    ));
+
+    list_box.add_controller(gesture);
+}
+
+/// Decodes the image in memory, once per entry. Only dragging writes images
+/// to disk, see `save_dragged_image`.
+fn entry_texture(entry: &ClipboardEntry) -> Option<gdk::Texture> {
+    if let Some(texture) = cached_texture(entry.key) {
+        return Some(texture);
     }
+
+    let texture = decode_texture(entry)?;
+    TEXTURES.with_borrow_mut(|textures| textures.insert(entry.key, texture.clone()));
+    Some(texture)
 }
 
-fn fetch_full_content(id: &str) -> Option<String> {
-    let output = Command::new("cliphist")
-        .arg("decode")
-        .arg(id)
-        .output()
-        .ok()?;
+fn cached_texture(key: EntryKey) -> Option<gdk::Texture> {
+    TEXTURES.with_borrow(|textures| textures.get(&key).cloned())
+}
 
-    if output.status.success() {
-        return Some(String::from_utf8_lossy(&output.stdout).to_string());
+fn decode_texture(entry: &ClipboardEntry) -> Option<gdk::Texture> {
+    let bytes = entry_image_bytes(entry)?;
+    gdk::Texture::from_bytes(&bytes).ok()
+}
+
+/// Shows the image of `entry` at the start of `row`. Images not decoded yet
+/// show an icon until they are, as cliphist images take a process each.
+fn show_thumbnail(row: &adw::ActionRow, entry: &ClipboardEntry) {
+    let picture = gtk::Picture::builder()
+        .content_fit(gtk::ContentFit::ScaleDown)
+        .width_request(48)
+        .height_request(48)
+        .build();
+
+    if let Some(texture) = cached_texture(entry.key) {
+        picture.set_paintable(Some(&texture));
+        row.add_prefix(&picture);
+        return;
     }
 
-    None
+    let icon = gtk::Image::from_icon_name("image-x-generic-symbolic");
+    row.add_prefix(&icon);
+
+    let row = row.downgrade();
+    let icon = icon.downgrade();
+    let entry = entry.clone();
+
+    glib::spawn_future_local(async move {
+        let key = entry.key;
+        let Ok(Some(texture)) = gio::spawn_blocking(move || decode_texture(&entry)).await else {
+            return;
+        };
+        TEXTURES.with_borrow_mut(|textures| textures.insert(key, texture.clone()));
+
+        let (Some(row), Some(icon)) = (row.upgrade(), icon.upgrade()) else {
+            return;
+        };
+        picture.set_paintable(Some(&texture));
+        row.remove(&icon);
+        row.add_prefix(&picture);
+    });
 }
 
-fn fetch_binary_content(id: &str) -> Option<glib::Bytes> {
-    let output = Command::new("cliphist")
-        .arg("decode")
-        .arg(id)
-        .output()
-        .ok()?;
+/// Lets rows be dragged into other applications with the content of their
+/// entry.
+fn setup_drag_source(
+    list_box: &gtk::ListBox,
+    history: Rc<RefCell<ClipboardHistory>>,
+    clipboard_monitor: ClipboardMonitor,
+) {
+    let drag_source = gtk::DragSource::builder()
+        .actions(gdk::DragAction::COPY)
+        .build();
+    let dragged_file: Rc<RefCell<Option<PathBuf>>> = Rc::default();
 
-    if output.status.success() {
-        return Some(glib::Bytes::from(&output.stdout));
+    drag_source.connect_prepare(glib::clone!(
+        #[weak]
+        list_box,
+        #[strong]
+        dragged_file,
+        #[upgrade_or]
+        None,
+        move |source, _, y| {
+            if clipboard_monitor.is_store_locked() {
+                return None;
+            }
+            let row = list_box.row_at_y(y as i32)?;
+            let entry = row_entry(&row, &history.borrow())?;
+            // Anything written to disk would be readable without the key.
+            let offer_file = !clipboard_monitor.is_store_encrypted();
+            let (provider, file) = drag_content_provider(&entry, offer_file)?;
+
+            source.set_icon(Some(&gtk::WidgetPaintable::new(Some(&row))), 0, 0);
+            dragged_file.replace(file);
+            Some(provider)
+        }
+    ));
+    drag_source.connect_drag_end(move |_, _, _| {
+        let Some(path) = dragged_file.take() else {
+            return;
+        };
+        // Drop targets may read the file only after the drag has ended.
+        glib::timeout_add_seconds_local_once(DRAGGED_FILE_SECONDS, move || {
+            let _ = std::fs::remove_file(path);
+        });
+    });
+
+    list_box.add_controller(drag_source);
+}
+
+/// The formats a copy would offer. Images are also offered as a file, which
+/// file managers save where they are dropped; returns its path as well.
+fn drag_content_provider(
+    entry: &ClipboardEntry,
+    offer_file: bool,
+) -> Option<(gdk::ContentProvider, Option<PathBuf>)> {
+    let (provider, _) = entry_content_provider(entry)?;
+    let Some(path) = (offer_file && entry.content.is_image())
+        .then(|| save_dragged_image(entry))
+        .flatten()
+    else {
+        return Some((provider, None));
+    };
+
+    let files = gdk::FileList::from_array(&[gio::File::for_path(&path)]);
+    let provider = gdk::ContentProvider::new_union(&[
+        provider,
+        gdk::ContentProvider::for_value(&files.to_value()),
+    ]);
+    Some((provider, Some(path)))
+}
+
+/// A file name for the image of `entry`, after the time it was copied and
+/// its key, so images copied within the same second do not collide.
+fn image_file_name(entry: &ClipboardEntry, extension: &str) -> String {
+    format!(
+        "clipboard-image-{}-{}.{extension}",
+        entry.timestamp.format("%Y-%m-%d-%H%M%S"),
+        entry.key
+    )
+}
+
+fn dragged_images_dir() -> PathBuf {
+    config_service::app_runtime_dir().join("dragged")
+}
+
+/// Writes the dragged image where only the user can read it. Images are
+/// written as they were copied; only unknown formats are converted to PNG.
+fn save_dragged_image(entry: &ClipboardEntry) -> Option<PathBuf> {
+    let bytes = entry_image_bytes(entry)?;
+    let (bytes, extension) = match history_transfer::image_extension(&bytes) {
+        "bin" => (
+            gdk::Texture::from_bytes(&bytes).ok()?.save_to_png_bytes(),
+            "png",
+        ),
+        extension => (bytes, extension),
+    };
+
+    let dir = dragged_images_dir();
+    if let Err(err) = std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)
+    {
+        eprintln!("Error creating drag directory: {err}");
+        return None;
     }
 
-    None
+    let path = dir.join(image_file_name(entry, extension));
+    let written = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&path)
+        .and_then(|mut file| file.write_all(&bytes));
+    if let Err(err) = written {
+        eprintln!("Error saving dragged image: {err}");
+        return None;
+    }
+
+    Some(path)
+}
+
+/// Removes images left over from drags, at startup and on shutdown.
+pub fn remove_dragged_images() {
+    let _ = std::fs::remove_dir_all(dragged_images_dir());
+}
+
+/// Earlier versions left decoded images world-readable in the temp
+/// directory. Removes whatever is still there.
+pub fn remove_legacy_temp_files() {
+    let Ok(entries) = std::fs::read_dir(std::env::temp_dir()) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name == "clipboard_manager_temp_image" || name.starts_with("clipboard_manager_preview_")
+        {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
 }
@@ -1,11 +1,28 @@
 pub mod about;
+pub mod details;
+pub mod drop_target;
+pub mod entry_row;
 pub mod header;
+pub mod image_viewer;
+pub mod keybindings;
 pub mod list;
+pub mod paste;
+pub mod popup;
+pub mod recording;
 pub mod search_bar;
+pub mod selection;
 pub mod shortcuts;
+pub mod sources;
+pub mod transfer;
+pub mod unlock;
 
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    sync::Arc,
+};
 
+use chrono::Duration;
 use gtk::{
     Orientation,
     gdk::{self},
@@ -15,16 +32,34 @@ use gtk::{
 };
 use libadwaita as adw;
 
-use crate::service::{
-    cliboard_monitor::{ClipboardMonitor, IClipboardMonitor},
-    cliphist_provider::CliphistProvider,
-    style_service::StyleService,
+use crate::{
+    cli::StartupOptions,
+    service::{
+        auto_paste::CommandKeyboard,
+        cliboard_history::IClipboardHistory,
+        cliboard_monitor::{ClipboardMonitor, IClipboardMonitor},
+        cliboard_provider::{BoxedClipboardProvider, IClipboardProvider},
+        clipboard_filter::ClipboardFilter,
+        config_service::{AppConfig, ConfigService, StoreEncryption},
+        entry_sort::SortMode,
+        history_transfer,
+        keybindings::KeybindingRegistry,
+        native_store_provider::{NativeStoreProvider, StoreProtection},
+        style_service::StyleService,
+        time_format::TimeStyle,
+    },
 };
 
-pub fn build_ui(app: &adw::Application, display: &gdk::Display) {
+const TIMESTAMP_REFRESH_SECONDS: u32 = 30;
+const SENSITIVE_EXPIRY_CHECK_SECONDS: u32 = 60;
+
+pub fn build_ui(app: &adw::Application, display: &gdk::Display, startup: StartupOptions) {
     let style_service = StyleService::new();
     style_service.apply_styles(display);
-    let (header_bar, search_button) = header::build();
+    list::remove_legacy_temp_files();
+    list::remove_dragged_images();
+    let config = Rc::new(RefCell::new(ConfigService::new().load()));
+    let (header_bar, search_button, title) = header::build(&sources::build_menu(&config.borrow()));
 
     let content = gtk::Box::builder()
         .orientation(Orientation::Vertical)
@@ -32,7 +67,17 @@ pub fn build_ui(app: &adw::Application, display: &gdk::Display) {
 
     content.append(&header_bar);
 
-    let (search_bar, search_entry) = search_bar::build();
+    let body = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .build();
+    let (lock_page, unlock_button) = unlock::build_lock_page();
+    let stack = gtk::Stack::new();
+    stack.add_named(&body, Some(unlock::HISTORY_PAGE));
+    stack.add_named(&lock_page, Some(unlock::LOCKED_PAGE));
+    content.append(&stack);
+
+    let (search_bar, search_entry, filter_chips) =
+        search_bar::build(&config.borrow().content_filters);
 
     search_button
         .bind_property("active", &search_bar, "search-mode-enabled")
@@ -40,11 +85,25 @@ pub fn build_ui(app: &adw::Application, display: &gdk::Display) {
         .bidirectional()
         .build();
 
-    content.append(&search_bar);
+    body.append(&search_bar);
 
     let clipboard_monitor = ClipboardMonitor::new(display);
+    if config.borrow().encryption != StoreEncryption::None {
+        clipboard_monitor.set_store_protection(StoreProtection::Locked);
+    }
+    recording::setup_recording_actions(
+        app,
+        &header_bar,
+        &title,
+        clipboard_monitor.clone(),
+        config.clone(),
+        startup.recording,
+    );
+
     let history = clipboard_monitor.history();
-    let current_clipboard = Rc::new(RefCell::new(None));
+    let time_style = Rc::new(Cell::new(config.borrow().time_style));
+    let collapsed_groups = list::CollapsedGroups::default();
+    let sort_mode = Rc::new(Cell::new(config.borrow().sort_mode));
 
     let toast_overlay = adw::ToastOverlay::new();
     toast_overlay.set_child(Some(&content));
@@ -52,47 +111,152 @@ pub fn build_ui(app: &adw::Application, display: &gdk::Display) {
     let list_view = list::build(
         history.clone(),
         display,
-        current_clipboard.clone(),
         toast_overlay.clone(),
+        time_style.clone(),
+        collapsed_groups.clone(),
+        sort_mode.clone(),
+        clipboard_monitor.clone(),
     );
 
-    list::setup_search(&list_view, &search_entry);
-    list::focus_list(&list_view);
+    let sort_dropdown = header::build_sort_dropdown(sort_mode.get());
+    sort_dropdown.connect_selected_notify(glib::clone!(
+        #[weak]
+        list_view,
+        #[strong]
+        config,
+        #[strong]
+        history,
+        #[strong]
+        sort_mode,
+        move |dropdown| {
+            let Some(mode) = SortMode::ALL.get(dropdown.selected() as usize).copied() else {
+                return;
+            };
+            sort_mode.set(mode);
+            list::resort(&list_view, &history.borrow(), mode);
 
-    let provider = CliphistProvider;
-    let display_clone = display.clone();
-    let toast_overlay_clone = toast_overlay.clone();
+            config.borrow_mut().sort_mode = mode;
+            if let Err(err) = ConfigService::new().save(&config.borrow()) {
+                eprintln!("Error saving config: {err}");
+            }
+        }
+    ));
+    header_bar.pack_end(&sort_dropdown);
 
-    glib::MainContext::default().spawn_local(glib::clone!(
+    list::setup_search(&list_view, &search_bar, &search_entry, &filter_chips);
+    filter_chips.connect_changed(glib::clone!(
+        #[strong]
+        config,
+        move |active| {
+            config.borrow_mut().content_filters = active.clone();
+            if let Err(err) = ConfigService::new().save(&config.borrow()) {
+                eprintln!("Error saving config: {err}");
+            }
+        }
+    ));
+    list::focus_list(&list_view);
+
+    let refresh: Rc<dyn Fn()> = Rc::new(glib::clone!(
         #[weak]
         list_view,
         #[strong]
         history,
         #[strong]
-        current_clipboard,
+        display,
         #[strong]
-        display_clone,
+        toast_overlay,
         #[strong]
-        toast_overlay_clone,
+        time_style,
         #[strong]
-        clipboard_monitor,
-        async move {
-            match clipboard_monitor.load_history(provider).await {
-                Ok(_) => {
-                    list::refresh_list(
-                        &list_view,
-                        history,
-                        &display_clone,
-                        current_clipboard,
-                        toast_overlay_clone,
-                    );
+        collapsed_groups,
+        #[strong]
+        filter_chips,
+        #[strong]
+        sort_mode,
+        move || {
+            list::refresh_list(
+                &list_view,
+                history.clone(),
+                &display,
+                toast_overlay.clone(),
+                time_style.clone(),
+                &collapsed_groups,
+                sort_mode.get(),
+            );
+            filter_chips.update_counts(history.borrow().entries());
+        }
+    ));
+    filter_chips.update_counts(history.borrow().entries());
+
+    glib::timeout_add_seconds_local(
+        SENSITIVE_EXPIRY_CHECK_SECONDS,
+        glib::clone!(
+            #[strong]
+            clipboard_monitor,
+            #[strong]
+            config,
+            #[strong]
+            refresh,
+            move || {
+                let (minutes, from_cliphist) = {
+                    let config = config.borrow();
+                    (config.sensitive_expiry_minutes, config.expire_from_cliphist)
+                };
+                if minutes > 0
+                    && clipboard_monitor
+                        .expire_sensitive(Duration::minutes(minutes as i64), from_cliphist)
+                        > 0
+                {
+                    refresh();
                 }
-                Err(err) => eprintln!("Error loading history: {err}"),
+                glib::ControlFlow::Continue
+            }
+        ),
+    );
+
+    glib::timeout_add_seconds_local(
+        TIMESTAMP_REFRESH_SECONDS,
+        glib::clone!(
+            #[weak]
+            list_view,
+            #[strong]
+            history,
+            #[strong]
+            time_style,
+            #[upgrade_or]
+            glib::ControlFlow::Break,
+            move || {
+                list::refresh_timestamps(&list_view, &history.borrow(), time_style.get());
+                glib::ControlFlow::Continue
             }
+        ),
+    );
+
+    let reload: Rc<dyn Fn()> = Rc::new(glib::clone!(
+        #[strong]
+        clipboard_monitor,
+        #[strong]
+        config,
+        #[strong]
+        refresh,
+        move || {
+            glib::MainContext::default().spawn_local(glib::clone!(
+                #[strong]
+                clipboard_monitor,
+                #[strong]
+                config,
+                #[strong]
+                refresh,
+                async move {
+                    let config = config.borrow().clone();
+                    load_sources(&clipboard_monitor, &config).await;
+                    refresh();
+                }
+            ));
         }
     ));
 
-    content.append(&list_view);
+    body.append(&list_view);
 
     let window = adw::ApplicationWindow::builder()
         .application(app)
@@ -104,9 +268,19 @@ pub fn build_ui(app: &adw::Application, display: &gdk::Display) {
         .modal(true)
         .build();
 
+    if startup.popup || config.borrow().popup_mode {
+        popup::setup_popup(&window, &config.borrow());
+    }
+
     search_bar.set_key_capture_widget(Some(&window));
 
-    let keyboard_service = crate::service::keyboard_service::KeyboardService::new();
+    let keybinding_registry = Rc::new(RefCell::new(KeybindingRegistry::new(
+        &config.borrow().keybindings,
+    )));
+    keybindings::apply_accels(app, &keybinding_registry.borrow());
+
+    let keyboard_service =
+        crate::service::keyboard_service::KeyboardService::new(keybinding_registry.clone());
     keyboard_service.setup(&window, &search_button, &list_view);
 
     let action_search = gio::SimpleAction::new("search", None);
@@ -121,12 +295,199 @@ pub fn build_ui(app: &adw::Application, display: &gdk::Display) {
 
     window.add_action(&action_search);
 
-    app.set_accels_for_action("win.search", &["<Control>f"]);
+    list::setup_plain_text_action(
+        &window,
+        &list_view,
+        history.clone(),
+        display,
+        &toast_overlay,
+    );
+
+    list::setup_copy_to_action(
+        &window,
+        &list_view,
+        history.clone(),
+        display,
+        &toast_overlay,
+    );
+    selection::setup_selection_actions(&window, clipboard_monitor.clone(), config.clone());
+    paste::setup_paste_actions(
+        &window,
+        config.clone(),
+        Arc::new(CommandKeyboard::new(config.borrow().paste_backend)),
+        &toast_overlay,
+    );
+    list::setup_entry_actions(
+        &window,
+        &list_view,
+        clipboard_monitor.clone(),
+        &toast_overlay,
+    );
+
+    let action_absolute_time = gio::SimpleAction::new_stateful(
+        "absolute-time",
+        None,
+        &(time_style.get() == TimeStyle::Absolute).into(),
+    );
+
+    action_absolute_time.connect_activate(glib::clone!(
+        #[weak]
+        list_view,
+        #[strong]
+        history,
+        #[strong]
+        time_style,
+        #[strong]
+        config,
+        move |action, _| {
+            let absolute = !action
+                .state()
+                .and_then(|state| state.get())
+                .unwrap_or(false);
+            action.set_state(&absolute.into());
+
+            let style = if absolute {
+                TimeStyle::Absolute
+            } else {
+                TimeStyle::Relative
+            };
+            time_style.set(style);
+            list::refresh_timestamps(&list_view, &history.borrow(), style);
+
+            config.borrow_mut().time_style = style;
+            if let Err(err) = ConfigService::new().save(&config.borrow()) {
+                eprintln!("Error saving config: {err}");
+            }
+        }
+    ));
+
+    window.add_action(&action_absolute_time);
 
     about::setup_about_action(&window);
 
-    shortcuts::setup_shortcuts_action(&window);
+    shortcuts::setup_shortcuts_action(&window, keybinding_registry.clone());
+    keybindings::setup_keybindings_action(app, &window, keybinding_registry, config.clone());
+
+    let action_quit = gio::SimpleAction::new("quit", None);
+    action_quit.connect_activate(glib::clone!(
+        #[weak]
+        app,
+        move |_, _| app.quit()
+    ));
+    app.add_action(&action_quit);
+
+    clipboard_monitor.set_filter(ClipboardFilter::new(&config.borrow().exclusions));
+    clipboard_monitor.connect_history_changed(glib::clone!(
+        #[strong]
+        refresh,
+        move || refresh()
+    ));
+
+    drop_target::setup_drop_target(&toast_overlay, clipboard_monitor.clone(), &toast_overlay);
+
+    transfer::setup_transfer_actions(
+        &window,
+        clipboard_monitor.clone(),
+        &search_entry,
+        &toast_overlay,
+        refresh.clone(),
+    );
+
+    unlock::setup_auto_lock(&window, &stack, clipboard_monitor.clone(), config.clone());
+    unlock::setup_locked_actions(&window, &stack);
+    unlock::setup_unlock_button(
+        &unlock_button,
+        &window,
+        &stack,
+        clipboard_monitor.clone(),
+        config.clone(),
+        refresh.clone(),
+    );
+
+    sources::setup_source_actions(&window, config.clone(), reload);
+
+    // Re-copied entries move to the top without a new capture, so their new
+    // order is only saved here. Dragged images are not needed any more.
+    app.connect_shutdown(glib::clone!(
+        #[strong]
+        clipboard_monitor,
+        move |_| {
+            clipboard_monitor.persist_now();
+            list::remove_dragged_images();
+        }
+    ));
 
     window.present();
+
+    glib::MainContext::default().spawn_local(glib::clone!(
+        #[weak]
+        window,
+        #[weak]
+        stack,
+        #[strong]
+        history,
+        #[strong]
+        clipboard_monitor,
+        #[strong]
+        config,
+        #[strong]
+        refresh,
+        async move {
+            let config = config.borrow().clone();
+            if config.encryption != StoreEncryption::None {
+                let store = NativeStoreProvider::default();
+                let protection =
+                    unlock::request_protection(&window, config.encryption, store).await;
+                clipboard_monitor.set_store_protection(protection);
+            }
+            if clipboard_monitor.is_store_locked() {
+                stack.set_visible_child_name(unlock::LOCKED_PAGE);
+            }
+
+            load_sources(&clipboard_monitor, &config).await;
+
+            if let Some(provider) = startup.import_from
+                && let Err(err) = clipboard_monitor.load_history(provider).await
+            {
+                eprintln!("Error loading imported history: {err}");
+            }
+
+            if let Some(path) = startup.import {
+                match gio::spawn_blocking(move || history_transfer::import_history(&path)).await {
+                    Ok(Ok(entries)) => {
+                        let added = history.borrow_mut().merge_entries(entries);
+                        eprintln!("imported entries: {added}");
+                    }
+                    Ok(Err(err)) => eprintln!("Error importing history: {err}"),
+                    Err(err) => eprintln!("Error importing history: {err:?}"),
+                }
+            }
+
+            clipboard_monitor.persist();
+            refresh();
+        }
+    ));
     list::select_first_row(&list_view);
 }
+
+async fn load_sources(clipboard_monitor: &ClipboardMonitor, config: &AppConfig) {
+    let store = config
+        .is_enabled("native")
+        .then(NativeStoreProvider::default);
+    clipboard_monitor.set_store(store);
+
+    // The native store is loaded through the monitor so it is read with the
+    // current key.
+    let mut providers: Vec<BoxedClipboardProvider> = config
+        .enabled_providers()
+        .into_iter()
+        .filter(|provider| provider.name() != "native")
+        .collect();
+    if let Some(store) = clipboard_monitor.store() {
+        providers.push(Box::new(store));
+    }
+
+    for err in clipboard_monitor.load_providers(providers).await {
+        eprintln!("Error loading history: {err}");
+    }
+}
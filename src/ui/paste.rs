@@ -0,0 +1,237 @@
+use std::{cell::RefCell, rc::Rc, sync::Arc, time::Duration};
+
+use gtk::{gio, glib, prelude::*};
+use libadwaita as adw;
+
+use crate::service::{
+    auto_paste::{AutoPaster, IVirtualKeyboard, PasteKeys},
+    config_service::{AppConfig, ConfigService},
+};
+
+/// Radio items for the keys sent when pasting.
+pub fn build_paste_keys_menu() -> gio::Menu {
+    let menu = gio::Menu::new();
+
+    for keys in PasteKeys::ALL {
+        menu.append(
+            Some(keys.label()),
+            Some(&format!("win.paste-keys('{}')", keys.name())),
+        );
+    }
+
+    menu
+}
+
+/// Adds the close-after-copy and auto-paste settings, and `win.after-copy`,
+/// which the list activates once an entry was copied. `keyboard` types the
+/// paste keys; failures show in `toast_overlay`.
+pub fn setup_paste_actions(
+    window: &adw::ApplicationWindow,
+    config: Rc<RefCell<AppConfig>>,
+    keyboard: Arc<dyn IVirtualKeyboard>,
+    toast_overlay: &adw::ToastOverlay,
+) {
+    window.add_action(&toggle_action(
+        "close-after-copy",
+        config.clone(),
+        |config| &mut config.close_after_copy,
+    ));
+    window.add_action(&toggle_action("auto-paste", config.clone(), |config| {
+        &mut config.auto_paste
+    }));
+
+    let action_keys = gio::SimpleAction::new_stateful(
+        "paste-keys",
+        Some(glib::VariantTy::STRING),
+        &config.borrow().paste_keys.name().to_variant(),
+    );
+    action_keys.connect_activate(glib::clone!(
+        #[strong]
+        config,
+        move |action, parameter| {
+            let Some(keys) = parameter
+                .and_then(|parameter| parameter.str())
+                .and_then(PasteKeys::from_name)
+            else {
+                return;
+            };
+            action.set_state(&keys.name().to_variant());
+
+            config.borrow_mut().paste_keys = keys;
+            save(&config.borrow());
+        }
+    ));
+    window.add_action(&action_keys);
+
+    let action_after_copy = gio::SimpleAction::new("after-copy", None);
+    action_after_copy.connect_activate(glib::clone!(
+        #[weak]
+        window,
+        #[weak]
+        toast_overlay,
+        move |_, _| {
+            let host = WindowHost {
+                window,
+                toast_overlay,
+            };
+            after_copy(&host, &config.borrow(), keyboard.clone());
+        }
+    ));
+    window.add_action(&action_after_copy);
+}
+
+fn toggle_action(
+    name: &str,
+    config: Rc<RefCell<AppConfig>>,
+    field: fn(&mut AppConfig) -> &mut bool,
+) -> gio::SimpleAction {
+    let initial = *field(&mut config.borrow_mut());
+    let action = gio::SimpleAction::new_stateful(name, None, &initial.into());
+
+    action.connect_activate(move |action, _| {
+        let enabled = !action
+            .state()
+            .and_then(|state| state.get())
+            .unwrap_or(false);
+        action.set_state(&enabled.into());
+
+        *field(&mut config.borrow_mut()) = enabled;
+        save(&config.borrow());
+    });
+
+    action
+}
+
+/// What the after-copy steps need from the window.
+trait PasteHost {
+    fn hide(&self);
+    /// Pastes with `paster` once `delay` has passed, off the main thread.
+    fn paste_after(&self, delay: Duration, paster: AutoPaster);
+}
+
+struct WindowHost {
+    window: adw::ApplicationWindow,
+    toast_overlay: adw::ToastOverlay,
+}
+
+impl PasteHost for WindowHost {
+    /// Hides the window rather than closing it: the process owns the copied
+    /// content, so quitting would take it off the clipboard again.
+    fn hide(&self) {
+        self.window.set_visible(false);
+    }
+
+    fn paste_after(&self, delay: Duration, paster: AutoPaster) {
+        let toast_overlay = self.toast_overlay.clone();
+
+        glib::timeout_add_local_once(delay, move || {
+            glib::MainContext::default().spawn_local(async move {
+                let message = match gio::spawn_blocking(move || paster.paste()).await {
+                    Ok(Ok(())) => return,
+                    Ok(Err(err)) => err,
+                    Err(_) => "Auto-paste failed".to_string(),
+                };
+                toast_overlay.add_toast(adw::Toast::new(&message));
+            });
+        });
+    }
+}
+
+/// Hides the window and, with auto-paste on, pastes into the one focused
+/// before. The compositor needs a moment to give the focus back to it.
+fn after_copy(host: &impl PasteHost, config: &AppConfig, keyboard: Arc<dyn IVirtualKeyboard>) {
+    if !config.close_after_copy && !config.auto_paste {
+        return;
+    }
+
+    host.hide();
+
+    if config.auto_paste {
+        host.paste_after(
+            Duration::from_millis(config.paste_delay_ms),
+            AutoPaster::new(keyboard, config.paste_keys),
+        );
+    }
+}
+
+fn save(config: &AppConfig) {
+    if let Err(err) = ConfigService::new().save(config) {
+        eprintln!("Error saving config: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct FakeKeyboard {
+        sent: Mutex<Vec<PasteKeys>>,
+    }
+
+    impl IVirtualKeyboard for FakeKeyboard {
+        fn send(&self, keys: PasteKeys) -> Result<(), String> {
+            self.sent.lock().unwrap().push(keys);
+            Ok(())
+        }
+    }
+
+    /// Records the steps and pastes right away.
+    #[derive(Default)]
+    struct FakeHost {
+        steps: RefCell<Vec<String>>,
+    }
+
+    impl PasteHost for FakeHost {
+        fn hide(&self) {
+            self.steps.borrow_mut().push("hide".to_string());
+        }
+
+        fn paste_after(&self, delay: Duration, paster: AutoPaster) {
+            self.steps
+                .borrow_mut()
+                .push(format!("wait {}ms", delay.as_millis()));
+            paster.paste().unwrap();
+            self.steps.borrow_mut().push("paste".to_string());
+        }
+    }
+
+    fn run(config: &AppConfig) -> (Vec<String>, Vec<PasteKeys>) {
+        let host = FakeHost::default();
+        let keyboard = Arc::new(FakeKeyboard::default());
+        after_copy(&host, config, keyboard.clone());
+
+        let sent = keyboard.sent.lock().unwrap().clone();
+        (host.steps.into_inner(), sent)
+    }
+
+    #[test]
+    fn hides_then_pastes_after_the_delay() {
+        let mut config = AppConfig {
+            close_after_copy: false,
+            auto_paste: false,
+            paste_keys: PasteKeys::ShiftInsert,
+            paste_delay_ms: 150,
+            ..AppConfig::default()
+        };
+        assert_eq!(run(&config), (vec![], vec![]));
+
+        config.close_after_copy = true;
+        assert_eq!(run(&config), (vec!["hide".to_string()], vec![]));
+
+        config.auto_paste = true;
+        assert_eq!(
+            run(&config),
+            (
+                vec![
+                    "hide".to_string(),
+                    "wait 150ms".to_string(),
+                    "paste".to_string()
+                ],
+                vec![PasteKeys::ShiftInsert]
+            )
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use gtk::{gdk, gio, glib, prelude::*};
+use libadwaita as adw;
+
+use crate::service::{
+    config_service::AppConfig,
+    popup_placement::{self, Area, PopupAnchor},
+};
+
+/// Style class of the window in popup mode; rows get compact there.
+pub const POPUP_CLASS: &str = "popup";
+
+thread_local! {
+    /// Dialogs open per window. Windows leave the map once their count is
+    /// back to zero, so it keeps none of them alive.
+    static OPEN_DIALOGS: RefCell<HashMap<gtk::Window, usize>> = RefCell::default();
+}
+
+/// Turns `window` into a popup: a layer surface on compositors supporting
+/// layer-shell, a small regular window elsewhere. Both are hidden when they
+/// lose the focus, unless to a dialog they opened.
+pub fn setup_popup(window: &adw::ApplicationWindow, config: &AppConfig) {
+    window.add_css_class(POPUP_CLASS);
+    window.set_default_size(config.popup_width, config.popup_height);
+    // The main window's fixed size and modality do not fit a popup.
+    window.set_resizable(true);
+    window.set_modal(false);
+
+    if layer_shell::init(window) {
+        let anchor = config.popup_anchor;
+        let size = (config.popup_width, config.popup_height);
+        // Placed again every time the popup is shown, as the pointer moves.
+        window.connect_show(move |window| {
+            glib::MainContext::default().spawn_local(glib::clone!(
+                #[weak]
+                window,
+                async move {
+                    // Asking the compositor runs a process; the popup moves
+                    // into place once it answered.
+                    let pointer = match anchor {
+                        PopupAnchor::Pointer => {
+                            gio::spawn_blocking(popup_placement::pointer_position)
+                                .await
+                                .ok()
+                                .flatten()
+                        }
+                        PopupAnchor::Center => None,
+                    };
+                    place(&window, pointer, size);
+                }
+            ));
+        });
+    }
+
+    // Hidden rather than closed, so that copied content stays on the
+    // clipboard.
+    window.connect_is_active_notify(|window| {
+        if !window.is_active() && window.is_visible() && open_dialogs(window.upcast_ref()) == 0 {
+            window.set_visible(false);
+        }
+    });
+}
+
+/// Awaits `dialog`, shown for `window`, without the popup hiding while the
+/// dialog has the focus. File dialogs run in another process, so the
+/// window cannot tell them apart from any other focus loss.
+pub async fn keep_open_during<T>(
+    window: &impl IsA<gtk::Window>,
+    dialog: impl Future<Output = T>,
+) -> T {
+    let window = window.as_ref().clone();
+    dialog_opened(&window);
+    let result = dialog.await;
+    dialog_closed(&window);
+    result
+}
+
+/// Marks a dialog of `window` as open, see [`keep_open_during`]. Every call
+/// needs a matching [`dialog_closed`].
+pub fn dialog_opened(window: &impl IsA<gtk::Window>) {
+    set_open_dialogs(window.as_ref(), open_dialogs(window.as_ref()) + 1);
+}
+
+pub fn dialog_closed(window: &impl IsA<gtk::Window>) {
+    set_open_dialogs(
+        window.as_ref(),
+        open_dialogs(window.as_ref()).saturating_sub(1),
+    );
+}
+
+fn open_dialogs(window: &gtk::Window) -> usize {
+    OPEN_DIALOGS.with_borrow(|open| open.get(window).copied().unwrap_or(0))
+}
+
+fn set_open_dialogs(window: &gtk::Window, count: usize) {
+    OPEN_DIALOGS.with_borrow_mut(|open| {
+        if count == 0 {
+            open.remove(window);
+        } else {
+            open.insert(window.clone(), count);
+        }
+    });
+}
+
+fn place(window: &adw::ApplicationWindow, pointer: Option<(i32, i32)>, size: (i32, i32)) {
+    let Some((pointer, monitor)) = pointer.and_then(|pointer| {
+        monitor_at(&WidgetExt::display(window), pointer).map(|monitor| (pointer, monitor))
+    }) else {
+        // Without anchors the compositor centers the surface.
+        layer_shell::anchor_top_left(window, None, None);
+        return;
+    };
+
+    let offset = popup_placement::offset_near(pointer, area(&monitor), size);
+    layer_shell::anchor_top_left(window, Some(&monitor), Some(offset));
+}
+
+fn monitor_at(display: &gdk::Display, pointer: (i32, i32)) -> Option<gdk::Monitor> {
+    let monitors = display.monitors();
+
+    (0..monitors.n_items())
+        .filter_map(|index| monitors.item(index)?.downcast::<gdk::Monitor>().ok())
+        .find(|monitor| area(monitor).contains(pointer))
+}
+
+fn area(monitor: &gdk::Monitor) -> Area {
+    let geometry = monitor.geometry();
+    Area {
+        x: geometry.x(),
+        y: geometry.y(),
+        width: geometry.width(),
+        height: geometry.height(),
+    }
+}
+
+/// Layer surfaces through gtk4-layer-shell, with the `layer-shell` feature.
+#[cfg(feature = "layer-shell")]
+mod layer_shell {
+    use gtk::gdk;
+    use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
+
+    const EDGES: [Edge; 4] = [Edge::Left, Edge::Right, Edge::Top, Edge::Bottom];
+
+    /// Makes `window` an overlay layer surface with exclusive keyboard
+    /// focus. Returns false when the compositor lacks layer-shell.
+    pub fn init(window: &impl gtk::prelude::IsA<gtk::Window>) -> bool {
+        if !gtk4_layer_shell::is_supported() {
+            return false;
+        }
+
+        window.init_layer_shell();
+        window.set_namespace(Some("clipboard-manager"));
+        window.set_layer(Layer::Overlay);
+        window.set_keyboard_mode(KeyboardMode::Exclusive);
+        true
+    }
+
+    /// Anchors the surface at `offset` from the top left corner of
+    /// `monitor`, or removes all anchors for `None`.
+    pub fn anchor_top_left(
+        window: &impl gtk::prelude::IsA<gtk::Window>,
+        monitor: Option<&gdk::Monitor>,
+        offset: Option<(i32, i32)>,
+    ) {
+        window.set_monitor(monitor);
+        for edge in EDGES {
+            let anchored = offset.is_some() && matches!(edge, Edge::Left | Edge::Top);
+            window.set_anchor(edge, anchored);
+        }
+        if let Some((left, top)) = offset {
+            window.set_margin(Edge::Left, left);
+            window.set_margin(Edge::Top, top);
+        }
+    }
+}
+
+/// Without the `layer-shell` feature the popup is a regular window.
+#[cfg(not(feature = "layer-shell"))]
+mod layer_shell {
+    use gtk::gdk;
+
+    pub fn init(_window: &impl gtk::prelude::IsA<gtk::Window>) -> bool {
+        false
+    }
+
+    pub fn anchor_top_left(
+        _window: &impl gtk::prelude::IsA<gtk::Window>,
+        _monitor: Option<&gdk::Monitor>,
+        _offset: Option<(i32, i32)>,
+    ) {
+    }
+}
@@ -0,0 +1,249 @@
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use chrono::Local;
+use gtk::{gio, glib, prelude::*};
+use libadwaita as adw;
+
+use crate::service::{
+    cliboard_monitor::ClipboardMonitor,
+    cliphist_provider::CliphistProvider,
+    config_service::{AppConfig, ConfigService},
+    recording_state::RecordingState,
+};
+
+const PAUSED_CLASS: &str = "recording-paused";
+/// How often, and how long at most, the history is checked for what resumed
+/// cliphist watchers store from the selection changes they missed.
+const REPLAY_POLL: Duration = Duration::from_millis(100);
+const REPLAY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Registers the pause actions on the application so they can also be
+/// triggered over D-Bus (`org.gtk.Actions`) and from the command line:
+/// `app.recording-paused` toggles an open-ended pause,
+/// `app.pause-recording(u)` pauses for some minutes (0 until resumed) and
+/// `app.resume-recording` records again.
+pub fn setup_recording_actions(
+    app: &adw::Application,
+    header_bar: &adw::HeaderBar,
+    title: &adw::WindowTitle,
+    clipboard_monitor: ClipboardMonitor,
+    config: Rc<RefCell<AppConfig>>,
+    initial: RecordingState,
+) {
+    let action_paused = gio::SimpleAction::new_stateful("recording-paused", None, &false.into());
+
+    let apply: Rc<dyn Fn(RecordingState)> = Rc::new(glib::clone!(
+        #[weak]
+        header_bar,
+        #[weak]
+        title,
+        #[weak]
+        action_paused,
+        #[strong]
+        clipboard_monitor,
+        #[strong]
+        config,
+        move |state| {
+            let was_paused = is_active(&action_paused);
+            let paused = state != RecordingState::Recording;
+
+            clipboard_monitor.set_recording_state(state);
+            action_paused.set_state(&paused.into());
+            title.set_subtitle(&state.description().unwrap_or_default());
+
+            if paused {
+                header_bar.add_css_class(PAUSED_CLASS);
+            } else {
+                header_bar.remove_css_class(PAUSED_CLASS);
+            }
+
+            if was_paused != paused && config.borrow().pause_cliphist_watcher {
+                pause_cliphist_watchers(paused);
+            }
+
+            if let RecordingState::Paused { until: Some(until) } = state {
+                let seconds = until.signed_duration_since(Local::now()).num_seconds();
+                let seconds = u32::try_from(seconds.max(1)).unwrap_or(u32::MAX);
+                schedule_resume(&action_paused, &clipboard_monitor, seconds);
+            }
+        }
+    ));
+
+    action_paused.connect_change_state(glib::clone!(
+        #[strong]
+        apply,
+        move |_, value| {
+            let paused = value.and_then(|value| value.get()).unwrap_or(false);
+            apply(if paused {
+                RecordingState::paused_for(0, Local::now())
+            } else {
+                RecordingState::Recording
+            });
+        }
+    ));
+    app.add_action(&action_paused);
+
+    let action_pause = gio::SimpleAction::new("pause-recording", Some(glib::VariantTy::UINT32));
+    action_pause.connect_activate(glib::clone!(
+        #[strong]
+        apply,
+        move |_, parameter| {
+            let minutes = parameter.and_then(|value| value.get()).unwrap_or(0);
+            apply(RecordingState::paused_for(minutes, Local::now()));
+        }
+    ));
+    app.add_action(&action_pause);
+
+    let action_resume = gio::SimpleAction::new("resume-recording", None);
+    action_resume.connect_activate(glib::clone!(
+        #[strong]
+        apply,
+        move |_, _| apply(RecordingState::Recording)
+    ));
+    app.add_action(&action_resume);
+
+    let action_cliphist = gio::SimpleAction::new_stateful(
+        "pause-cliphist-watcher",
+        None,
+        &config.borrow().pause_cliphist_watcher.into(),
+    );
+    action_cliphist.connect_activate(glib::clone!(
+        #[weak]
+        action_paused,
+        #[strong]
+        config,
+        move |action, _| {
+            let enabled = !is_active(action);
+            action.set_state(&enabled.into());
+
+            if is_active(&action_paused) {
+                pause_cliphist_watchers(enabled);
+            }
+
+            config.borrow_mut().pause_cliphist_watcher = enabled;
+            if let Err(err) = ConfigService::new().save(&config.borrow()) {
+                eprintln!("Error saving config: {err}");
+            }
+        }
+    ));
+    app.add_action(&action_cliphist);
+
+    // Stopped watchers would otherwise stay stopped after the app exits.
+    app.connect_shutdown(glib::clone!(
+        #[weak]
+        action_paused,
+        #[strong]
+        config,
+        move |_| {
+            if is_active(&action_paused) && config.borrow().pause_cliphist_watcher {
+                pause_cliphist_watchers(false);
+            }
+        }
+    ));
+
+    // Watchers stopped by a run that crashed are continued, and stopped
+    // again below if this run starts paused.
+    if CliphistProvider.watchers_left_paused() {
+        pause_cliphist_watchers(false);
+    }
+
+    if initial != RecordingState::Recording {
+        apply(initial);
+    }
+}
+
+fn schedule_resume(
+    action_paused: &gio::SimpleAction,
+    clipboard_monitor: &ClipboardMonitor,
+    seconds: u32,
+) {
+    glib::timeout_add_seconds_local_once(
+        seconds,
+        glib::clone!(
+            #[weak]
+            action_paused,
+            #[strong]
+            clipboard_monitor,
+            move || {
+                // Only end the pause that expired, not one started since.
+                if is_active(&action_paused)
+                    && clipboard_monitor.recording_state() == RecordingState::Recording
+                {
+                    action_paused.change_state(&false.into());
+                }
+            }
+        ),
+    );
+}
+
+fn pause_cliphist_watchers(paused: bool) {
+    if paused {
+        if let Err(err) = CliphistProvider.pause_watchers() {
+            eprintln!("Error pausing cliphist watcher: {err}");
+        }
+        return;
+    }
+
+    match CliphistProvider.resume_watchers() {
+        Ok(Some(newest)) => drop_replayed_entries(newest),
+        Ok(None) => {}
+        Err(err) => eprintln!("Error resuming cliphist watcher: {err}"),
+    }
+}
+
+/// Removes what resumed watchers stored from the selection changes they
+/// missed while paused. Not done when the application quits.
+fn drop_replayed_entries(newest: u64) {
+    glib::MainContext::default().spawn_local(async move {
+        let replayed = replayed_ids(newest).await;
+        if replayed.is_empty() {
+            return;
+        }
+
+        let deleted = gio::spawn_blocking(move || {
+            replayed
+                .iter()
+                .try_for_each(|id| CliphistProvider.delete(id))
+        })
+        .await;
+        if let Ok(Err(err)) = deleted {
+            eprintln!("Error dropping entries stored while paused: {err}");
+        }
+    });
+}
+
+/// The watchers store their replays as soon as they continue, before the
+/// user can copy anything new. Those are the entries found by the first
+/// check that finds any, plus what other watchers add until the next one.
+/// Later entries are left alone.
+async fn replayed_ids(newest: u64) -> Vec<String> {
+    let mut found = Vec::new();
+    let mut waited = Duration::ZERO;
+
+    while waited < REPLAY_TIMEOUT {
+        glib::timeout_future(REPLAY_POLL).await;
+        waited += REPLAY_POLL;
+
+        let ids = match gio::spawn_blocking(move || CliphistProvider.ids_newer_than(newest)).await {
+            Ok(Ok(ids)) => ids,
+            Ok(Err(err)) => {
+                eprintln!("Error listing entries stored while paused: {err}");
+                return Vec::new();
+            }
+            Err(_) => return Vec::new(),
+        };
+        if !found.is_empty() {
+            return ids;
+        }
+        found = ids;
+    }
+
+    found
+}
+
+fn is_active(action: &gio::SimpleAction) -> bool {
+    action
+        .state()
+        .and_then(|state| state.get())
+        .unwrap_or(false)
+}
@@ -1,12 +1,139 @@
-use gtk;
+use std::{cell::RefCell, collections::BTreeSet, rc::Rc};
 
-pub fn build() -> (gtk::SearchBar, gtk::SearchEntry) {
+use gtk::{glib, prelude::*};
+
+use crate::service::{cliboard_history::ClipboardEntry, content_filter::ContentFilter};
+
+pub fn build(active: &BTreeSet<ContentFilter>) -> (gtk::SearchBar, gtk::SearchEntry, FilterChips) {
     let search_enty = gtk::SearchEntry::builder()
         .hexpand(true)
         .placeholder_text("Seach...")
         .build();
 
-    let search_bar = gtk::SearchBar::builder().child(&search_enty).build();
+    let chips = FilterChips::new(active);
+
+    let content = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(6)
+        .build();
+    content.append(&search_enty);
+    // Scrolls sideways in the narrow popup window.
+    content.append(
+        &gtk::ScrolledWindow::builder()
+            .child(&chips.container)
+            .vscrollbar_policy(gtk::PolicyType::Never)
+            .build(),
+    );
+
+    let search_bar = gtk::SearchBar::builder().child(&content).build();
+
+    (search_bar, search_enty, chips)
+}
+
+type ChangedCallback = Box<dyn Fn(&BTreeSet<ContentFilter>)>;
+
+/// Toggle buttons narrowing the list to kinds of entries. "All" is active
+/// exactly when no kind is chosen.
+#[derive(Clone)]
+pub struct FilterChips {
+    container: gtk::Box,
+    all: gtk::ToggleButton,
+    chips: Rc<Vec<(ContentFilter, gtk::ToggleButton)>>,
+    active: Rc<RefCell<BTreeSet<ContentFilter>>>,
+    on_changed: Rc<RefCell<Vec<ChangedCallback>>>,
+}
+
+impl FilterChips {
+    fn new(active: &BTreeSet<ContentFilter>) -> Self {
+        let container = gtk::Box::builder().spacing(6).build();
+        let all = chip("All", active.is_empty());
+        container.append(&all);
+
+        let chips: Vec<_> = ContentFilter::ALL
+            .into_iter()
+            .map(|filter| {
+                let button = chip(filter.label(), active.contains(&filter));
+                container.append(&button);
+                (filter, button)
+            })
+            .collect();
+
+        let chips = Self {
+            container,
+            all,
+            chips: Rc::new(chips),
+            active: Rc::new(RefCell::new(active.clone())),
+            on_changed: Rc::default(),
+        };
+        chips.connect_toggles();
+        chips
+    }
+
+    fn connect_toggles(&self) {
+        self.all.connect_clicked(glib::clone!(
+            #[strong(rename_to = chips)]
+            self,
+            move |_| chips.set_active(BTreeSet::new())
+        ));
+
+        for (filter, button) in self.chips.iter() {
+            let filter = *filter;
+            button.connect_clicked(glib::clone!(
+                #[strong(rename_to = chips)]
+                self,
+                move |button| {
+                    let mut active = chips.active();
+                    if button.is_active() {
+                        active.insert(filter);
+                    } else {
+                        active.remove(&filter);
+                    }
+                    chips.set_active(active);
+                }
+            ));
+        }
+    }
+
+    pub fn active(&self) -> BTreeSet<ContentFilter> {
+        self.active.borrow().clone()
+    }
+
+    fn set_active(&self, active: BTreeSet<ContentFilter>) {
+        // Clicking the active "All" chip would otherwise turn it off.
+        self.all.set_active(active.is_empty());
+        for (filter, button) in self.chips.iter() {
+            button.set_active(active.contains(filter));
+        }
+
+        if *self.active.borrow() == active {
+            return;
+        }
+        *self.active.borrow_mut() = active;
+
+        let active = self.active();
+        for callback in self.on_changed.borrow().iter() {
+            callback(&active);
+        }
+    }
+
+    /// Shows how many of `entries` each chip matches.
+    pub fn update_counts(&self, entries: &[ClipboardEntry]) {
+        self.all.set_label(&format!("All {}", entries.len()));
+        for ((filter, button), count) in self.chips.iter().zip(ContentFilter::counts(entries)) {
+            button.set_label(&format!("{} {count}", filter.label()));
+        }
+    }
+
+    pub fn connect_changed(&self, callback: impl Fn(&BTreeSet<ContentFilter>) + 'static) {
+        self.on_changed.borrow_mut().push(Box::new(callback));
+    }
+}
 
-    (search_bar, search_enty)
+fn chip(label: &str, active: bool) -> gtk::ToggleButton {
+    gtk::ToggleButton::builder()
+        .label(label)
+        .active(active)
+        .can_focus(false)
+        .css_classes(["pill", "filter-chip"])
+        .build()
 }
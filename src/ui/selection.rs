@@ -0,0 +1,98 @@
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use gtk::{gio, glib, prelude::*};
+use libadwaita as adw;
+
+use crate::service::{
+    cliboard_monitor::{ClipboardMonitor, PrimaryTracking},
+    config_service::{AppConfig, ConfigService},
+    selection_sync::{SelectionSync, SyncDirection},
+};
+
+/// Radio items for the sync direction.
+pub fn build_sync_menu() -> gio::Menu {
+    let menu = gio::Menu::new();
+
+    for direction in SyncDirection::ALL {
+        menu.append(
+            Some(direction.label()),
+            Some(&format!("win.selection-sync('{}')", direction.name())),
+        );
+    }
+
+    menu
+}
+
+/// Applies the PRIMARY selection and sync settings and adds the actions
+/// changing them.
+pub fn setup_selection_actions(
+    window: &adw::ApplicationWindow,
+    monitor: ClipboardMonitor,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    monitor.set_primary_tracking(primary_tracking(&config.borrow()));
+    monitor.set_selection_sync(selection_sync(&config.borrow()));
+
+    let action_track = gio::SimpleAction::new_stateful(
+        "track-primary",
+        None,
+        &config.borrow().track_primary.into(),
+    );
+    action_track.connect_activate(glib::clone!(
+        #[strong]
+        config,
+        #[strong]
+        monitor,
+        move |action, _| {
+            let enabled = !action
+                .state()
+                .and_then(|state| state.get())
+                .unwrap_or(false);
+            action.set_state(&enabled.into());
+
+            config.borrow_mut().track_primary = enabled;
+            monitor.set_primary_tracking(primary_tracking(&config.borrow()));
+            if let Err(err) = ConfigService::new().save(&config.borrow()) {
+                eprintln!("Error saving config: {err}");
+            }
+        }
+    ));
+    window.add_action(&action_track);
+
+    let action_sync = gio::SimpleAction::new_stateful(
+        "selection-sync",
+        Some(glib::VariantTy::STRING),
+        &config.borrow().selection_sync.name().to_variant(),
+    );
+    action_sync.connect_activate(glib::clone!(
+        #[strong]
+        config,
+        move |action, parameter| {
+            let Some(direction) = parameter
+                .and_then(|parameter| parameter.str())
+                .and_then(SyncDirection::from_name)
+            else {
+                return;
+            };
+            action.set_state(&direction.name().to_variant());
+
+            config.borrow_mut().selection_sync = direction;
+            monitor.set_selection_sync(selection_sync(&config.borrow()));
+            if let Err(err) = ConfigService::new().save(&config.borrow()) {
+                eprintln!("Error saving config: {err}");
+            }
+        }
+    ));
+    window.add_action(&action_sync);
+}
+
+fn selection_sync(config: &AppConfig) -> SelectionSync {
+    SelectionSync::new(config.selection_sync, config.sync_min_length)
+}
+
+fn primary_tracking(config: &AppConfig) -> PrimaryTracking {
+    PrimaryTracking {
+        enabled: config.track_primary,
+        debounce: Duration::from_millis(config.primary_debounce_ms),
+    }
+}
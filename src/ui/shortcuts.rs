@@ -1,7 +1,14 @@
+use std::{cell::RefCell, rc::Rc};
+
 use gtk::{gio, glib, prelude::*};
 use libadwaita as adw;
 
-pub fn setup_shortcuts_action(window: &adw::ApplicationWindow) {
+use crate::service::keybindings::{BindingScope, KeybindingRegistry};
+
+pub fn setup_shortcuts_action(
+    window: &adw::ApplicationWindow,
+    registry: Rc<RefCell<KeybindingRegistry>>,
+) {
     let action_shortcuts = gio::SimpleAction::new("show-shortcuts", None);
     action_shortcuts.connect_activate(glib::clone!(
         #[weak]
@@ -17,21 +24,25 @@ pub fn setup_shortcuts_action(window: &adw::ApplicationWindow) {
                 .title("General")
                 .build();
 
-            let group = gtk::ShortcutsGroup::builder().title("Application").build();
+            // Built from the registry so it always shows the bindings in use.
+            for scope in [BindingScope::Application, BindingScope::List] {
+                let group = gtk::ShortcutsGroup::builder().title(scope.title()).build();
 
-            let shortcut_search = gtk::ShortcutsShortcut::builder()
-                .title("Search")
-                .accelerator("<Control>f")
-                .build();
+                for (spec, accels) in registry.borrow().bindings() {
+                    if spec.scope != scope || accels.is_empty() {
+                        continue;
+                    }
 
-            let shortcut_quit = gtk::ShortcutsShortcut::builder()
-                .title("Quit")
-                .accelerator("<Control>q")
-                .build();
+                    let shortcut = gtk::ShortcutsShortcut::builder()
+                        .title(spec.title)
+                        .accelerator(accels.join(" "))
+                        .build();
+                    group.append(&shortcut);
+                }
+
+                section.append(&group);
+            }
 
-            group.append(&shortcut_search);
-            group.append(&shortcut_quit);
-            section.append(&group);
             shortcuts.set_child(Some(&section));
 
             shortcuts.present();
@@ -0,0 +1,59 @@
+use std::{cell::RefCell, rc::Rc};
+
+use gtk::{gio, glib, prelude::*};
+use libadwaita as adw;
+
+use crate::service::config_service::{AppConfig, ConfigService};
+
+pub fn build_menu(config: &AppConfig) -> gio::Menu {
+    let menu = gio::Menu::new();
+
+    for (index, provider) in config.providers.iter().enumerate() {
+        menu.append(
+            Some(&provider.label()),
+            Some(&format!("win.{}", action_name(index))),
+        );
+    }
+
+    menu
+}
+
+pub fn setup_source_actions(
+    window: &adw::ApplicationWindow,
+    config: Rc<RefCell<AppConfig>>,
+    reload: Rc<dyn Fn()>,
+) {
+    let provider_count = config.borrow().providers.len();
+
+    for index in 0..provider_count {
+        let enabled = config.borrow().providers[index].enabled;
+        let action = gio::SimpleAction::new_stateful(&action_name(index), None, &enabled.into());
+
+        action.connect_activate(glib::clone!(
+            #[strong]
+            config,
+            #[strong]
+            reload,
+            move |action, _| {
+                let enabled = !action
+                    .state()
+                    .and_then(|state| state.get())
+                    .unwrap_or(false);
+                action.set_state(&enabled.into());
+
+                config.borrow_mut().providers[index].enabled = enabled;
+                if let Err(err) = ConfigService::new().save(&config.borrow()) {
+                    eprintln!("Error saving config: {err}");
+                }
+
+                reload();
+            }
+        ));
+
+        window.add_action(&action);
+    }
+}
+
+fn action_name(index: usize) -> String {
+    format!("toggle-source-{index}")
+}
@@ -0,0 +1,159 @@
+use std::rc::Rc;
+
+use gtk::{gio, glib, prelude::*};
+use libadwaita as adw;
+
+use crate::{
+    service::{
+        cliboard_history::IClipboardHistory,
+        cliboard_monitor::{ClipboardMonitor, IClipboardMonitor},
+        history_transfer::{self, ExportFilter, ExportFormat, ExportOptions, ImageMode},
+    },
+    ui::popup,
+};
+
+pub fn setup_transfer_actions(
+    window: &adw::ApplicationWindow,
+    clipboard_monitor: ClipboardMonitor,
+    search_entry: &gtk::SearchEntry,
+    toast_overlay: &adw::ToastOverlay,
+    refresh: Rc<dyn Fn()>,
+) {
+    let action_export = gio::SimpleAction::new("export-history", None);
+    action_export.connect_activate(glib::clone!(
+        #[weak]
+        window,
+        #[weak]
+        search_entry,
+        #[weak]
+        toast_overlay,
+        #[strong]
+        clipboard_monitor,
+        move |_, _| {
+            let dialog = gtk::FileDialog::builder()
+                .title("Export Clipboard History")
+                .initial_name("clipboard-history.json")
+                .filters(&file_filters())
+                .modal(true)
+                .build();
+
+            let entries = clipboard_monitor.history().borrow().entries().to_vec();
+            let query = search_entry.text().to_string();
+
+            glib::MainContext::default().spawn_local(glib::clone!(
+                #[weak]
+                window,
+                #[weak]
+                toast_overlay,
+                async move {
+                    let Ok(file) =
+                        popup::keep_open_during(&window, dialog.save_future(Some(&window))).await
+                    else {
+                        return;
+                    };
+                    let Some(path) = file.path() else {
+                        return;
+                    };
+
+                    let options = ExportOptions {
+                        format: ExportFormat::from_path(&path),
+                        filter: ExportFilter {
+                            query: Some(query).filter(|query| !query.is_empty()),
+                            pinned_only: false,
+                        },
+                        images: ImageMode::Embedded,
+                    };
+
+                    let result = gio::spawn_blocking(move || {
+                        history_transfer::export_to_file(&entries, &path, &options)
+                    })
+                    .await;
+
+                    let message = match result {
+                        Ok(Ok(count)) => format!("Exported {count} entries"),
+                        Ok(Err(err)) => format!("Export failed: {err}"),
+                        Err(_) => "Export failed".to_string(),
+                    };
+                    toast_overlay.add_toast(adw::Toast::new(&message));
+                }
+            ));
+        }
+    ));
+    window.add_action(&action_export);
+
+    let action_import = gio::SimpleAction::new("import-history", None);
+    action_import.connect_activate(glib::clone!(
+        #[weak]
+        window,
+        #[weak]
+        toast_overlay,
+        #[strong]
+        clipboard_monitor,
+        #[strong]
+        refresh,
+        move |_, _| {
+            let dialog = gtk::FileDialog::builder()
+                .title("Import Clipboard History")
+                .filters(&file_filters())
+                .modal(true)
+                .build();
+
+            glib::MainContext::default().spawn_local(glib::clone!(
+                #[weak]
+                window,
+                #[weak]
+                toast_overlay,
+                #[strong]
+                clipboard_monitor,
+                #[strong]
+                refresh,
+                async move {
+                    let Ok(file) =
+                        popup::keep_open_during(&window, dialog.open_future(Some(&window))).await
+                    else {
+                        return;
+                    };
+                    let Some(path) = file.path() else {
+                        return;
+                    };
+
+                    let result =
+                        gio::spawn_blocking(move || history_transfer::import_history(&path)).await;
+
+                    let message = match result {
+                        Ok(Ok(entries)) => {
+                            let added = clipboard_monitor
+                                .history()
+                                .borrow_mut()
+                                .merge_entries(entries);
+                            clipboard_monitor.persist();
+                            refresh();
+                            format!("Imported {added} new entries")
+                        }
+                        Ok(Err(err)) => format!("Import failed: {err}"),
+                        Err(_) => "Import failed".to_string(),
+                    };
+                    toast_overlay.add_toast(adw::Toast::new(&message));
+                }
+            ));
+        }
+    ));
+    window.add_action(&action_import);
+}
+
+fn file_filters() -> gio::ListStore {
+    let filters = gio::ListStore::new::<gtk::FileFilter>();
+
+    for (name, pattern) in [
+        ("JSON", "*.json"),
+        ("Plain text", "*.txt"),
+        ("CSV", "*.csv"),
+    ] {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(name));
+        filter.add_pattern(pattern);
+        filters.append(&filter);
+    }
+
+    filters
+}
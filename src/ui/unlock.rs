@@ -0,0 +1,275 @@
+use std::{cell::RefCell, rc::Rc, time::Instant};
+
+use gtk::{gio, glib, prelude::*};
+use libadwaita::{self as adw, prelude::*};
+
+use crate::service::{
+    cliboard_monitor::ClipboardMonitor,
+    config_service::{AppConfig, StoreEncryption},
+    native_store_provider::{NativeStoreProvider, StoreProtection},
+    secret_keyring::SecretServiceKeyring,
+};
+
+pub const HISTORY_PAGE: &str = "history";
+pub const LOCKED_PAGE: &str = "locked";
+const AUTO_LOCK_CHECK_SECONDS: u32 = 30;
+/// Window actions that reveal or change entries, off while locked.
+const ENTRY_ACTIONS: [&str; 6] = [
+    "copy-plain-text",
+    "copy-to",
+    "copy-nth",
+    "remove-selected",
+    "toggle-pin",
+    "view-image",
+];
+
+pub fn build_lock_page() -> (adw::StatusPage, gtk::Button) {
+    let unlock_button = gtk::Button::builder()
+        .label("Unlock")
+        .halign(gtk::Align::Center)
+        .css_classes(["pill", "suggested-action"])
+        .build();
+
+    let page = adw::StatusPage::builder()
+        .icon_name("system-lock-screen-symbolic")
+        .title("History Locked")
+        .description("The clipboard history is encrypted")
+        .child(&unlock_button)
+        .build();
+
+    (page, unlock_button)
+}
+
+/// Asks for the store key until the store opens or the user gives up, in
+/// which case the store stays locked.
+pub async fn request_protection(
+    window: &adw::ApplicationWindow,
+    encryption: StoreEncryption,
+    store: NativeStoreProvider,
+) -> StoreProtection {
+    match encryption {
+        StoreEncryption::None => StoreProtection::Plain,
+        StoreEncryption::Keyring => {
+            match gio::spawn_blocking(move || store.unlock_with_keyring(&SecretServiceKeyring))
+                .await
+            {
+                Ok(Ok(cipher)) => StoreProtection::Unlocked(cipher),
+                Ok(Err(err)) => {
+                    eprintln!("Error unlocking history: {err}");
+                    StoreProtection::Locked
+                }
+                Err(_) => StoreProtection::Locked,
+            }
+        }
+        StoreEncryption::Passphrase => {
+            let mut error = None;
+            // Without an encrypted file the passphrase is set, not checked.
+            let store_clone = store.clone();
+            let mut new = matches!(
+                gio::spawn_blocking(move || store_clone.is_encrypted()).await,
+                Ok(Ok(false))
+            );
+
+            loop {
+                let Some(passphrase) = prompt_passphrase(window, error.as_deref(), new).await
+                else {
+                    return StoreProtection::Locked;
+                };
+
+                let store = store.clone();
+                match gio::spawn_blocking(move || store.unlock_with_passphrase(&passphrase)).await {
+                    Ok(Ok(cipher)) => return StoreProtection::Unlocked(cipher),
+                    Ok(Err(err)) => error = Some(err),
+                    Err(_) => error = Some("Unlocking failed".to_string()),
+                }
+                new = false;
+            }
+        }
+    }
+}
+
+/// Asks for the passphrase, twice when a `new` one is being set so a typo
+/// does not lock the history away.
+async fn prompt_passphrase(
+    window: &adw::ApplicationWindow,
+    error: Option<&str>,
+    new: bool,
+) -> Option<String> {
+    let entry = gtk::PasswordEntry::builder()
+        .show_peek_icon(true)
+        .activates_default(true)
+        .build();
+    let confirm = gtk::PasswordEntry::builder()
+        .show_peek_icon(true)
+        .activates_default(true)
+        .placeholder_text("Repeat passphrase")
+        .visible(new)
+        .build();
+    let entries = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(6)
+        .build();
+    entries.append(&entry);
+    entries.append(&confirm);
+
+    let (heading, body) = if new {
+        (
+            "Protect Clipboard History",
+            "Choose a passphrase to encrypt the history",
+        )
+    } else {
+        (
+            "Unlock Clipboard History",
+            "Enter the passphrase protecting the history",
+        )
+    };
+    let dialog = adw::AlertDialog::new(Some(heading), Some(error.unwrap_or(body)));
+    dialog.set_extra_child(Some(&entries));
+    dialog.add_responses(&[("cancel", "Cancel"), ("unlock", "Unlock")]);
+    if new {
+        let update = glib::clone!(
+            #[weak]
+            dialog,
+            #[weak]
+            entry,
+            #[weak]
+            confirm,
+            move || {
+                let matches = !entry.text().is_empty() && entry.text() == confirm.text();
+                dialog.set_response_enabled("unlock", matches);
+            }
+        );
+        update();
+        entry.connect_changed(glib::clone!(
+            #[strong]
+            update,
+            move |_| update()
+        ));
+        confirm.connect_changed(move |_| update());
+    }
+    dialog.set_response_appearance("unlock", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("unlock"));
+    dialog.set_close_response("cancel");
+
+    let response = dialog.choose_future(Some(window)).await;
+    (response == "unlock").then(|| entry.text().to_string())
+}
+
+/// Shows the lock page after the window stayed inactive for the configured
+/// time, dropping the store key until the next unlock.
+pub fn setup_auto_lock(
+    window: &adw::ApplicationWindow,
+    stack: &gtk::Stack,
+    clipboard_monitor: ClipboardMonitor,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let inactive_since = Rc::new(RefCell::new(Instant::now()));
+
+    window.connect_is_active_notify(glib::clone!(
+        #[strong]
+        inactive_since,
+        move |window| {
+            if !window.is_active() {
+                *inactive_since.borrow_mut() = Instant::now();
+            }
+        }
+    ));
+
+    glib::timeout_add_seconds_local(
+        AUTO_LOCK_CHECK_SECONDS,
+        glib::clone!(
+            #[weak]
+            window,
+            #[weak]
+            stack,
+            #[upgrade_or]
+            glib::ControlFlow::Break,
+            move || {
+                let config = config.borrow();
+                let limit = config.auto_lock_minutes * 60;
+
+                if config.encryption != StoreEncryption::None
+                    && limit > 0
+                    && !window.is_active()
+                    && !clipboard_monitor.is_store_locked()
+                    && inactive_since.borrow().elapsed().as_secs() >= limit
+                {
+                    clipboard_monitor.lock_store();
+                    stack.set_visible_child_name(LOCKED_PAGE);
+                }
+
+                glib::ControlFlow::Continue
+            }
+        ),
+    );
+}
+
+/// Disables the entry actions while the lock page is shown. Rows cannot be
+/// dragged then either; see `list::setup_drag_source`.
+pub fn setup_locked_actions(window: &adw::ApplicationWindow, stack: &gtk::Stack) {
+    stack.connect_visible_child_name_notify(glib::clone!(
+        #[weak]
+        window,
+        move |stack| {
+            let unlocked = stack.visible_child_name().as_deref() != Some(LOCKED_PAGE);
+            for name in ENTRY_ACTIONS {
+                if let Some(action) = window
+                    .lookup_action(name)
+                    .and_downcast::<gio::SimpleAction>()
+                {
+                    action.set_enabled(unlocked);
+                }
+            }
+        }
+    ));
+}
+
+/// Unlocks the store from the lock page and merges what it holds with the
+/// entries recorded while it was locked.
+pub fn setup_unlock_button(
+    button: &gtk::Button,
+    window: &adw::ApplicationWindow,
+    stack: &gtk::Stack,
+    clipboard_monitor: ClipboardMonitor,
+    config: Rc<RefCell<AppConfig>>,
+    refresh: Rc<dyn Fn()>,
+) {
+    button.connect_clicked(glib::clone!(
+        #[weak]
+        window,
+        #[weak]
+        stack,
+        move |_| {
+            let encryption = config.borrow().encryption;
+
+            glib::MainContext::default().spawn_local(glib::clone!(
+                #[weak]
+                window,
+                #[weak]
+                stack,
+                #[strong]
+                clipboard_monitor,
+                #[strong]
+                refresh,
+                async move {
+                    let store = clipboard_monitor.store().unwrap_or_default();
+                    let protection = request_protection(&window, encryption, store).await;
+                    if matches!(protection, StoreProtection::Locked) {
+                        return;
+                    }
+
+                    clipboard_monitor.set_store_protection(protection);
+                    if let Some(store) = clipboard_monitor.store()
+                        && let Err(err) = clipboard_monitor.load_history(store).await
+                    {
+                        eprintln!("Error loading history: {err}");
+                    }
+
+                    clipboard_monitor.persist();
+                    stack.set_visible_child_name(HISTORY_PAGE);
+                    refresh();
+                }
+            ));
+        }
+    ));
+}